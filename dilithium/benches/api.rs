@@ -2,7 +2,7 @@ use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use pqc_dilithium::*;
 
 fn sign_small_msg(c: &mut Criterion) {
-  let keys = Keypair::generate();
+  let keys = Keypair::generate(DilithiumMode::default());
   let msg = "Hello".as_bytes();
   c.bench_function("Sign Small Message", |b| {
     b.iter(|| keys.sign(black_box(msg)))
@@ -10,11 +10,18 @@ fn sign_small_msg(c: &mut Criterion) {
 }
 
 fn verify_small_msg(c: &mut Criterion) {
-  let keys = Keypair::generate();
+  let keys = Keypair::generate(DilithiumMode::default());
   let msg = "Hello".as_bytes();
   let sig = keys.sign(msg);
   c.bench_function("Verify Small Message", |b| {
-    b.iter(|| verify(black_box(sig), black_box(msg), black_box(&keys.public)))
+    b.iter(|| {
+      verify(
+        keys.mode,
+        black_box(&sig),
+        black_box(msg),
+        black_box(&keys.public),
+      )
+    })
   });
 }
 
@@ -1,20 +1,25 @@
-use crate::params::{PUBLICKEYBYTES, SECRETKEYBYTES, SIGNBYTES};
+use crate::params::{DilithiumMode, PreHash, SigningMode, SEEDBYTES};
 use crate::sign::*;
-use std::convert::TryInto;
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Keypair {
-  pub public: [u8; PUBLICKEYBYTES],
-  secret: [u8; SECRETKEYBYTES],
+  pub mode: DilithiumMode,
+  pub public: Vec<u8>,
+  secret: Vec<u8>,
 }
 
 /// Secret key elided
 impl std::fmt::Debug for Keypair {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "public: {:?}\nsecret: <elided>", self.public)
+    write!(
+      f,
+      "mode: {:?}\npublic: {:?}\nsecret: <elided>",
+      self.mode, self.public
+    )
   }
 }
 
+#[derive(Debug)]
 pub enum SignError {
   Input,
   Verify,
@@ -24,73 +29,227 @@ impl Keypair {
   /// Explicitly expose secret key
   /// ```
   /// # use pqc_dilithium::*;
-  /// let keys = Keypair::generate();
+  /// let keys = Keypair::generate(DilithiumMode::Dilithium3);
   /// let secret_key = keys.expose_secret();
-  /// assert!(secret_key.len() == SECRETKEYBYTES);
+  /// assert!(secret_key.len() == DilithiumMode::Dilithium3.secret_key_bytes());
   /// ```
   pub fn expose_secret(&self) -> &[u8] {
     &self.secret
   }
 
-  /// Generates a keypair for signing and verification
+  /// Generates a keypair for signing and verification with the given
+  /// parameter set
   ///
   /// Example:
   /// ```
   /// # use pqc_dilithium::*;
-  /// let keys = Keypair::generate();
-  /// assert!(keys.public.len() == PUBLICKEYBYTES);
-  /// assert!(keys.expose_secret().len() == SECRETKEYBYTES);
+  /// let mode = DilithiumMode::Dilithium2;
+  /// let keys = Keypair::generate(mode);
+  /// assert!(keys.public.len() == mode.public_key_bytes());
+  /// assert!(keys.expose_secret().len() == mode.secret_key_bytes());
   /// ```
-  pub fn generate() -> Keypair {
-    let mut public = [0u8; PUBLICKEYBYTES];
-    let mut secret = [0u8; SECRETKEYBYTES];
-    crypto_sign_keypair(&mut public, &mut secret, None);
-    Keypair { public, secret }
+  pub fn generate(mode: DilithiumMode) -> Keypair {
+    let mut public = vec![0u8; mode.public_key_bytes()];
+    let mut secret = vec![0u8; mode.secret_key_bytes()];
+    crypto_sign_keypair(mode, &mut public, &mut secret, None);
+    Keypair {
+      mode,
+      public,
+      secret,
+    }
+  }
+
+  /// Derives the keypair of the given parameter set from a seed, the
+  /// xi of FIPS 204. The same seed always gives the same keypair, so
+  /// ML-DSA secret keys can be stored as just their seed
+  ///
+  /// Example:
+  /// ```
+  /// # use pqc_dilithium::*;
+  /// let keys = Keypair::from_seed(DilithiumMode::MlDsa65, &[42u8; SEEDBYTES]);
+  /// assert_eq!(keys, Keypair::from_seed(DilithiumMode::MlDsa65, &[42u8; SEEDBYTES]));
+  /// ```
+  pub fn from_seed(mode: DilithiumMode, seed: &[u8; SEEDBYTES]) -> Keypair {
+    let mut public = vec![0u8; mode.public_key_bytes()];
+    let mut secret = vec![0u8; mode.secret_key_bytes()];
+    crypto_sign_keypair(mode, &mut public, &mut secret, Some(seed));
+    Keypair {
+      mode,
+      public,
+      secret,
+    }
   }
 
-  /// Generates a signature for the given message using a keypair
+  /// Generates a signature for the given message using a keypair,
+  /// hedged with fresh randomness as `SigningMode::default` is
   ///
   /// Example:
   /// ```
   /// # use pqc_dilithium::*;
-  /// # let keys = Keypair::generate();
+  /// # let keys = Keypair::generate(DilithiumMode::Dilithium5);
   /// let msg = "Hello".as_bytes();
   /// let sig = keys.sign(&msg);
-  /// assert!(sig.len() == SIGNBYTES);
-  /// ```  
-  pub fn sign(&self, msg: &[u8]) -> [u8; SIGNBYTES] {
-    let mut sig = [0u8; SIGNBYTES];
-    crypto_sign_signature(&mut sig, msg, &self.secret);
+  /// assert!(sig.len() == DilithiumMode::Dilithium5.signature_bytes());
+  /// ```
+  pub fn sign(&self, msg: &[u8]) -> Vec<u8> {
+    self.sign_with(msg, SigningMode::default())
+  }
+
+  /// Generates a signature for the given message using a keypair, either
+  /// deterministically or hedged with some randomness
+  ///
+  /// Example:
+  /// ```
+  /// # use pqc_dilithium::*;
+  /// # let keys = Keypair::generate(DilithiumMode::Dilithium2);
+  /// let msg = "Hello".as_bytes();
+  /// let sig = keys.sign_with(&msg, SigningMode::Deterministic);
+  /// assert_eq!(sig, keys.sign_with(&msg, SigningMode::Deterministic));
+  /// let sig = keys.sign_with(&msg, SigningMode::Hedged(None));
+  /// assert!(verify(keys.mode, &sig, &msg, &keys.public).is_ok());
+  /// ```
+  pub fn sign_with(&self, msg: &[u8], signing: SigningMode) -> Vec<u8> {
+    // An empty context string fits every mode
+    self.sign_ctx(msg, &[], signing).unwrap()
+  }
+
+  /// Generates an ML-DSA signature for the given message, bound to a
+  /// context string of at most `MAX_CONTEXT_BYTES`. The signature only
+  /// verifies with the same context string. The legacy modes have no
+  /// context strings, so they only take an empty one
+  ///
+  /// Example:
+  /// ```
+  /// # use pqc_dilithium::*;
+  /// # let keys = Keypair::generate(DilithiumMode::MlDsa44);
+  /// let msg = "Hello".as_bytes();
+  /// let sig = keys.sign_ctx(&msg, b"greeting", SigningMode::Hedged(None)).unwrap();
+  /// assert!(verify_ctx(keys.mode, &sig, &msg, b"greeting", &keys.public).is_ok());
+  /// assert!(verify(keys.mode, &sig, &msg, &keys.public).is_err());
+  /// ```
+  pub fn sign_ctx(
+    &self,
+    msg: &[u8],
+    ctx: &[u8],
+    signing: SigningMode,
+  ) -> Result<Vec<u8>, SignError> {
+    let prefix = message_prefix(self.mode, ctx, None)?;
+    Ok(self.sign_prefixed(&prefix, msg, signing))
+  }
+
+  /// Generates a HashML-DSA signature over the digest of a message the
+  /// caller hashed with `prehash`, so messages of any size can be signed
+  /// without holding them in memory. Only the ML-DSA modes can sign
+  /// pre-hashed messages
+  ///
+  /// Example:
+  /// ```
+  /// # use pqc_dilithium::*;
+  /// # let keys = Keypair::generate(DilithiumMode::MlDsa87);
+  /// let digest = [7u8; 64];
+  /// let sig = keys
+  ///   .sign_prehashed(&digest, PreHash::Sha2_512, &[], SigningMode::Deterministic)
+  ///   .unwrap();
+  /// assert!(verify_prehashed(keys.mode, &sig, &digest, PreHash::Sha2_512, &[], &keys.public).is_ok());
+  /// ```
+  pub fn sign_prehashed(
+    &self,
+    digest: &[u8],
+    prehash: PreHash,
+    ctx: &[u8],
+    signing: SigningMode,
+  ) -> Result<Vec<u8>, SignError> {
+    if digest.len() != prehash.digest_bytes() {
+      return Err(SignError::Input);
+    }
+    let prefix = message_prefix(self.mode, ctx, Some(prehash))?;
+    Ok(self.sign_prefixed(&prefix, digest, signing))
+  }
+
+  fn sign_prefixed(
+    &self,
+    prefix: &[u8],
+    msg: &[u8],
+    signing: SigningMode,
+  ) -> Vec<u8> {
+    let mut sig = vec![0u8; self.mode.signature_bytes()];
+    crypto_sign_signature(
+      self.mode,
+      signing,
+      &mut sig,
+      prefix,
+      msg,
+      &self.secret,
+    );
     sig
   }
 
-  /// Restores a `Keypair` from the specified `public` key and
-  /// `secret` key.
-  pub fn restore_from_keys(public: Vec<u8>, secret: Vec<u8>) -> Self {
-    Self {
-      public: public.try_into().unwrap(),
-      secret: secret.try_into().unwrap(),
+  /// Restores a `Keypair` of the given parameter set from the specified
+  /// `public` key and `secret` key. Fails if either doesn't have the
+  /// length the parameter set calls for.
+  pub fn restore_from_keys(
+    mode: DilithiumMode,
+    public: Vec<u8>,
+    secret: Vec<u8>,
+  ) -> Result<Self, SignError> {
+    if public.len() != mode.public_key_bytes()
+      || secret.len() != mode.secret_key_bytes()
+    {
+      return Err(SignError::Input);
     }
+    Ok(Self {
+      mode,
+      public,
+      secret,
+    })
   }
 }
 
-/// Verify signature using keypair
+/// Verify signature using keypair. ML-DSA signatures are checked with an
+/// empty context string
 ///
 /// Example:
 /// ```
 /// # use pqc_dilithium::*;
-/// # let keys = Keypair::generate();
+/// # let keys = Keypair::generate(DilithiumMode::Dilithium3);
 /// # let msg = [0u8; 32];
 /// # let sig = keys.sign(&msg);
-/// let sig_verify = verify(&sig, &msg, &keys.public);
+/// let sig_verify = verify(keys.mode, &sig, &msg, &keys.public);
 /// assert!(sig_verify.is_ok());
 pub fn verify(
+  mode: DilithiumMode,
   sig: &[u8],
   msg: &[u8],
   public_key: &[u8],
 ) -> Result<(), SignError> {
-  if sig.len() != SIGNBYTES {
+  verify_ctx(mode, sig, msg, &[], public_key)
+}
+
+/// Verify an ML-DSA signature bound to the given context string
+pub fn verify_ctx(
+  mode: DilithiumMode,
+  sig: &[u8],
+  msg: &[u8],
+  ctx: &[u8],
+  public_key: &[u8],
+) -> Result<(), SignError> {
+  let prefix = message_prefix(mode, ctx, None)?;
+  crypto_sign_verify(mode, sig, &prefix, msg, public_key)
+}
+
+/// Verify a HashML-DSA signature over the digest of a message hashed
+/// with `prehash`
+pub fn verify_prehashed(
+  mode: DilithiumMode,
+  sig: &[u8],
+  digest: &[u8],
+  prehash: PreHash,
+  ctx: &[u8],
+  public_key: &[u8],
+) -> Result<(), SignError> {
+  if digest.len() != prehash.digest_bytes() {
     return Err(SignError::Input);
   }
-  crypto_sign_verify(&sig, &msg, public_key)
+  let prefix = message_prefix(mode, ctx, Some(prehash))?;
+  crypto_sign_verify(mode, sig, &prefix, digest, public_key)
 }
@@ -1,6 +1,5 @@
 #![allow(clippy::needless_range_loop)]
 
-#[cfg(not(feature = "aes"))]
 pub const SHAKE128_RATE: usize = 168;
 pub const SHAKE256_RATE: usize = 136;
 
@@ -439,13 +438,11 @@ fn keccak_squeezeblocks(
 }
 
 /// Description: Absorb step of the SHAKE128 XOF; incremental.
-#[cfg(not(feature = "aes"))]
 pub fn shake128_absorb(state: &mut KeccakState, input: &[u8], inlen: usize) {
   keccak_absorb(state, SHAKE128_RATE, input, inlen);
 }
 
 /// Finalize absorb step of the SHAKE128 XOF.
-#[cfg(not(feature = "aes"))]
 pub fn shake128_finalize(state: &mut KeccakState) {
   keccak_finalize(&mut state.s, state.pos as usize, SHAKE128_RATE, 0x1F);
   state.pos = SHAKE128_RATE;
@@ -455,7 +452,6 @@ pub fn shake128_finalize(state: &mut KeccakState) {
 ///  SHAKE128_RATE bytes each. Can be called multiple times
 ///  to keep squeezing. Assumes new block has not yet been
 ///  started (state->pos = SHAKE128_RATE).
-#[cfg(not(feature = "aes"))]
 pub fn shake128_squeezeblocks(
   output: &mut [u8],
   nblocks: usize,
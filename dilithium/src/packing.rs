@@ -3,7 +3,7 @@ use crate::{params::*, poly::*, polyvec::*, SignError};
 /// Bit-pack public key pk = (rho, t1).
 pub fn pack_pk(pk: &mut [u8], rho: &[u8], t1: &Polyveck) {
   pk[..SEEDBYTES].copy_from_slice(&rho[..SEEDBYTES]);
-  for i in 0..K {
+  for i in 0..t1.vec.len() {
     polyt1_pack(&mut pk[SEEDBYTES + i * POLYT1_PACKEDBYTES..], &t1.vec[i]);
   }
 }
@@ -11,13 +11,14 @@ pub fn pack_pk(pk: &mut [u8], rho: &[u8], t1: &Polyveck) {
 /// Unpack public key pk = (rho, t1).
 pub fn unpack_pk(rho: &mut [u8], t1: &mut Polyveck, pk: &[u8]) {
   rho[..SEEDBYTES].copy_from_slice(&pk[..SEEDBYTES]);
-  for i in 0..K {
+  for i in 0..t1.vec.len() {
     polyt1_unpack(&mut t1.vec[i], &pk[SEEDBYTES + i * POLYT1_PACKEDBYTES..])
   }
 }
 
 /// Bit-pack secret key sk = (rho, key, tr, s1, s2, t0).
 pub fn pack_sk(
+  mode: DilithiumMode,
   sk: &mut [u8],
   rho: &[u8],
   tr: &[u8],
@@ -26,6 +27,8 @@ pub fn pack_sk(
   s1: &Polyvecl,
   s2: &Polyveck,
 ) {
+  let (k, l) = (mode.k(), mode.l());
+  let polyeta_packedbytes = mode.polyeta_packedbytes();
   let mut idx = 0usize;
 
   sk[idx..SEEDBYTES].copy_from_slice(&rho[0..SEEDBYTES]);
@@ -34,26 +37,28 @@ pub fn pack_sk(
   sk[idx..idx + SEEDBYTES].copy_from_slice(&key[0..SEEDBYTES]);
   idx += SEEDBYTES;
 
-  sk[idx..idx + SEEDBYTES].copy_from_slice(&tr[0..SEEDBYTES]);
-  idx += SEEDBYTES;
+  let trbytes = mode.tr_bytes();
+  sk[idx..idx + trbytes].copy_from_slice(&tr[0..trbytes]);
+  idx += trbytes;
 
-  for i in 0..L {
-    polyeta_pack(&mut sk[idx + i * POLYETA_PACKEDBYTES..], &s1.vec[i]);
+  for i in 0..l {
+    polyeta_pack(mode, &mut sk[idx + i * polyeta_packedbytes..], &s1.vec[i]);
   }
-  idx += L * POLYETA_PACKEDBYTES;
+  idx += l * polyeta_packedbytes;
 
-  for i in 0..K {
-    polyeta_pack(&mut sk[idx + i * POLYETA_PACKEDBYTES..], &s2.vec[i]);
+  for i in 0..k {
+    polyeta_pack(mode, &mut sk[idx + i * polyeta_packedbytes..], &s2.vec[i]);
   }
-  idx += K * POLYETA_PACKEDBYTES;
+  idx += k * polyeta_packedbytes;
 
-  for i in 0..K {
+  for i in 0..k {
     polyt0_pack(&mut sk[idx + i * POLYT0_PACKEDBYTES..], &t0.vec[i]);
   }
 }
 
 /// Unpack secret key sk = (rho, key, tr, s1, s2, t0).
 pub fn unpack_sk(
+  mode: DilithiumMode,
   rho: &mut [u8],
   tr: &mut [u8],
   key: &mut [u8],
@@ -62,6 +67,8 @@ pub fn unpack_sk(
   s2: &mut Polyveck,
   sk: &[u8],
 ) {
+  let (k, l) = (mode.k(), mode.l());
+  let polyeta_packedbytes = mode.polyeta_packedbytes();
   let mut idx = 0usize;
 
   rho[..SEEDBYTES].copy_from_slice(&sk[..SEEDBYTES]);
@@ -70,88 +77,102 @@ pub fn unpack_sk(
   key[..SEEDBYTES].copy_from_slice(&sk[idx..idx + SEEDBYTES]);
   idx += SEEDBYTES;
 
-  tr[..SEEDBYTES].copy_from_slice(&sk[idx..idx + SEEDBYTES]);
-  idx += SEEDBYTES;
+  let trbytes = mode.tr_bytes();
+  tr[..trbytes].copy_from_slice(&sk[idx..idx + trbytes]);
+  idx += trbytes;
 
-  for i in 0..L {
-    polyeta_unpack(&mut s1.vec[i], &sk[idx + i * POLYETA_PACKEDBYTES..]);
+  for i in 0..l {
+    polyeta_unpack(mode, &mut s1.vec[i], &sk[idx + i * polyeta_packedbytes..]);
   }
-  idx += L * POLYETA_PACKEDBYTES;
+  idx += l * polyeta_packedbytes;
 
-  for i in 0..K {
-    polyeta_unpack(&mut s2.vec[i], &sk[idx + i * POLYETA_PACKEDBYTES..]);
+  for i in 0..k {
+    polyeta_unpack(mode, &mut s2.vec[i], &sk[idx + i * polyeta_packedbytes..]);
   }
-  idx += K * POLYETA_PACKEDBYTES;
+  idx += k * polyeta_packedbytes;
 
-  for i in 0..K {
+  for i in 0..k {
     polyt0_unpack(&mut t0.vec[i], &sk[idx + i * POLYT0_PACKEDBYTES..]);
   }
 }
 
 /// Bit-pack signature sig = (c, z, h).
-pub fn pack_sig(sig: &mut [u8], c: Option<&[u8]>, z: &Polyvecl, h: &Polyveck) {
+pub fn pack_sig(
+  mode: DilithiumMode,
+  sig: &mut [u8],
+  c: Option<&[u8]>,
+  z: &Polyvecl,
+  h: &Polyveck,
+) {
+  let (k, l, omega) = (mode.k(), mode.l(), mode.omega());
+  let (polyz_packedbytes, ctildebytes) =
+    (mode.polyz_packedbytes(), mode.ctilde_bytes());
   let mut idx = 0usize;
 
   if let Some(challenge) = c {
-    sig[..SEEDBYTES].copy_from_slice(&challenge[..SEEDBYTES]);
+    sig[..ctildebytes].copy_from_slice(&challenge[..ctildebytes]);
   }
 
-  idx += SEEDBYTES;
+  idx += ctildebytes;
 
-  for i in 0..L {
-    polyz_pack(&mut sig[idx + i * POLYZ_PACKEDBYTES..], &z.vec[i]);
+  for i in 0..l {
+    polyz_pack(mode, &mut sig[idx + i * polyz_packedbytes..], &z.vec[i]);
   }
-  idx += L * POLYZ_PACKEDBYTES;
+  idx += l * polyz_packedbytes;
   // Encode H
-  sig[idx..idx + OMEGA + K].copy_from_slice(&[0u8; OMEGA + K]);
+  sig[idx..idx + omega + k].fill(0);
 
-  let mut k = 0;
-  for i in 0..K {
+  let mut n = 0;
+  for i in 0..k {
     for j in 0..N {
       if h.vec[i].coeffs[j] != 0 {
-        sig[idx + k] = j as u8;
-        k += 1;
+        sig[idx + n] = j as u8;
+        n += 1;
       }
     }
-    sig[idx + OMEGA + i] = k as u8;
+    sig[idx + omega + i] = n as u8;
   }
 }
 
 /// Unpack signature sig = (z, h, c).
 pub fn unpack_sig(
+  mode: DilithiumMode,
   c: &mut [u8],
   z: &mut Polyvecl,
   h: &mut Polyveck,
   sig: &[u8],
 ) -> Result<(), SignError> {
+  let (k, l, omega) = (mode.k(), mode.l(), mode.omega());
+  let (polyz_packedbytes, ctildebytes) =
+    (mode.polyz_packedbytes(), mode.ctilde_bytes());
   let mut idx = 0usize;
 
-  c[..SEEDBYTES].copy_from_slice(&sig[..SEEDBYTES]);
-  idx += SEEDBYTES;
+  c[..ctildebytes].copy_from_slice(&sig[..ctildebytes]);
+  idx += ctildebytes;
 
-  for i in 0..L {
-    polyz_unpack(&mut z.vec[i], &sig[idx + i * POLYZ_PACKEDBYTES..]);
+  for i in 0..l {
+    polyz_unpack(mode, &mut z.vec[i], &sig[idx + i * polyz_packedbytes..]);
   }
-  idx += L * POLYZ_PACKEDBYTES;
+  idx += l * polyz_packedbytes;
 
   // Decode h
-  let mut k = 0usize;
-  for i in 0..K {
-    if sig[idx + OMEGA + i] < k as u8 || sig[idx + OMEGA + i] > OMEGA_U8 {
+  let mut n = 0usize;
+  for i in 0..k {
+    if sig[idx + omega + i] < n as u8 || sig[idx + omega + i] > omega as u8 {
       return Err(SignError::Input);
     }
-    for j in k..sig[idx + OMEGA + i] as usize {
+    for j in n..sig[idx + omega + i] as usize {
       // Coefficients are ordered for strong unforgeability
-      if j > k && sig[idx + j as usize] <= sig[idx + j as usize - 1] {
+      if j > n && sig[idx + j as usize] <= sig[idx + j as usize - 1] {
         return Err(SignError::Input);
       }
       h.vec[i].coeffs[sig[idx + j] as usize] = 1;
     }
-    k = sig[idx + OMEGA + i] as usize;
+    n = sig[idx + omega + i] as usize;
   }
 
   // Extra indices are zero for strong unforgeability
-  for j in k..OMEGA {
+  for j in n..omega {
     if sig[idx + j as usize] > 0 {
       return Err(SignError::Input);
     }
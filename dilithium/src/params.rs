@@ -1,19 +1,8 @@
-#[cfg(feature = "mode2")]
-mod mode_2;
-#[cfg(not(any(feature = "mode2", feature = "mode5")))]
-mod mode_3;
-#[cfg(feature = "mode5")]
-mod mode_5;
-
-#[cfg(feature = "mode2")]
-pub use mode_2::*;
-#[cfg(not(any(feature = "mode2", feature = "mode5")))]
-pub use mode_3::*;
-#[cfg(feature = "mode5")]
-pub use mode_5::*;
-
 pub const SEEDBYTES: usize = 32;
 pub const CRHBYTES: usize = 64;
+/// Length of tr, the hash of the public key, in ML-DSA. The legacy modes
+/// hash it down to `SEEDBYTES`
+pub const TRBYTES: usize = 64;
 pub const N: usize = 256;
 pub const Q: usize = 8380417;
 pub const D: usize = 13;
@@ -21,37 +10,288 @@ pub const ROOT_OF_UNITY: usize = 1753;
 
 pub const POLYT1_PACKEDBYTES: usize = 320;
 pub const POLYT0_PACKEDBYTES: usize = 416;
-pub const POLYVECH_PACKEDBYTES: usize = OMEGA + K;
-
-pub const POLYZ_PACKEDBYTES: usize =
-  if cfg!(feature = "mode2") { 576 } else { 640 };
-pub const POLYW1_PACKEDBYTES: usize =
-  if cfg!(feature = "mode2") { 192 } else { 128 };
-
-pub const POLYETA_PACKEDBYTES: usize =
-  if cfg!(not(any(feature = "mode2", feature = "mode5"))) {
-    128
-  } else {
-    96
-  };
 
 // Concise types to avoid cast cluttering
 pub const Q_I32: i32 = Q as i32;
 pub const N_U32: u32 = N as u32;
-pub const L_U16: u16 = L as u16;
-pub const BETA_I32: i32 = BETA as i32;
-pub const GAMMA1_I32: i32 = GAMMA1 as i32;
-pub const GAMMA2_I32: i32 = GAMMA2 as i32;
-pub const OMEGA_U8: u8 = OMEGA as u8;
-pub const ETA_I32: i32 = ETA as i32;
-pub const GAMMA1_SUB_BETA: i32 = (GAMMA1 - BETA) as i32;
-
-pub const PUBLICKEYBYTES: usize = SEEDBYTES + K * POLYT1_PACKEDBYTES;
-pub const SECRETKEYBYTES: usize = 3 * SEEDBYTES
-  + L * POLYETA_PACKEDBYTES
-  + K * POLYETA_PACKEDBYTES
-  + K * POLYT0_PACKEDBYTES;
-pub const SIGNBYTES: usize =
-  SEEDBYTES + L * POLYZ_PACKEDBYTES + POLYVECH_PACKEDBYTES;
-
-pub const RANDOMIZED_SIGNING: bool = cfg!(feature = "random_signing");
+
+/// Length of the randomness mixed into hedged signatures
+pub const RNDBYTES: usize = 32;
+
+/// Longest context string ML-DSA signatures can be bound to
+pub const MAX_CONTEXT_BYTES: usize = 255;
+
+/// How the randomness behind a signature is derived, picked every time
+/// something is signed.
+///
+/// * Deterministic signatures derive it from the secret key and the
+///   message alone, so the same message always gets the same signature
+/// * Hedged signatures mix fresh randomness in as well, which takes the
+///   edge off fault and side channel attacks that exploit determinism.
+///   Pass `Some` to supply the randomness yourself, or `None` to draw it
+///   from the thread's RNG
+///
+/// The default is hedged, with randomness from the thread's RNG.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SigningMode {
+  Deterministic,
+  Hedged(Option<[u8; RNDBYTES]>),
+}
+
+impl Default for SigningMode {
+  fn default() -> Self {
+    SigningMode::Hedged(None)
+  }
+}
+
+/// Hash functions FIPS 204 allows for pre-hashed (HashML-DSA) signing.
+/// The message is hashed by the caller, and only its digest is signed,
+/// bound to the OID of the hash function.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum PreHash {
+  Sha2_224,
+  Sha2_256,
+  Sha2_384,
+  Sha2_512,
+  Sha2_512_224,
+  Sha2_512_256,
+  Sha3_224,
+  Sha3_256,
+  Sha3_384,
+  Sha3_512,
+  /// SHAKE128 with a 256 bit output
+  Shake128,
+  /// SHAKE256 with a 512 bit output
+  Shake256,
+}
+
+impl PreHash {
+  /// DER encoded OID of the hash function, as signed in front of the
+  /// digest
+  pub const fn oid(self) -> [u8; 11] {
+    let last = match self {
+      PreHash::Sha2_256 => 0x01,
+      PreHash::Sha2_384 => 0x02,
+      PreHash::Sha2_512 => 0x03,
+      PreHash::Sha2_224 => 0x04,
+      PreHash::Sha2_512_224 => 0x05,
+      PreHash::Sha2_512_256 => 0x06,
+      PreHash::Sha3_224 => 0x07,
+      PreHash::Sha3_256 => 0x08,
+      PreHash::Sha3_384 => 0x09,
+      PreHash::Sha3_512 => 0x0a,
+      PreHash::Shake128 => 0x0b,
+      PreHash::Shake256 => 0x0c,
+    };
+    [
+      0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, last,
+    ]
+  }
+
+  /// Size in bytes of the digest
+  pub const fn digest_bytes(self) -> usize {
+    match self {
+      PreHash::Sha2_224 | PreHash::Sha2_512_224 | PreHash::Sha3_224 => 28,
+      PreHash::Sha2_256
+      | PreHash::Sha2_512_256
+      | PreHash::Sha3_256
+      | PreHash::Shake128 => 32,
+      PreHash::Sha2_384 | PreHash::Sha3_384 => 48,
+      PreHash::Sha2_512 | PreHash::Sha3_512 | PreHash::Shake256 => 64,
+    }
+  }
+}
+
+/// The Dilithium parameter set. Unlike upstream, where it's fixed at
+/// compile time through features, it's picked at runtime, so a single
+/// build can sign and verify with any of them.
+///
+/// * Dilithium2 targets NIST security level 2
+/// * Dilithium3 targets NIST security level 3, and is the default
+/// * Dilithium5 targets NIST security level 5
+/// * MlDsa44, MlDsa65 and MlDsa87 are their FIPS 204 counterparts. They
+///   share the lattice parameters, but hash differently, bind signatures
+///   to a context string and can sign pre-hashed messages, so keys and
+///   signatures don't carry over between the two
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum DilithiumMode {
+  Dilithium2,
+  Dilithium3,
+  Dilithium5,
+  MlDsa44,
+  MlDsa65,
+  MlDsa87,
+}
+
+impl Default for DilithiumMode {
+  fn default() -> Self {
+    DilithiumMode::Dilithium3
+  }
+}
+
+impl DilithiumMode {
+  /// Every mode, the legacy ones first, each from the smallest to the
+  /// largest
+  pub const ALL: [DilithiumMode; 6] = [
+    DilithiumMode::Dilithium2,
+    DilithiumMode::Dilithium3,
+    DilithiumMode::Dilithium5,
+    DilithiumMode::MlDsa44,
+    DilithiumMode::MlDsa65,
+    DilithiumMode::MlDsa87,
+  ];
+
+  /// Whether the mode is one of the FIPS 204 ones
+  pub const fn is_ml_dsa(self) -> bool {
+    matches!(
+      self,
+      DilithiumMode::MlDsa44 | DilithiumMode::MlDsa65 | DilithiumMode::MlDsa87
+    )
+  }
+
+  /// The legacy mode with the same lattice parameters, which is the
+  /// mode itself for the legacy ones
+  const fn legacy(self) -> DilithiumMode {
+    match self {
+      DilithiumMode::MlDsa44 => DilithiumMode::Dilithium2,
+      DilithiumMode::MlDsa65 => DilithiumMode::Dilithium3,
+      DilithiumMode::MlDsa87 => DilithiumMode::Dilithium5,
+      legacy => legacy,
+    }
+  }
+
+  /// Whether the matrix and vectors are expanded with AES-256 in counter
+  /// mode instead of SHAKE. ML-DSA only knows SHAKE, so the `aes` feature
+  /// only applies to the legacy modes
+  #[cfg(feature = "aes")]
+  pub(crate) const fn aes(self) -> bool {
+    !self.is_ml_dsa()
+  }
+
+  /// Rows of the matrix A, ie the length of the vectors in the public key
+  pub const fn k(self) -> usize {
+    match self.legacy() {
+      DilithiumMode::Dilithium2 => 4,
+      DilithiumMode::Dilithium3 => 6,
+      _ => 8,
+    }
+  }
+
+  /// Columns of the matrix A, ie the length of the vectors in the signature
+  pub const fn l(self) -> usize {
+    match self.legacy() {
+      DilithiumMode::Dilithium2 => 4,
+      DilithiumMode::Dilithium3 => 5,
+      _ => 7,
+    }
+  }
+
+  pub(crate) const fn eta(self) -> usize {
+    match self.legacy() {
+      DilithiumMode::Dilithium3 => 4,
+      _ => 2,
+    }
+  }
+
+  pub(crate) const fn tau(self) -> usize {
+    match self.legacy() {
+      DilithiumMode::Dilithium2 => 39,
+      DilithiumMode::Dilithium3 => 49,
+      _ => 60,
+    }
+  }
+
+  pub(crate) const fn beta(self) -> usize {
+    match self.legacy() {
+      DilithiumMode::Dilithium2 => 78,
+      DilithiumMode::Dilithium3 => 196,
+      _ => 120,
+    }
+  }
+
+  pub(crate) const fn gamma1(self) -> usize {
+    match self.legacy() {
+      DilithiumMode::Dilithium2 => 1 << 17,
+      _ => 1 << 19,
+    }
+  }
+
+  pub(crate) const fn gamma2(self) -> usize {
+    match self.legacy() {
+      DilithiumMode::Dilithium2 => (Q - 1) / 88,
+      _ => (Q - 1) / 32,
+    }
+  }
+
+  pub(crate) const fn omega(self) -> usize {
+    match self.legacy() {
+      DilithiumMode::Dilithium2 => 80,
+      DilithiumMode::Dilithium3 => 55,
+      _ => 75,
+    }
+  }
+
+  /// Length of tr, the hash of the public key kept in the secret key
+  pub(crate) const fn tr_bytes(self) -> usize {
+    match self.is_ml_dsa() {
+      true => TRBYTES,
+      false => SEEDBYTES,
+    }
+  }
+
+  /// Length of the commitment hash c~ at the start of a signature. FIPS
+  /// 204 grows it with the security level
+  pub(crate) const fn ctilde_bytes(self) -> usize {
+    match self {
+      DilithiumMode::MlDsa65 => 48,
+      DilithiumMode::MlDsa87 => 64,
+      _ => SEEDBYTES,
+    }
+  }
+
+  pub(crate) const fn polyvech_packedbytes(self) -> usize {
+    self.omega() + self.k()
+  }
+
+  pub(crate) const fn polyz_packedbytes(self) -> usize {
+    match self.legacy() {
+      DilithiumMode::Dilithium2 => 576,
+      _ => 640,
+    }
+  }
+
+  pub(crate) const fn polyw1_packedbytes(self) -> usize {
+    match self.legacy() {
+      DilithiumMode::Dilithium2 => 192,
+      _ => 128,
+    }
+  }
+
+  pub(crate) const fn polyeta_packedbytes(self) -> usize {
+    match self.eta() {
+      4 => 128,
+      _ => 96,
+    }
+  }
+
+  /// Size in bytes of the public key
+  pub const fn public_key_bytes(self) -> usize {
+    SEEDBYTES + self.k() * POLYT1_PACKEDBYTES
+  }
+
+  /// Size in bytes of the secret key
+  pub const fn secret_key_bytes(self) -> usize {
+    2 * SEEDBYTES
+      + self.tr_bytes()
+      + self.l() * self.polyeta_packedbytes()
+      + self.k() * self.polyeta_packedbytes()
+      + self.k() * POLYT0_PACKEDBYTES
+  }
+
+  /// Size in bytes of a signature
+  pub const fn signature_bytes(self) -> usize {
+    self.ctilde_bytes()
+      + self.l() * self.polyz_packedbytes()
+      + self.polyvech_packedbytes()
+  }
+}
@@ -1,10 +0,0 @@
-use super::Q;
-
-pub const K: usize = 6;
-pub const L: usize = 5;
-pub const ETA: usize = 4;
-pub const TAU: usize = 49;
-pub const BETA: usize = 196;
-pub const GAMMA1: usize = 1 << 19;
-pub const GAMMA2: usize = (Q - 1) / 32;
-pub const OMEGA: usize = 55;
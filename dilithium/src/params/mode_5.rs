@@ -1,10 +0,0 @@
-use super::Q;
-
-pub const K: usize = 8;
-pub const L: usize = 7;
-pub const ETA: usize = 2;
-pub const TAU: usize = 60;
-pub const BETA: usize = 120;
-pub const GAMMA1: usize = 1 << 19;
-pub const GAMMA2: usize = (Q - 1) / 32;
-pub const OMEGA: usize = 75;
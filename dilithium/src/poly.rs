@@ -93,19 +93,24 @@ pub fn poly_power2round(a1: &mut Poly, a0: &mut Poly) {
 /// with -ALPHA/2 < c0 <= ALPHA/2 except c1 = (Q-1)/ALPHA where we
 /// set c1 = 0 and -ALPHA/2 <= c0 = c mod Q - Q < 0.
 /// Assumes coefficients to be standard representatives.
-pub fn poly_decompose(a1: &mut Poly, a0: &mut Poly) {
+pub fn poly_decompose(mode: DilithiumMode, a1: &mut Poly, a0: &mut Poly) {
   for i in 0..N {
-    a1.coeffs[i] = decompose(&mut a0.coeffs[i], a1.coeffs[i]);
+    a1.coeffs[i] = decompose(mode, &mut a0.coeffs[i], a1.coeffs[i]);
   }
 }
 
 /// Compute hint polynomial. The coefficients of which indicate
 /// whether the low bits of the corresponding coefficient of
 /// the input polynomial overflow into the high bits.
-pub fn poly_make_hint(h: &mut Poly, a0: &Poly, a1: &Poly) -> i32 {
+pub fn poly_make_hint(
+  mode: DilithiumMode,
+  h: &mut Poly,
+  a0: &Poly,
+  a1: &Poly,
+) -> i32 {
   let mut s = 0i32;
   for i in 0..N {
-    h.coeffs[i] = make_hint(a0.coeffs[i], a1.coeffs[i]) as i32;
+    h.coeffs[i] = make_hint(mode, a0.coeffs[i], a1.coeffs[i]) as i32;
     s += h.coeffs[i];
   }
   s
@@ -116,9 +121,9 @@ pub fn poly_make_hint(h: &mut Poly, a0: &Poly, a1: &Poly) -> i32 {
 /// Arguments:   - poly *b: pointer to output polynomial with corrected high bits
 /// - const poly *a: pointer to input polynomial
 /// - const poly *h: pointer to input hint polynomial
-pub fn poly_use_hint(b: &mut Poly, h: &Poly) {
+pub fn poly_use_hint(mode: DilithiumMode, b: &mut Poly, h: &Poly) {
   for i in 0..N {
-    b.coeffs[i] = use_hint(b.coeffs[i], h.coeffs[i] as u8);
+    b.coeffs[i] = use_hint(mode, b.coeffs[i], h.coeffs[i] as u8);
   }
 }
 
@@ -170,19 +175,22 @@ pub fn rej_uniform(a: &mut [i32], len: u32, buf: &[u8], buflen: usize) -> u32 {
   ctr as u32
 }
 
-const POLY_UNIFORM_NBLOCKS: usize =
-  (768 + STREAM128_BLOCKBYTES - 1) / STREAM128_BLOCKBYTES;
-
 /// Sample polynomial with uniformly random coefficients
 /// in [0, Q-1] by performing rejection sampling using the
-/// output stream of SHAKE256(seed|nonce) or AES256CTR(seed,nonce).
-pub fn poly_uniform(a: &mut Poly, seed: &[u8], nonce: u16) {
-  let mut buflen = POLY_UNIFORM_NBLOCKS * STREAM128_BLOCKBYTES;
-  let mut buf = [0u8; POLY_UNIFORM_NBLOCKS * STREAM128_BLOCKBYTES + 2];
-  let mut state = Stream128State::default();
-
-  stream128_init(&mut state, seed, nonce);
-  stream128_squeezeblocks(&mut buf, POLY_UNIFORM_NBLOCKS as u64, &mut state);
+/// output stream of SHAKE128(seed|nonce) or AES256CTR(seed,nonce).
+pub fn poly_uniform(
+  mode: DilithiumMode,
+  a: &mut Poly,
+  seed: &[u8],
+  nonce: u16,
+) {
+  let mut state = stream128_init(mode, seed, nonce);
+  let blockbytes = state.blockbytes();
+  let nblocks = (768 + blockbytes - 1) / blockbytes;
+  let mut buflen = nblocks * blockbytes;
+  let mut buf = vec![0u8; buflen + 2];
+
+  stream_squeezeblocks(&mut buf, nblocks as u64, &mut state);
 
   let mut ctr = rej_uniform(&mut a.coeffs, N_U32, &mut buf, buflen);
   let mut off;
@@ -191,8 +199,8 @@ pub fn poly_uniform(a: &mut Poly, seed: &[u8], nonce: u16) {
     for i in 0..off {
       buf[i] = buf[buflen - off + i];
     }
-    buflen = STREAM128_BLOCKBYTES + off;
-    stream128_squeezeblocks(&mut buf[off..], 1, &mut state);
+    buflen = blockbytes + off;
+    stream_squeezeblocks(&mut buf[off..], 1, &mut state);
     ctr += rej_uniform(
       &mut a.coeffs[(ctr as usize)..],
       N_U32 - ctr,
@@ -204,7 +212,14 @@ pub fn poly_uniform(a: &mut Poly, seed: &[u8], nonce: u16) {
 
 /// Sample uniformly random coefficients in [-ETA, ETA] by
 /// performing rejection sampling using array of random bytes.
-pub fn rej_eta(a: &mut [i32], len: usize, buf: &[u8], buflen: usize) -> u32 {
+pub fn rej_eta(
+  mode: DilithiumMode,
+  a: &mut [i32],
+  len: usize,
+  buf: &[u8],
+  buflen: usize,
+) -> u32 {
+  let eta = mode.eta();
   let (mut ctr, mut pos) = (0usize, 0usize);
   let (mut t0, mut t1);
   while ctr < len && pos < buflen {
@@ -212,7 +227,7 @@ pub fn rej_eta(a: &mut [i32], len: usize, buf: &[u8], buflen: usize) -> u32 {
     t1 = (buf[pos] >> 4) as u32;
     pos += 1;
 
-    if ETA == 2 {
+    if eta == 2 {
       if t0 < 15 {
         t0 = t0 - (205 * t0 >> 10) * 5;
         a[ctr] = 2 - t0 as i32;
@@ -223,7 +238,7 @@ pub fn rej_eta(a: &mut [i32], len: usize, buf: &[u8], buflen: usize) -> u32 {
         a[ctr] = 2 - t1 as i32;
         ctr += 1;
       }
-    } else if ETA == 4 {
+    } else if eta == 4 {
       if t0 < 9 {
         a[ctr] = 4 - t0 as i32;
         ctr += 1;
@@ -237,68 +252,82 @@ pub fn rej_eta(a: &mut [i32], len: usize, buf: &[u8], buflen: usize) -> u32 {
   ctr as u32
 }
 
+const fn poly_uniform_eta_nblocks(
+  mode: DilithiumMode,
+  blockbytes: usize,
+) -> usize {
+  if mode.eta() == 2 {
+    (136 + blockbytes - 1) / blockbytes
+  } else {
+    (227 + blockbytes - 1) / blockbytes
+  }
+}
+
 /// Sample polynomial with uniformly random coefficients
 ///  in [-ETA,ETA] by performing rejection sampling using the
 ///  output stream from SHAKE256(seed|nonce) or AES256CTR(seed,nonce).
-const POLY_UNIFORM_ETA_NBLOCKS: usize = if ETA == 2 {
-  (136 + STREAM256_BLOCKBYTES - 1) / STREAM256_BLOCKBYTES
-} else {
-  (227 + STREAM256_BLOCKBYTES - 1) / STREAM256_BLOCKBYTES
-};
-
-pub fn poly_uniform_eta(a: &mut Poly, seed: &[u8], nonce: u16) {
-  let buflen = POLY_UNIFORM_ETA_NBLOCKS * STREAM256_BLOCKBYTES;
-  let mut buf = [0u8; POLY_UNIFORM_ETA_NBLOCKS * STREAM256_BLOCKBYTES];
-  let mut state = Stream256State::default();
-  stream256_init(&mut state, seed, nonce);
-  stream256_squeezeblocks(
-    &mut buf,
-    POLY_UNIFORM_ETA_NBLOCKS as u64,
-    &mut state,
-  );
-
-  let mut ctr = rej_eta(&mut a.coeffs, N, &buf, buflen);
+pub fn poly_uniform_eta(
+  mode: DilithiumMode,
+  a: &mut Poly,
+  seed: &[u8],
+  nonce: u16,
+) {
+  let mut state = stream256_init(mode, seed, nonce);
+  let blockbytes = state.blockbytes();
+  let nblocks = poly_uniform_eta_nblocks(mode, blockbytes);
+  let buflen = nblocks * blockbytes;
+  let mut buf = vec![0u8; buflen];
+  stream_squeezeblocks(&mut buf, nblocks as u64, &mut state);
+
+  let mut ctr = rej_eta(mode, &mut a.coeffs, N, &buf, buflen);
 
   while ctr < N_U32 {
-    stream256_squeezeblocks(&mut buf, 1, &mut state);
+    stream_squeezeblocks(&mut buf, 1, &mut state);
     ctr += rej_eta(
+      mode,
       &mut a.coeffs[ctr as usize..],
       N - ctr as usize,
       &buf,
-      STREAM256_BLOCKBYTES,
+      blockbytes,
     );
   }
 }
 
-const POLY_UNIFORM_GAMMA1_NBLOCKS: usize =
-  (POLYZ_PACKEDBYTES + STREAM256_BLOCKBYTES - 1) / STREAM256_BLOCKBYTES;
+const fn poly_uniform_gamma1_nblocks(
+  mode: DilithiumMode,
+  blockbytes: usize,
+) -> usize {
+  (mode.polyz_packedbytes() + blockbytes - 1) / blockbytes
+}
 
 /// Sample polynomial with uniformly random coefficients
 /// in [-(GAMMA1 - 1), GAMMA1 - 1] by performing rejection
 /// sampling on output stream of SHAKE256(seed|nonce)
 /// or AES256CTR(seed,nonce).
-pub fn poly_uniform_gamma1(a: &mut Poly, seed: &[u8], nonce: u16) {
-  let mut buf = [0u8; POLY_UNIFORM_GAMMA1_NBLOCKS * STREAM256_BLOCKBYTES];
-  let mut state = Stream256State::default();
-
-  stream256_init(&mut state, seed, nonce);
-  stream256_squeezeblocks(
-    &mut buf,
-    POLY_UNIFORM_GAMMA1_NBLOCKS as u64,
-    &mut state,
-  );
-  polyz_unpack(a, &mut buf);
+pub fn poly_uniform_gamma1(
+  mode: DilithiumMode,
+  a: &mut Poly,
+  seed: &[u8],
+  nonce: u16,
+) {
+  let mut state = stream256_init(mode, seed, nonce);
+  let blockbytes = state.blockbytes();
+  let nblocks = poly_uniform_gamma1_nblocks(mode, blockbytes);
+  let mut buf = vec![0u8; nblocks * blockbytes];
+
+  stream_squeezeblocks(&mut buf, nblocks as u64, &mut state);
+  polyz_unpack(mode, a, &buf);
 }
 
 /// Implementation of H. Samples polynomial with TAU nonzero
 /// coefficients in {-1,1} using the output stream of
-/// SHAKE256(seed).
-pub fn poly_challenge(c: &mut Poly, seed: &[u8]) {
+/// SHAKE256(seed), where the seed is the whole commitment hash c~.
+pub fn poly_challenge(mode: DilithiumMode, c: &mut Poly, seed: &[u8]) {
   let mut _signs = 0u64;
   let mut buf = [0u8; SHAKE256_RATE];
   let mut state = KeccakState::default(); //shake256_init
 
-  shake256_absorb(&mut state, seed, SEEDBYTES);
+  shake256_absorb(&mut state, seed, mode.ctilde_bytes());
   shake256_finalize(&mut state);
   shake256_squeezeblocks(&mut buf, 1, &mut state);
 
@@ -309,7 +338,7 @@ pub fn poly_challenge(c: &mut Poly, seed: &[u8]) {
   // let mut b = buf[pos];
   let mut b;
   c.coeffs.fill(0);
-  for i in N - TAU..N {
+  for i in N - mode.tau()..N {
     loop {
       if pos >= SHAKE256_RATE {
         shake256_squeezeblocks(&mut buf, 1, &mut state);
@@ -329,18 +358,19 @@ pub fn poly_challenge(c: &mut Poly, seed: &[u8]) {
 
 /// Bit-pack polynomial with coefficients in [-ETA,ETA].
 /// Input coefficients are assumed to lie in [Q-ETA,Q+ETA].
-pub fn polyeta_pack(r: &mut [u8], a: &Poly) {
+pub fn polyeta_pack(mode: DilithiumMode, r: &mut [u8], a: &Poly) {
+  let eta = mode.eta() as i32;
   let mut t = [0u8; 8];
-  if ETA == 2 {
+  if eta == 2 {
     for i in 0..N / 8 {
-      t[0] = (ETA_I32 - a.coeffs[8 * i + 0]) as u8;
-      t[1] = (ETA_I32 - a.coeffs[8 * i + 1]) as u8;
-      t[2] = (ETA_I32 - a.coeffs[8 * i + 2]) as u8;
-      t[3] = (ETA_I32 - a.coeffs[8 * i + 3]) as u8;
-      t[4] = (ETA_I32 - a.coeffs[8 * i + 4]) as u8;
-      t[5] = (ETA_I32 - a.coeffs[8 * i + 5]) as u8;
-      t[6] = (ETA_I32 - a.coeffs[8 * i + 6]) as u8;
-      t[7] = (ETA_I32 - a.coeffs[8 * i + 7]) as u8;
+      t[0] = (eta - a.coeffs[8 * i + 0]) as u8;
+      t[1] = (eta - a.coeffs[8 * i + 1]) as u8;
+      t[2] = (eta - a.coeffs[8 * i + 2]) as u8;
+      t[3] = (eta - a.coeffs[8 * i + 3]) as u8;
+      t[4] = (eta - a.coeffs[8 * i + 4]) as u8;
+      t[5] = (eta - a.coeffs[8 * i + 5]) as u8;
+      t[6] = (eta - a.coeffs[8 * i + 6]) as u8;
+      t[7] = (eta - a.coeffs[8 * i + 7]) as u8;
 
       r[3 * i + 0] = (t[0] >> 0) | (t[1] << 3) | (t[2] << 6);
       r[3 * i + 1] = (t[2] >> 2) | (t[3] << 1) | (t[4] << 4) | (t[5] << 7);
@@ -348,16 +378,17 @@ pub fn polyeta_pack(r: &mut [u8], a: &Poly) {
     }
   } else {
     for i in 0..N / 2 {
-      t[0] = (ETA_I32 - a.coeffs[2 * i + 0]) as u8;
-      t[1] = (ETA_I32 - a.coeffs[2 * i + 1]) as u8;
+      t[0] = (eta - a.coeffs[2 * i + 0]) as u8;
+      t[1] = (eta - a.coeffs[2 * i + 1]) as u8;
       r[i] = t[0] | (t[1] << 4);
     }
   }
 }
 
 /// Unpack polynomial with coefficients in [-ETA,ETA].
-pub fn polyeta_unpack(r: &mut Poly, a: &[u8]) {
-  if ETA == 2 {
+pub fn polyeta_unpack(mode: DilithiumMode, r: &mut Poly, a: &[u8]) {
+  let eta = mode.eta() as i32;
+  if eta == 2 {
     for i in 0..N / 8 {
       r.coeffs[8 * i + 0] = (a[3 * i + 0] & 0x07) as i32;
       r.coeffs[8 * i + 1] = ((a[3 * i + 0] >> 3) & 0x07) as i32;
@@ -370,21 +401,21 @@ pub fn polyeta_unpack(r: &mut Poly, a: &[u8]) {
       r.coeffs[8 * i + 6] = ((a[3 * i + 2] >> 2) & 0x07) as i32;
       r.coeffs[8 * i + 7] = ((a[3 * i + 2] >> 5) & 0x07) as i32;
 
-      r.coeffs[8 * i + 0] = (ETA_I32 - r.coeffs[8 * i + 0]) as i32;
-      r.coeffs[8 * i + 1] = (ETA_I32 - r.coeffs[8 * i + 1]) as i32;
-      r.coeffs[8 * i + 2] = (ETA_I32 - r.coeffs[8 * i + 2]) as i32;
-      r.coeffs[8 * i + 3] = (ETA_I32 - r.coeffs[8 * i + 3]) as i32;
-      r.coeffs[8 * i + 4] = (ETA_I32 - r.coeffs[8 * i + 4]) as i32;
-      r.coeffs[8 * i + 5] = (ETA_I32 - r.coeffs[8 * i + 5]) as i32;
-      r.coeffs[8 * i + 6] = (ETA_I32 - r.coeffs[8 * i + 6]) as i32;
-      r.coeffs[8 * i + 7] = (ETA_I32 - r.coeffs[8 * i + 7]) as i32;
+      r.coeffs[8 * i + 0] = (eta - r.coeffs[8 * i + 0]) as i32;
+      r.coeffs[8 * i + 1] = (eta - r.coeffs[8 * i + 1]) as i32;
+      r.coeffs[8 * i + 2] = (eta - r.coeffs[8 * i + 2]) as i32;
+      r.coeffs[8 * i + 3] = (eta - r.coeffs[8 * i + 3]) as i32;
+      r.coeffs[8 * i + 4] = (eta - r.coeffs[8 * i + 4]) as i32;
+      r.coeffs[8 * i + 5] = (eta - r.coeffs[8 * i + 5]) as i32;
+      r.coeffs[8 * i + 6] = (eta - r.coeffs[8 * i + 6]) as i32;
+      r.coeffs[8 * i + 7] = (eta - r.coeffs[8 * i + 7]) as i32;
     }
   } else {
     for i in 0..N / 2 {
       r.coeffs[2 * i + 0] = (a[i] & 0x0F) as i32;
       r.coeffs[2 * i + 1] = (a[i] >> 4) as i32;
-      r.coeffs[2 * i + 0] = (ETA_I32 - r.coeffs[2 * i + 0]) as i32;
-      r.coeffs[2 * i + 1] = (ETA_I32 - r.coeffs[2 * i + 1]) as i32;
+      r.coeffs[2 * i + 0] = (eta - r.coeffs[2 * i + 0]) as i32;
+      r.coeffs[2 * i + 1] = (eta - r.coeffs[2 * i + 1]) as i32;
     }
   }
 }
@@ -514,14 +545,15 @@ pub fn polyt0_unpack(r: &mut Poly, a: &[u8]) {
 /// Bit-pack polynomial z with coefficients
 /// in [-(GAMMA1 - 1), GAMMA1 - 1].
 /// Input coefficients are assumed to be standard representatives.*
-pub fn polyz_pack(r: &mut [u8], a: &Poly) {
+pub fn polyz_pack(mode: DilithiumMode, r: &mut [u8], a: &Poly) {
+  let gamma1 = mode.gamma1() as i32;
   let mut t = [0i32; 4];
-  if GAMMA1 == (1 << 17) {
+  if gamma1 == (1 << 17) {
     for i in 0..N / 4 {
-      t[0] = GAMMA1_I32 - a.coeffs[4 * i + 0];
-      t[1] = GAMMA1_I32 - a.coeffs[4 * i + 1];
-      t[2] = GAMMA1_I32 - a.coeffs[4 * i + 2];
-      t[3] = GAMMA1_I32 - a.coeffs[4 * i + 3];
+      t[0] = gamma1 - a.coeffs[4 * i + 0];
+      t[1] = gamma1 - a.coeffs[4 * i + 1];
+      t[2] = gamma1 - a.coeffs[4 * i + 2];
+      t[3] = gamma1 - a.coeffs[4 * i + 3];
 
       r[9 * i + 0] = (t[0]) as u8;
       r[9 * i + 1] = (t[0] >> 8) as u8;
@@ -536,10 +568,10 @@ pub fn polyz_pack(r: &mut [u8], a: &Poly) {
       r[9 * i + 7] = (t[3] >> 2) as u8;
       r[9 * i + 8] = (t[3] >> 10) as u8;
     }
-  } else if GAMMA1 == 1 << 19 {
+  } else if gamma1 == 1 << 19 {
     for i in 0..N / 2 {
-      t[0] = GAMMA1_I32 - a.coeffs[2 * i + 0];
-      t[1] = GAMMA1_I32 - a.coeffs[2 * i + 1];
+      t[0] = gamma1 - a.coeffs[2 * i + 0];
+      t[1] = gamma1 - a.coeffs[2 * i + 1];
 
       r[5 * i + 0] = (t[0]) as u8;
       r[5 * i + 1] = (t[0] >> 8) as u8;
@@ -554,8 +586,9 @@ pub fn polyz_pack(r: &mut [u8], a: &Poly) {
 /// Unpack polynomial z with coefficients
 /// in [-(GAMMA1 - 1), GAMMA1 - 1].
 /// Output coefficients are standard representatives.
-pub fn polyz_unpack(r: &mut Poly, a: &[u8]) {
-  if GAMMA1 == (1 << 17) {
+pub fn polyz_unpack(mode: DilithiumMode, r: &mut Poly, a: &[u8]) {
+  let gamma1 = mode.gamma1() as i32;
+  if gamma1 == (1 << 17) {
     for i in 0..N / 4 {
       r.coeffs[4 * i + 0] = a[9 * i + 0] as i32;
       r.coeffs[4 * i + 0] |= (a[9 * i + 1] as i32) << 8;
@@ -577,12 +610,12 @@ pub fn polyz_unpack(r: &mut Poly, a: &[u8]) {
       r.coeffs[4 * i + 3] |= (a[9 * i + 8] as i32) << 10;
       r.coeffs[4 * i + 3] &= 0x3FFFF; // TODO: Unnecessary mask?
 
-      r.coeffs[4 * i + 0] = GAMMA1_I32 - r.coeffs[4 * i + 0];
-      r.coeffs[4 * i + 1] = GAMMA1_I32 - r.coeffs[4 * i + 1];
-      r.coeffs[4 * i + 2] = GAMMA1_I32 - r.coeffs[4 * i + 2];
-      r.coeffs[4 * i + 3] = GAMMA1_I32 - r.coeffs[4 * i + 3];
+      r.coeffs[4 * i + 0] = gamma1 - r.coeffs[4 * i + 0];
+      r.coeffs[4 * i + 1] = gamma1 - r.coeffs[4 * i + 1];
+      r.coeffs[4 * i + 2] = gamma1 - r.coeffs[4 * i + 2];
+      r.coeffs[4 * i + 3] = gamma1 - r.coeffs[4 * i + 3];
     }
-  } else if GAMMA1 == 1 << 19 {
+  } else if gamma1 == 1 << 19 {
     for i in 0..N / 2 {
       r.coeffs[2 * i + 0] = a[5 * i + 0] as i32;
       r.coeffs[2 * i + 0] |= (a[5 * i + 1] as i32) << 8;
@@ -594,16 +627,16 @@ pub fn polyz_unpack(r: &mut Poly, a: &[u8]) {
       r.coeffs[2 * i + 1] |= (a[5 * i + 4] as i32) << 12;
       r.coeffs[2 * i + 0] &= 0xFFFFF; // TODO: Unnecessary mask?
 
-      r.coeffs[2 * i + 0] = GAMMA1_I32 - r.coeffs[2 * i + 0];
-      r.coeffs[2 * i + 1] = GAMMA1_I32 - r.coeffs[2 * i + 1];
+      r.coeffs[2 * i + 0] = gamma1 - r.coeffs[2 * i + 0];
+      r.coeffs[2 * i + 1] = gamma1 - r.coeffs[2 * i + 1];
     }
   }
 }
 
 /// Bit-pack polynomial w1 with coefficients in [0, 15].
 /// Input coefficients are assumed to be standard representatives.
-pub fn polyw1_pack(r: &mut [u8], a: &Poly) {
-  if GAMMA2 == (Q - 1) / 88 {
+pub fn polyw1_pack(mode: DilithiumMode, r: &mut [u8], a: &Poly) {
+  if mode.gamma2() == (Q - 1) / 88 {
     for i in 0..N / 4 {
       r[3 * i + 0] = a.coeffs[4 * i + 0] as u8;
       r[3 * i + 0] |= (a.coeffs[4 * i + 1] << 6) as u8;
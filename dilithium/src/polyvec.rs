@@ -1,40 +1,49 @@
 use crate::params::*;
 use crate::poly::*;
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct Polyveck {
-  pub vec: [Poly; K],
+  pub vec: Vec<Poly>,
 }
 
-impl Default for Polyveck {
-  fn default() -> Self {
+impl Polyveck {
+  pub fn new(mode: DilithiumMode) -> Self {
     Polyveck {
-      vec: [Poly::default(); K],
+      vec: vec![Poly::default(); mode.k()],
     }
   }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct Polyvecl {
-  pub vec: [Poly; L],
+  pub vec: Vec<Poly>,
 }
 
-impl Default for Polyvecl {
-  fn default() -> Self {
+impl Polyvecl {
+  pub fn new(mode: DilithiumMode) -> Self {
     Polyvecl {
-      vec: [Poly::default(); L],
+      vec: vec![Poly::default(); mode.l()],
     }
   }
 }
 
+/// The K x L matrix A, one vector of length L per row
+pub fn polyvec_matrix_new(mode: DilithiumMode) -> Vec<Polyvecl> {
+  vec![Polyvecl::new(mode); mode.k()]
+}
+
 /// Implementation of ExpandA. Generates matrix A with uniformly
 /// random coefficients a_{i,j} by performing rejection
 /// sampling on the output stream of SHAKE128(rho|j|i)
 /// or AES256CTR(rho,j|i).
-pub fn polyvec_matrix_expand(mat: &mut [Polyvecl], rho: &[u8]) {
-  for i in 0..K {
-    for j in 0..L {
-      poly_uniform(&mut mat[i].vec[j], rho, ((i << 8) + j) as u16);
+pub fn polyvec_matrix_expand(
+  mode: DilithiumMode,
+  mat: &mut [Polyvecl],
+  rho: &[u8],
+) {
+  for i in 0..mat.len() {
+    for j in 0..mat[i].vec.len() {
+      poly_uniform(mode, &mut mat[i].vec[j], rho, ((i << 8) + j) as u16);
     }
   }
 }
@@ -44,27 +53,38 @@ pub fn polyvec_matrix_pointwise_montgomery(
   mat: &[Polyvecl],
   v: &Polyvecl,
 ) {
-  for i in 0..K {
+  for i in 0..t.vec.len() {
     polyvecl_pointwise_acc_montgomery(&mut t.vec[i], &mat[i], v);
   }
 }
 
 //*********** Vectors of polynomials of length L ****************************
 
-pub fn polyvecl_uniform_eta(v: &mut Polyvecl, seed: &[u8], mut nonce: u16) {
-  for i in 0..L {
-    poly_uniform_eta(&mut v.vec[i], seed, nonce);
+pub fn polyvecl_uniform_eta(
+  mode: DilithiumMode,
+  v: &mut Polyvecl,
+  seed: &[u8],
+  mut nonce: u16,
+) {
+  for i in 0..v.vec.len() {
+    poly_uniform_eta(mode, &mut v.vec[i], seed, nonce);
     nonce += 1;
   }
 }
 
-pub fn polyvecl_uniform_gamma1(v: &mut Polyvecl, seed: &[u8], nonce: u16) {
-  for i in 0..L {
-    poly_uniform_gamma1(&mut v.vec[i], seed, L_U16 * nonce + i as u16);
+pub fn polyvecl_uniform_gamma1(
+  mode: DilithiumMode,
+  v: &mut Polyvecl,
+  seed: &[u8],
+  nonce: u16,
+) {
+  let l = v.vec.len() as u16;
+  for i in 0..v.vec.len() {
+    poly_uniform_gamma1(mode, &mut v.vec[i], seed, l * nonce + i as u16);
   }
 }
 pub fn polyvecl_reduce(v: &mut Polyvecl) {
-  for i in 0..L {
+  for i in 0..v.vec.len() {
     poly_reduce(&mut v.vec[i]);
   }
 }
@@ -72,7 +92,7 @@ pub fn polyvecl_reduce(v: &mut Polyvecl) {
 /// Add vectors of polynomials of length L.
 /// No modular reduction is performed.
 pub fn polyvecl_add(w: &mut Polyvecl, v: &Polyvecl) {
-  for i in 0..L {
+  for i in 0..w.vec.len() {
     poly_add(&mut w.vec[i], &v.vec[i]);
   }
 }
@@ -80,13 +100,13 @@ pub fn polyvecl_add(w: &mut Polyvecl, v: &Polyvecl) {
 /// Forward NTT of all polynomials in vector of length L. Output
 /// coefficients can be up to 16*Q larger than input coefficients.*
 pub fn polyvecl_ntt(v: &mut Polyvecl) {
-  for i in 0..L {
+  for i in 0..v.vec.len() {
     poly_ntt(&mut v.vec[i]);
   }
 }
 
 pub fn polyvecl_invntt_tomont(v: &mut Polyvecl) {
-  for i in 0..L {
+  for i in 0..v.vec.len() {
     poly_invntt_tomont(&mut v.vec[i]);
   }
 }
@@ -96,7 +116,7 @@ pub fn polyvecl_pointwise_poly_montgomery(
   a: &Poly,
   v: &Polyvecl,
 ) {
-  for i in 0..L {
+  for i in 0..r.vec.len() {
     poly_pointwise_montgomery(&mut r.vec[i], a, &v.vec[i]);
   }
 }
@@ -113,7 +133,7 @@ pub fn polyvecl_pointwise_acc_montgomery(
 ) {
   let mut t = Poly::default();
   poly_pointwise_montgomery(w, &u.vec[0], &v.vec[0]);
-  for i in 1..L {
+  for i in 1..u.vec.len() {
     poly_pointwise_montgomery(&mut t, &u.vec[i], &v.vec[i]);
     poly_add(w, &t);
   }
@@ -124,7 +144,7 @@ pub fn polyvecl_pointwise_acc_montgomery(
 /// Returns 0 if norm of all polynomials is strictly smaller than B and 1
 /// otherwise.
 pub fn polyvecl_chknorm(v: &Polyvecl, bound: i32) -> u8 {
-  for i in 0..L {
+  for i in 0..v.vec.len() {
     if poly_chknorm(&v.vec[i], bound) > 0 {
       return 1;
     }
@@ -134,9 +154,14 @@ pub fn polyvecl_chknorm(v: &Polyvecl, bound: i32) -> u8 {
 
 //*********** Vectors of polynomials of length K ****************************
 
-pub fn polyveck_uniform_eta(v: &mut Polyveck, seed: &[u8], mut nonce: u16) {
-  for i in 0..K {
-    poly_uniform_eta(&mut v.vec[i], seed, nonce);
+pub fn polyveck_uniform_eta(
+  mode: DilithiumMode,
+  v: &mut Polyveck,
+  seed: &[u8],
+  mut nonce: u16,
+) {
+  for i in 0..v.vec.len() {
+    poly_uniform_eta(mode, &mut v.vec[i], seed, nonce);
     nonce += 1
   }
 }
@@ -144,7 +169,7 @@ pub fn polyveck_uniform_eta(v: &mut Polyveck, seed: &[u8], mut nonce: u16) {
 /// Reduce coefficients of polynomials in vector of length K
 /// to representatives in [0,2*Q].
 pub fn polyveck_reduce(v: &mut Polyveck) {
-  for i in 0..K {
+  for i in 0..v.vec.len() {
     poly_reduce(&mut v.vec[i]);
   }
 }
@@ -152,7 +177,7 @@ pub fn polyveck_reduce(v: &mut Polyveck) {
 /// For all coefficients of polynomials in vector of length K
 /// add Q if coefficient is negative.
 pub fn polyveck_caddq(v: &mut Polyveck) {
-  for i in 0..K {
+  for i in 0..v.vec.len() {
     poly_caddq(&mut v.vec[i]);
   }
 }
@@ -160,7 +185,7 @@ pub fn polyveck_caddq(v: &mut Polyveck) {
 /// Add vectors of polynomials of length K.
 /// No modular reduction is performed.
 pub fn polyveck_add(w: &mut Polyveck, v: &Polyveck) {
-  for i in 0..K {
+  for i in 0..w.vec.len() {
     poly_add(&mut w.vec[i], &v.vec[i]);
   }
 }
@@ -169,7 +194,7 @@ pub fn polyveck_add(w: &mut Polyveck, v: &Polyveck) {
 /// Assumes coefficients of polynomials in second input vector
 /// to be less than 2*Q. No modular reduction is performed.
 pub fn polyveck_sub(w: &mut Polyveck, v: &Polyveck) {
-  for i in 0..K {
+  for i in 0..w.vec.len() {
     poly_sub(&mut w.vec[i], &v.vec[i]);
   }
 }
@@ -177,7 +202,7 @@ pub fn polyveck_sub(w: &mut Polyveck, v: &Polyveck) {
 /// Multiply vector of polynomials of Length K by 2^D without modular
 /// reduction. Assumes input coefficients to be less than 2^{32-D}.
 pub fn polyveck_shiftl(v: &mut Polyveck) {
-  for i in 0..K {
+  for i in 0..v.vec.len() {
     poly_shiftl(&mut v.vec[i]);
   }
 }
@@ -185,7 +210,7 @@ pub fn polyveck_shiftl(v: &mut Polyveck) {
 /// Forward NTT of all polynomials in vector of length K. Output
 /// coefficients can be up to 16*Q larger than input coefficients.
 pub fn polyveck_ntt(v: &mut Polyveck) {
-  for i in 0..K {
+  for i in 0..v.vec.len() {
     poly_ntt(&mut v.vec[i]);
   }
 }
@@ -194,7 +219,7 @@ pub fn polyveck_ntt(v: &mut Polyveck) {
 /// in vector of length K. Input coefficients need to be less
 /// than 2*Q.
 pub fn polyveck_invntt_tomont(v: &mut Polyveck) {
-  for i in 0..K {
+  for i in 0..v.vec.len() {
     poly_invntt_tomont(&mut v.vec[i]);
   }
 }
@@ -204,7 +229,7 @@ pub fn polyveck_pointwise_poly_montgomery(
   a: &Poly,
   v: &Polyveck,
 ) {
-  for i in 0..K {
+  for i in 0..r.vec.len() {
     poly_pointwise_montgomery(&mut r.vec[i], a, &v.vec[i]);
   }
 }
@@ -215,7 +240,7 @@ pub fn polyveck_pointwise_poly_montgomery(
 /// Returns 0 if norm of all polynomials are strictly smaller than B and 1
 /// otherwise.
 pub fn polyveck_chknorm(v: &Polyveck, bound: i32) -> u8 {
-  for i in 0..K {
+  for i in 0..v.vec.len() {
     if poly_chknorm(&v.vec[i], bound) > 0 {
       return 1;
     }
@@ -228,7 +253,7 @@ pub fn polyveck_chknorm(v: &Polyveck, bound: i32) -> u8 {
 /// with -2^{D-1} < a0 <= 2^{D-1}. Assumes coefficients to be
 /// standard representatives.
 pub fn polyveck_power2round(v1: &mut Polyveck, v0: &mut Polyveck) {
-  for i in 0..K {
+  for i in 0..v1.vec.len() {
     poly_power2round(&mut v1.vec[i], &mut v0.vec[i]);
   }
 }
@@ -238,9 +263,13 @@ pub fn polyveck_power2round(v1: &mut Polyveck, v0: &mut Polyveck) {
 /// with -ALPHA/2 < a0 <= ALPHA/2 except a1 = (Q-1)/ALPHA where we
 /// set a1 = 0 and -ALPHA/2 <= a0 = a mod Q - Q < 0.
 /// Assumes coefficients to be standard representatives.
-pub fn polyveck_decompose(v1: &mut Polyveck, v0: &mut Polyveck) {
-  for i in 0..K {
-    poly_decompose(&mut v1.vec[i], &mut v0.vec[i]);
+pub fn polyveck_decompose(
+  mode: DilithiumMode,
+  v1: &mut Polyveck,
+  v0: &mut Polyveck,
+) {
+  for i in 0..v1.vec.len() {
+    poly_decompose(mode, &mut v1.vec[i], &mut v0.vec[i]);
   }
 }
 
@@ -248,26 +277,28 @@ pub fn polyveck_decompose(v1: &mut Polyveck, v0: &mut Polyveck) {
 ///
 /// Returns number of 1 bits.
 pub fn polyveck_make_hint(
+  mode: DilithiumMode,
   h: &mut Polyveck,
   v0: &Polyveck,
   v1: &Polyveck,
 ) -> i32 {
   let mut s = 0i32;
-  for i in 0..K {
-    s += poly_make_hint(&mut h.vec[i], &v0.vec[i], &v1.vec[i]);
+  for i in 0..h.vec.len() {
+    s += poly_make_hint(mode, &mut h.vec[i], &v0.vec[i], &v1.vec[i]);
   }
   s
 }
 
 /// Use hint vector to correct the high bits of input vector.
-pub fn polyveck_use_hint(w: &mut Polyveck, h: &Polyveck) {
-  for i in 0..K {
-    poly_use_hint(&mut w.vec[i], &h.vec[i]);
+pub fn polyveck_use_hint(mode: DilithiumMode, w: &mut Polyveck, h: &Polyveck) {
+  for i in 0..w.vec.len() {
+    poly_use_hint(mode, &mut w.vec[i], &h.vec[i]);
   }
 }
 
-pub fn polyveck_pack_w1(r: &mut [u8], w1: &Polyveck) {
-  for i in 0..K {
-    polyw1_pack(&mut r[i * POLYW1_PACKEDBYTES..], &w1.vec[i]);
+pub fn polyveck_pack_w1(mode: DilithiumMode, r: &mut [u8], w1: &Polyveck) {
+  let packedbytes = mode.polyw1_packedbytes();
+  for i in 0..w1.vec.len() {
+    polyw1_pack(mode, &mut r[i * packedbytes..], &w1.vec[i]);
   }
 }
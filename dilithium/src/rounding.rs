@@ -18,16 +18,17 @@ pub fn power2round(a: i32, a0: &mut i32) -> i32 {
 /// representative.
 ///
 /// Returns a1.
-pub fn decompose(a0: &mut i32, a: i32) -> i32 {
+pub fn decompose(mode: DilithiumMode, a0: &mut i32, a: i32) -> i32 {
+  let gamma2 = mode.gamma2();
   let mut a1 = (a + 127) >> 7;
-  if GAMMA2 == (Q - 1) / 32 {
+  if gamma2 == (Q - 1) / 32 {
     a1 = (a1 * 1025 + (1 << 21)) >> 22;
     a1 &= 15;
-  } else if GAMMA2 == (Q - 1) / 88 {
+  } else if gamma2 == (Q - 1) / 88 {
     a1 = (a1 * 11275 + (1 << 23)) >> 24;
     a1 ^= ((43 - a1) >> 31) & a1;
   }
-  *a0 = a - a1 * 2 * GAMMA2_I32;
+  *a0 = a - a1 * 2 * gamma2 as i32;
   *a0 -= (((Q_I32 - 1) / 2 - *a0) >> 31) & Q_I32;
   a1
 }
@@ -36,8 +37,9 @@ pub fn decompose(a0: &mut i32, a: i32) -> i32 {
 /// input element overflow into the high bits.
 ///
 /// Returns 1 if overflow.
-pub fn make_hint(a0: i32, a1: i32) -> u8 {
-  if a0 > GAMMA2_I32 || a0 < -GAMMA2_I32 || (a0 == -GAMMA2_I32 && a1 != 0) {
+pub fn make_hint(mode: DilithiumMode, a0: i32, a1: i32) -> u8 {
+  let gamma2 = mode.gamma2() as i32;
+  if a0 > gamma2 || a0 < -gamma2 || (a0 == -gamma2 && a1 != 0) {
     return 1;
   }
   return 0;
@@ -46,14 +48,14 @@ pub fn make_hint(a0: i32, a1: i32) -> u8 {
 /// Correct high bits according to hint.
 ///
 /// Returns corrected high bits.
-pub fn use_hint(a: i32, hint: u8) -> i32 {
+pub fn use_hint(mode: DilithiumMode, a: i32, hint: u8) -> i32 {
   let mut a0 = 0i32;
-  let a1 = decompose(&mut a0, a);
+  let a1 = decompose(mode, &mut a0, a);
   if hint == 0 {
     return a1;
   }
 
-  if GAMMA2 == (Q - 1) / 32 {
+  if mode.gamma2() == (Q - 1) / 32 {
     if a0 > 0 {
       return (a1 + 1) & 15;
     } else {
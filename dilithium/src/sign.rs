@@ -4,6 +4,7 @@ use crate::{
 };
 
 pub fn crypto_sign_keypair(
+  mode: DilithiumMode,
   pk: &mut [u8],
   sk: &mut [u8],
   seed: Option<&[u8]>,
@@ -14,36 +15,39 @@ pub fn crypto_sign_keypair(
     None => randombytes(&mut init_seed, SEEDBYTES),
   };
   let mut seedbuf = [0u8; 2 * SEEDBYTES + CRHBYTES];
-  let mut tr = [0u8; SEEDBYTES];
+  let mut tr = [0u8; TRBYTES];
   let (mut rho, mut rhoprime, mut key) =
     ([0u8; SEEDBYTES], [0u8; CRHBYTES], [0u8; SEEDBYTES]);
-  let mut mat = [Polyvecl::default(); K];
-  let mut s1 = Polyvecl::default();
+  let mut mat = polyvec_matrix_new(mode);
+  let mut s1 = Polyvecl::new(mode);
   let (mut s2, mut t1, mut t0) = (
-    Polyveck::default(),
-    Polyveck::default(),
-    Polyveck::default(),
+    Polyveck::new(mode),
+    Polyveck::new(mode),
+    Polyveck::new(mode),
   );
 
-  // Get randomness for rho, rhoprime and key
-  shake256(
-    &mut seedbuf,
-    2 * SEEDBYTES + CRHBYTES,
-    &init_seed,
-    SEEDBYTES,
-  );
+  let mut state = KeccakState::default();
+
+  // Get randomness for rho, rhoprime and key. ML-DSA binds it to the
+  // dimensions of the matrix too
+  shake256_absorb(&mut state, &init_seed, SEEDBYTES);
+  if mode.is_ml_dsa() {
+    shake256_absorb(&mut state, &[mode.k() as u8, mode.l() as u8], 2);
+  }
+  shake256_finalize(&mut state);
+  shake256_squeeze(&mut seedbuf, 2 * SEEDBYTES + CRHBYTES, &mut state);
   rho.copy_from_slice(&seedbuf[..SEEDBYTES]);
   rhoprime.copy_from_slice(&seedbuf[SEEDBYTES..SEEDBYTES + CRHBYTES]);
   key.copy_from_slice(&seedbuf[SEEDBYTES + CRHBYTES..]);
 
   // Expand matrix
-  polyvec_matrix_expand(&mut mat, &rho);
+  polyvec_matrix_expand(mode, &mut mat, &rho);
   // Sample short vectors s1 and s2
-  polyvecl_uniform_eta(&mut s1, &rhoprime, 0);
-  polyveck_uniform_eta(&mut s2, &rhoprime, L_U16);
+  polyvecl_uniform_eta(mode, &mut s1, &rhoprime, 0);
+  polyveck_uniform_eta(mode, &mut s2, &rhoprime, mode.l() as u16);
 
   // Matrix-vector multiplication
-  let mut s1hat = s1;
+  let mut s1hat = s1.clone();
   polyvecl_ntt(&mut s1hat);
 
   polyvec_matrix_pointwise_montgomery(&mut t1, &mat, &s1hat);
@@ -58,29 +62,68 @@ pub fn crypto_sign_keypair(
   pack_pk(pk, &rho, &t1);
 
   // Compute H(rho, t1) and write secret key
-  shake256(&mut tr, SEEDBYTES, pk, PUBLICKEYBYTES);
-  pack_sk(sk, &rho, &tr, &key, &t0, &s1, &s2);
+  shake256(&mut tr, mode.tr_bytes(), pk, mode.public_key_bytes());
+  pack_sk(mode, sk, &rho, &tr, &key, &t0, &s1, &s2);
 
   return 0;
 }
 
-pub fn crypto_sign_signature(sig: &mut [u8], m: &[u8], sk: &[u8]) {
+/// The bytes signed in front of the message. The legacy modes sign the
+/// message as it is, while ML-DSA prefixes it with a domain separator, the
+/// context string and, for pre-hashed messages, the OID of the hash, so
+/// the message itself never has to be copied. Fails if the mode can't
+/// take the context string or pre-hash.
+pub fn message_prefix(
+  mode: DilithiumMode,
+  ctx: &[u8],
+  prehash: Option<PreHash>,
+) -> Result<Vec<u8>, SignError> {
+  if !mode.is_ml_dsa() {
+    if !ctx.is_empty() || prehash.is_some() {
+      return Err(SignError::Input);
+    }
+    return Ok(Vec::new());
+  }
+  if ctx.len() > MAX_CONTEXT_BYTES {
+    return Err(SignError::Input);
+  }
+  let mut prefix = vec![prehash.is_some() as u8, ctx.len() as u8];
+  prefix.extend_from_slice(ctx);
+  if let Some(prehash) = prehash {
+    prefix.extend_from_slice(&prehash.oid());
+  }
+  Ok(prefix)
+}
+
+/// Signs `prefix|m`, see `message_prefix`. For ML-DSA, this is the
+/// internal signing function of FIPS 204, with `prefix|m` as M'.
+pub fn crypto_sign_signature(
+  mode: DilithiumMode,
+  signing: SigningMode,
+  sig: &mut [u8],
+  prefix: &[u8],
+  m: &[u8],
+  sk: &[u8],
+) {
+  let ctildebytes = mode.ctilde_bytes();
+  let w1_packedbytes = mode.k() * mode.polyw1_packedbytes();
   // `key` and `mu` are concatenated
   let mut keymu = [0u8; SEEDBYTES + CRHBYTES];
 
   let mut nonce = 0u16;
-  let mut mat = [Polyvecl::default(); K];
-  let (mut s1, mut y) = (Polyvecl::default(), Polyvecl::default());
-  let (mut s2, mut t0) = (Polyveck::default(), Polyveck::default());
-  let (mut w1, mut w0) = (Polyveck::default(), Polyveck::default());
-  let mut h = Polyveck::default();
+  let mut mat = polyvec_matrix_new(mode);
+  let (mut s1, mut y) = (Polyvecl::new(mode), Polyvecl::new(mode));
+  let (mut s2, mut t0) = (Polyveck::new(mode), Polyveck::new(mode));
+  let (mut w1, mut w0) = (Polyveck::new(mode), Polyveck::new(mode));
+  let mut h = Polyveck::new(mode);
   let mut cp = Poly::default();
   let mut state = KeccakState::default(); //shake256_init()
   let mut rho = [0u8; SEEDBYTES];
-  let mut tr = [0u8; SEEDBYTES];
+  let mut tr = [0u8; TRBYTES];
   let mut rhoprime = [0u8; CRHBYTES];
 
   unpack_sk(
+    mode,
     &mut rho,
     &mut tr,
     &mut keymu[..SEEDBYTES],
@@ -90,31 +133,53 @@ pub fn crypto_sign_signature(sig: &mut [u8], m: &[u8], sk: &[u8]) {
     &sk,
   );
 
-  // Compute CRH(tr, msg)
-  shake256_absorb(&mut state, &tr, SEEDBYTES);
+  // Compute CRH(tr, prefix, msg)
+  shake256_absorb(&mut state, &tr, mode.tr_bytes());
+  shake256_absorb(&mut state, prefix, prefix.len());
   shake256_absorb(&mut state, m, m.len());
   shake256_finalize(&mut state);
   shake256_squeeze(&mut keymu[SEEDBYTES..], CRHBYTES, &mut state);
 
-  if RANDOMIZED_SIGNING {
-    randombytes(&mut rhoprime, CRHBYTES);
-  } else {
-    shake256(&mut rhoprime, CRHBYTES, &keymu, SEEDBYTES + CRHBYTES);
+  // Deterministic ML-DSA signatures hedge with all zero randomness, while
+  // the legacy ones leave it out
+  let rnd = match signing {
+    SigningMode::Deterministic if !mode.is_ml_dsa() => None,
+    SigningMode::Deterministic => Some([0u8; RNDBYTES]),
+    SigningMode::Hedged(rnd) => Some(rnd.unwrap_or_else(|| {
+      let mut rnd = [0u8; RNDBYTES];
+      randombytes(&mut rnd, RNDBYTES);
+      rnd
+    })),
+  };
+
+  match rnd {
+    None => {
+      shake256(&mut rhoprime, CRHBYTES, &keymu, SEEDBYTES + CRHBYTES);
+    }
+    // rhoprime = CRH(key, rnd, mu), as in FIPS 204
+    Some(rnd) => {
+      state.init();
+      shake256_absorb(&mut state, &keymu[..SEEDBYTES], SEEDBYTES);
+      shake256_absorb(&mut state, &rnd, RNDBYTES);
+      shake256_absorb(&mut state, &keymu[SEEDBYTES..], CRHBYTES);
+      shake256_finalize(&mut state);
+      shake256_squeeze(&mut rhoprime, CRHBYTES, &mut state);
+    }
   }
 
   // Expand matrix and transform vectors
-  polyvec_matrix_expand(&mut mat, &rho);
+  polyvec_matrix_expand(mode, &mut mat, &rho);
   polyvecl_ntt(&mut s1);
   polyveck_ntt(&mut s2);
   polyveck_ntt(&mut t0);
 
   loop {
     // Sample intermediate vector y
-    polyvecl_uniform_gamma1(&mut y, &rhoprime, nonce);
+    polyvecl_uniform_gamma1(mode, &mut y, &rhoprime, nonce);
     nonce += 1;
 
     // Matrix-vector multiplication
-    let mut z = y;
+    let mut z = y.clone();
     polyvecl_ntt(&mut z);
     polyvec_matrix_pointwise_montgomery(&mut w1, &mat, &z);
     polyveck_reduce(&mut w1);
@@ -122,15 +187,15 @@ pub fn crypto_sign_signature(sig: &mut [u8], m: &[u8], sk: &[u8]) {
 
     // Decompose w and call the random oracle
     polyveck_caddq(&mut w1);
-    polyveck_decompose(&mut w1, &mut w0);
-    polyveck_pack_w1(sig, &w1);
+    polyveck_decompose(mode, &mut w1, &mut w0);
+    polyveck_pack_w1(mode, sig, &w1);
 
     state.init();
     shake256_absorb(&mut state, &keymu[SEEDBYTES..], CRHBYTES);
-    shake256_absorb(&mut state, &sig, K * POLYW1_PACKEDBYTES);
+    shake256_absorb(&mut state, &sig, w1_packedbytes);
     shake256_finalize(&mut state);
-    shake256_squeeze(sig, SEEDBYTES, &mut state);
-    poly_challenge(&mut cp, sig);
+    shake256_squeeze(sig, ctildebytes, &mut state);
+    poly_challenge(mode, &mut cp, sig);
     poly_ntt(&mut cp);
 
     // Compute z, reject if it reveals secret
@@ -138,7 +203,7 @@ pub fn crypto_sign_signature(sig: &mut [u8], m: &[u8], sk: &[u8]) {
     polyvecl_invntt_tomont(&mut z);
     polyvecl_add(&mut z, &y);
     polyvecl_reduce(&mut z);
-    if polyvecl_chknorm(&z, (GAMMA1 - BETA) as i32) > 0 {
+    if polyvecl_chknorm(&z, (mode.gamma1() - mode.beta()) as i32) > 0 {
       continue;
     }
 
@@ -148,7 +213,7 @@ pub fn crypto_sign_signature(sig: &mut [u8], m: &[u8], sk: &[u8]) {
     polyveck_invntt_tomont(&mut h);
     polyveck_sub(&mut w0, &h);
     polyveck_reduce(&mut w0);
-    if polyveck_chknorm(&w0, (GAMMA2 - BETA) as i32) > 0 {
+    if polyveck_chknorm(&w0, (mode.gamma2() - mode.beta()) as i32) > 0 {
       continue;
     }
 
@@ -156,63 +221,69 @@ pub fn crypto_sign_signature(sig: &mut [u8], m: &[u8], sk: &[u8]) {
     polyveck_pointwise_poly_montgomery(&mut h, &cp, &t0);
     polyveck_invntt_tomont(&mut h);
     polyveck_reduce(&mut h);
-    if polyveck_chknorm(&h, GAMMA2_I32) > 0 {
+    if polyveck_chknorm(&h, mode.gamma2() as i32) > 0 {
       continue;
     }
 
     polyveck_add(&mut w0, &h);
-    let n = polyveck_make_hint(&mut h, &w0, &w1);
-    if n > OMEGA as i32 {
+    let n = polyveck_make_hint(mode, &mut h, &w0, &w1);
+    if n > mode.omega() as i32 {
       continue;
     }
 
     // Write signature
-    pack_sig(sig, None, &z, &h);
+    pack_sig(mode, sig, None, &z, &h);
     return;
   }
 }
 
+/// Verifies a signature over `prefix|m`, see `message_prefix`
 pub fn crypto_sign_verify(
+  mode: DilithiumMode,
   sig: &[u8],
+  prefix: &[u8],
   m: &[u8],
   pk: &[u8],
 ) -> Result<(), SignError> {
-  let mut buf = [0u8; K * POLYW1_PACKEDBYTES];
+  let ctildebytes = mode.ctilde_bytes();
+  let mut buf = vec![0u8; mode.k() * mode.polyw1_packedbytes()];
   let mut rho = [0u8; SEEDBYTES];
   let mut mu = [0u8; CRHBYTES];
-  let mut c = [0u8; SEEDBYTES];
-  let mut c2 = [0u8; SEEDBYTES];
+  let mut c = [0u8; CRHBYTES];
+  let mut c2 = [0u8; CRHBYTES];
   let mut cp = Poly::default();
-  let (mut mat, mut z) = ([Polyvecl::default(); K], Polyvecl::default());
+  let (mut mat, mut z) = (polyvec_matrix_new(mode), Polyvecl::new(mode));
   let (mut t1, mut w1, mut h) = (
-    Polyveck::default(),
-    Polyveck::default(),
-    Polyveck::default(),
+    Polyveck::new(mode),
+    Polyveck::new(mode),
+    Polyveck::new(mode),
   );
   let mut state = KeccakState::default(); // shake256_init()
 
-  if sig.len() != SIGNBYTES {
+  if sig.len() != mode.signature_bytes() || pk.len() != mode.public_key_bytes()
+  {
     return Err(SignError::Input);
   }
 
   unpack_pk(&mut rho, &mut t1, pk);
-  if let Err(e) = unpack_sig(&mut c, &mut z, &mut h, sig) {
+  if let Err(e) = unpack_sig(mode, &mut c, &mut z, &mut h, sig) {
     return Err(e);
   }
-  if polyvecl_chknorm(&z, (GAMMA1 - BETA) as i32) > 0 {
+  if polyvecl_chknorm(&z, (mode.gamma1() - mode.beta()) as i32) > 0 {
     return Err(SignError::Input);
   }
 
-  // Compute CRH(CRH(rho, t1), msg)
-  shake256(&mut mu, SEEDBYTES, pk, PUBLICKEYBYTES);
-  shake256_absorb(&mut state, &mu, SEEDBYTES);
+  // Compute CRH(CRH(rho, t1), prefix, msg)
+  shake256(&mut mu, mode.tr_bytes(), pk, mode.public_key_bytes());
+  shake256_absorb(&mut state, &mu, mode.tr_bytes());
+  shake256_absorb(&mut state, prefix, prefix.len());
   shake256_absorb(&mut state, m, m.len());
   shake256_finalize(&mut state);
   shake256_squeeze(&mut mu, CRHBYTES, &mut state);
 
   // Matrix-vector multiplication; compute Az - c2^dt1
-  poly_challenge(&mut cp, &c);
-  polyvec_matrix_expand(&mut mat, &rho);
+  poly_challenge(mode, &mut cp, &c);
+  polyvec_matrix_expand(mode, &mut mat, &rho);
 
   polyvecl_ntt(&mut z);
   polyvec_matrix_pointwise_montgomery(&mut w1, &mat, &z);
@@ -229,17 +300,17 @@ pub fn crypto_sign_verify(
 
   // Reconstruct w1
   polyveck_caddq(&mut w1);
-  polyveck_use_hint(&mut w1, &h);
-  polyveck_pack_w1(&mut buf, &w1);
+  polyveck_use_hint(mode, &mut w1, &h);
+  polyveck_pack_w1(mode, &mut buf, &w1);
 
   // Call random oracle and verify challenge
   state.init();
   shake256_absorb(&mut state, &mu, CRHBYTES);
-  shake256_absorb(&mut state, &buf, K * POLYW1_PACKEDBYTES);
+  shake256_absorb(&mut state, &buf, buf.len());
   shake256_finalize(&mut state);
-  shake256_squeeze(&mut c2, SEEDBYTES, &mut state);
+  shake256_squeeze(&mut c2, ctildebytes, &mut state);
   // Doesn't require constant time equality check
-  if c != c2 {
+  if c[..ctildebytes] != c2[..ctildebytes] {
     Err(SignError::Verify)
   } else {
     Ok(())
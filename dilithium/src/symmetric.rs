@@ -1,106 +1,107 @@
 use crate::fips202::*;
-use crate::params::{CRHBYTES, SEEDBYTES};
+use crate::params::{DilithiumMode, CRHBYTES, SEEDBYTES};
 
 #[cfg(feature = "aes")]
 use crate::aes256ctr::*;
 
-#[cfg(not(feature = "aes"))]
-pub type Stream128State = KeccakState;
-#[cfg(feature = "aes")]
-pub type Stream128State = Aes256ctrCtx;
-#[cfg(not(feature = "aes"))]
-pub type Stream256State = KeccakState;
-#[cfg(feature = "aes")]
-pub type Stream256State = Aes256ctrCtx;
-
-#[cfg(feature = "aes")]
-pub const STREAM128_BLOCKBYTES: usize = AES256CTR_BLOCKBYTES;
-#[cfg(not(feature = "aes"))]
-pub const STREAM128_BLOCKBYTES: usize = SHAKE128_RATE;
+/// State of the stream the matrix and vectors are sampled from. ML-DSA
+/// always uses SHAKE, while the legacy modes switch to AES-256 in counter
+/// mode when the `aes` feature is enabled, so the stream is picked at
+/// runtime from the mode.
+pub enum StreamState {
+  Shake128(KeccakState),
+  Shake256(KeccakState),
+  #[cfg(feature = "aes")]
+  Aes256ctr(Aes256ctrCtx),
+}
 
-#[cfg(feature = "aes")]
-pub const STREAM256_BLOCKBYTES: usize = AES256CTR_BLOCKBYTES;
-#[cfg(not(feature = "aes"))]
-pub const STREAM256_BLOCKBYTES: usize = SHAKE256_RATE;
+impl StreamState {
+  /// Size in bytes of the blocks the stream is squeezed in
+  pub fn blockbytes(&self) -> usize {
+    match self {
+      StreamState::Shake128(_) => SHAKE128_RATE,
+      StreamState::Shake256(_) => SHAKE256_RATE,
+      #[cfg(feature = "aes")]
+      StreamState::Aes256ctr(_) => AES256CTR_BLOCKBYTES,
+    }
+  }
+}
 
 pub fn _crh(out: &mut [u8], input: &[u8], inbytes: usize) {
   shake256(out, CRHBYTES, input, inbytes)
 }
 
-pub fn stream128_init(state: &mut Stream128State, seed: &[u8], nonce: u16) {
-  #[cfg(not(feature = "aes"))]
-  dilithium_shake128_stream_init(state, seed, nonce);
-
-  #[cfg(feature = "aes")]
-  dilithium_aes256ctr_init(state, seed, nonce)
-}
-
-pub fn stream128_squeezeblocks(
-  out: &mut [u8],
-  outblocks: u64,
-  state: &mut Stream128State,
-) {
-  #[cfg(not(feature = "aes"))]
-  shake128_squeezeblocks(out, outblocks as usize, state);
-
+/// SHAKE128(seed|nonce), or AES256CTR(seed, nonce)
+#[cfg_attr(not(feature = "aes"), allow(unused_variables))]
+pub fn stream128_init(
+  mode: DilithiumMode,
+  seed: &[u8],
+  nonce: u16,
+) -> StreamState {
   #[cfg(feature = "aes")]
-  aes256ctr_squeezeblocks(out, outblocks, state);
+  if mode.aes() {
+    return dilithium_aes256ctr_init(seed, nonce);
+  }
+  dilithium_shake128_stream_init(seed, nonce)
 }
 
-pub fn stream256_init(state: &mut Stream256State, seed: &[u8], nonce: u16) {
-  #[cfg(not(feature = "aes"))]
-  dilithium_shake256_stream_init(state, seed, nonce);
-
+/// SHAKE256(seed|nonce), or AES256CTR(seed, nonce)
+#[cfg_attr(not(feature = "aes"), allow(unused_variables))]
+pub fn stream256_init(
+  mode: DilithiumMode,
+  seed: &[u8],
+  nonce: u16,
+) -> StreamState {
   #[cfg(feature = "aes")]
-  dilithium_aes256ctr_init(state, seed, nonce)
+  if mode.aes() {
+    return dilithium_aes256ctr_init(seed, nonce);
+  }
+  dilithium_shake256_stream_init(seed, nonce)
 }
 
-pub fn stream256_squeezeblocks(
+pub fn stream_squeezeblocks(
   out: &mut [u8],
   outblocks: u64,
-  state: &mut Stream256State,
+  state: &mut StreamState,
 ) {
-  #[cfg(not(feature = "aes"))]
-  shake256_squeezeblocks(out, outblocks as usize, state);
-
-  #[cfg(feature = "aes")]
-  aes256ctr_squeezeblocks(out, outblocks, state);
+  match state {
+    StreamState::Shake128(state) => {
+      shake128_squeezeblocks(out, outblocks as usize, state)
+    }
+    StreamState::Shake256(state) => {
+      shake256_squeezeblocks(out, outblocks as usize, state)
+    }
+    #[cfg(feature = "aes")]
+    StreamState::Aes256ctr(state) => {
+      aes256ctr_squeezeblocks(out, outblocks, state)
+    }
+  }
 }
 
 #[cfg(feature = "aes")]
-pub fn dilithium_aes256ctr_init(
-  state: &mut Aes256ctrCtx,
-  key: &[u8],
-  nonce: u16,
-) {
+pub fn dilithium_aes256ctr_init(key: &[u8], nonce: u16) -> StreamState {
+  let mut state = Aes256ctrCtx::default();
   let mut expnonce = [0u8; 12];
   expnonce[0] = nonce as u8;
   expnonce[1] = (nonce >> 8) as u8;
-  aes256ctr_init(state, key, expnonce);
+  aes256ctr_init(&mut state, key, expnonce);
+  StreamState::Aes256ctr(state)
 }
 
-#[cfg(not(feature = "aes"))]
-pub fn dilithium_shake128_stream_init(
-  state: &mut KeccakState,
-  seed: &[u8],
-  nonce: u16,
-) {
+pub fn dilithium_shake128_stream_init(seed: &[u8], nonce: u16) -> StreamState {
+  let mut state = KeccakState::default();
   let t = [nonce as u8, (nonce >> 8) as u8];
-  state.init();
-  shake128_absorb(state, seed, SEEDBYTES);
-  shake128_absorb(state, &t, 2);
-  shake128_finalize(state);
+  shake128_absorb(&mut state, seed, SEEDBYTES);
+  shake128_absorb(&mut state, &t, 2);
+  shake128_finalize(&mut state);
+  StreamState::Shake128(state)
 }
 
-#[cfg(not(feature = "aes"))]
-pub fn dilithium_shake256_stream_init(
-  state: &mut KeccakState,
-  seed: &[u8],
-  nonce: u16,
-) {
+pub fn dilithium_shake256_stream_init(seed: &[u8], nonce: u16) -> StreamState {
+  let mut state = KeccakState::default();
   let t = [nonce as u8, (nonce >> 8) as u8];
-  state.init();
-  shake256_absorb(state, seed, CRHBYTES);
-  shake256_absorb(state, &t, 2);
-  shake256_finalize(state);
+  shake256_absorb(&mut state, seed, CRHBYTES);
+  shake256_absorb(&mut state, &t, 2);
+  shake256_finalize(&mut state);
+  StreamState::Shake256(state)
 }
@@ -0,0 +1,227 @@
+#!/usr/bin/env python3
+"""Regenerates the ML-DSA vectors in this folder, in the layout of the
+ACVP-Server JSON files (internalProjection, with the expected results
+merged in), from the FIPS 204 implementation of OpenSSL 3.5 or later.
+
+    python3 generate.py
+
+Everything is derived from a fixed seed, so the output is reproducible.
+"""
+
+import hashlib
+import json
+import os
+import re
+import subprocess
+import tempfile
+
+PARAMETER_SETS = ["ML-DSA-44", "ML-DSA-65", "ML-DSA-87"]
+
+# Pre-hash functions, with their ACVP names, hashlib names, OID suffixes
+# and digest sizes
+HASHES = {
+    "SHA2-224": ("sha224", 0x04, 28),
+    "SHA2-256": ("sha256", 0x01, 32),
+    "SHA2-384": ("sha384", 0x02, 48),
+    "SHA2-512": ("sha512", 0x03, 64),
+    "SHA2-512/224": ("sha512_224", 0x05, 28),
+    "SHA2-512/256": ("sha512_256", 0x06, 32),
+    "SHA3-224": ("sha3_224", 0x07, 28),
+    "SHA3-256": ("sha3_256", 0x08, 32),
+    "SHA3-384": ("sha3_384", 0x09, 48),
+    "SHA3-512": ("sha3_512", 0x0A, 64),
+    "SHAKE-128": ("shake_128", 0x0B, 32),
+    "SHAKE-256": ("shake_256", 0x0C, 64),
+}
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+TMP = tempfile.mkdtemp()
+
+
+class Drbg:
+    """Deterministic bytes, so regenerating gives the same files."""
+
+    def __init__(self, label):
+        self.state = hashlib.sha256(label.encode()).digest()
+
+    def bytes(self, n):
+        out = b""
+        while len(out) < n:
+            self.state = hashlib.sha256(self.state).digest()
+            out += self.state
+        return out[:n]
+
+    def below(self, n):
+        return int.from_bytes(self.bytes(4), "big") % n
+
+
+def openssl(*args, stdin=None):
+    return subprocess.run(
+        ["openssl", *args], input=stdin, capture_output=True, check=True
+    ).stdout
+
+
+def path(name):
+    return os.path.join(TMP, name)
+
+
+def keygen(parameter_set, seed):
+    openssl(
+        "genpkey", "-algorithm", parameter_set,
+        "-pkeyopt", "hexseed:" + seed.hex(), "-out", path("key.pem"),
+    )
+    text = openssl("pkey", "-in", path("key.pem"), "-text", "-noout").decode()
+    fields = {}
+    for name, body in re.findall(r"^(\w+):\n((?:\s+[0-9a-f:]+\n)+)", text, re.M):
+        fields[name] = bytes.fromhex(re.sub(r"[\s:]", "", body))
+    return fields["pub"], fields["priv"]
+
+
+def message_prime(message, context, hash_alg):
+    """M' of FIPS 204, the message as the internal interface signs it."""
+    if hash_alg == "none":
+        return bytes([0, len(context)]) + context + message
+    name, oid, size = HASHES[hash_alg]
+    h = hashlib.new(name, message)
+    digest = h.digest(size) if name.startswith("shake") else h.digest()
+    oid = bytes([0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, oid])
+    return bytes([1, len(context)]) + context + oid + digest
+
+
+def sign(message, context, hash_alg, rnd):
+    with open(path("msg"), "wb") as f:
+        f.write(message_prime(message, context, hash_alg))
+    openssl(
+        "pkeyutl", "-sign", "-rawin", "-inkey", path("key.pem"),
+        "-in", path("msg"), "-out", path("sig"),
+        "-pkeyopt", "message-encoding:0",
+        "-pkeyopt", "hextest-entropy:" + rnd.hex(),
+    )
+    with open(path("sig"), "rb") as f:
+        return f.read()
+
+
+def document(mode, groups):
+    return {
+        "vsId": 0,
+        "algorithm": "ML-DSA",
+        "mode": mode,
+        "revision": "FIPS204",
+        "isSample": True,
+        "testGroups": groups,
+    }
+
+
+def write(name, doc):
+    with open(os.path.join(HERE, name), "w") as f:
+        json.dump(doc, f, indent=2)
+        f.write("\n")
+
+
+def keygen_vectors():
+    rng, groups, tc = Drbg("keyGen"), [], 0
+    for tg, parameter_set in enumerate(PARAMETER_SETS, 1):
+        tests = []
+        for _ in range(5):
+            tc += 1
+            seed = rng.bytes(32)
+            pk, sk = keygen(parameter_set, seed)
+            tests.append({
+                "tcId": tc, "deferred": False,
+                "seed": seed.hex().upper(), "pk": pk.hex().upper(),
+                "sk": sk.hex().upper(),
+            })
+        groups.append({
+            "tgId": tg, "testType": "AFT", "parameterSet": parameter_set,
+            "tests": tests,
+        })
+    return document("keyGen", groups)
+
+
+def random_case(rng, pre_hash):
+    message = rng.bytes(rng.below(256) + 1)
+    context = rng.bytes(rng.below(256))
+    hash_alg = "none"
+    if pre_hash == "preHash":
+        hash_alg = list(HASHES)[rng.below(len(HASHES))]
+    return message, context, hash_alg
+
+
+def siggen_vectors():
+    rng, groups, tg, tc = Drbg("sigGen"), [], 0, 0
+    for parameter_set in PARAMETER_SETS:
+        for pre_hash in ["pure", "preHash"]:
+            for deterministic in [True, False]:
+                tg += 1
+                tests = []
+                for _ in range(3):
+                    tc += 1
+                    pk, sk = keygen(parameter_set, rng.bytes(32))
+                    message, context, hash_alg = random_case(rng, pre_hash)
+                    rnd = bytes(32) if deterministic else rng.bytes(32)
+                    test = {
+                        "tcId": tc, "deferred": False,
+                        "message": message.hex().upper(),
+                        "context": context.hex().upper(),
+                        "hashAlg": hash_alg, "sk": sk.hex().upper(),
+                    }
+                    if not deterministic:
+                        test["rnd"] = rnd.hex().upper()
+                    test["signature"] = sign(message, context, hash_alg, rnd).hex().upper()
+                    tests.append(test)
+                groups.append({
+                    "tgId": tg, "testType": "AFT", "parameterSet": parameter_set,
+                    "deterministic": deterministic, "signatureInterface": "external",
+                    "preHash": pre_hash, "externalMu": False, "tests": tests,
+                })
+    return document("sigGen", groups)
+
+
+def sigver_vectors():
+    rng, groups, tg, tc = Drbg("sigVer"), [], 0, 0
+    reasons = [
+        "valid signature and message - signature should verify successfully",
+        "modified message",
+        "modified signature - commitment",
+        "modified signature - z",
+        "modified signature - hint",
+        "modified context",
+    ]
+    for parameter_set in PARAMETER_SETS:
+        for pre_hash in ["pure", "preHash"]:
+            tg += 1
+            tests = []
+            for reason in reasons:
+                tc += 1
+                pk, _ = keygen(parameter_set, rng.bytes(32))
+                message, context, hash_alg = random_case(rng, pre_hash)
+                signature = bytearray(sign(message, context, hash_alg, rng.bytes(32)))
+                if reason == "modified message":
+                    message = bytes([message[0] ^ 1]) + message[1:]
+                elif reason == "modified signature - commitment":
+                    signature[0] ^= 1
+                elif reason == "modified signature - z":
+                    signature[100] ^= 1
+                elif reason == "modified signature - hint":
+                    # The last hint counter can't exceed omega
+                    signature[-1] = 0xFF
+                elif reason == "modified context":
+                    context = context + b"\x00"
+                tests.append({
+                    "tcId": tc, "deferred": False, "pk": pk.hex().upper(),
+                    "message": message.hex().upper(), "context": context.hex().upper(),
+                    "hashAlg": hash_alg, "signature": bytes(signature).hex().upper(),
+                    "testPassed": reason.startswith("valid"), "reason": reason,
+                })
+            groups.append({
+                "tgId": tg, "testType": "AFT", "parameterSet": parameter_set,
+                "signatureInterface": "external", "preHash": pre_hash,
+                "externalMu": False, "tests": tests,
+            })
+    return document("sigVer", groups)
+
+
+if __name__ == "__main__":
+    write("ML-DSA-keyGen-FIPS204.json", keygen_vectors())
+    write("ML-DSA-sigGen-FIPS204.json", siggen_vectors())
+    write("ML-DSA-sigVer-FIPS204.json", sigver_vectors())
@@ -0,0 +1,147 @@
+use pqc_dilithium::*;
+use serde_json::Value;
+use sha2::Digest;
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use std::path::PathBuf;
+
+fn vectors(name: &str) -> Value {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.extend(["tests", "ACVP", name].iter());
+  let file = std::fs::read_to_string(path).unwrap();
+  serde_json::from_str(&file).unwrap()
+}
+
+fn bytes(value: &Value) -> Vec<u8> {
+  let hex = value.as_str().unwrap();
+  (0..hex.len())
+    .step_by(2)
+    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+    .collect()
+}
+
+fn mode(group: &Value) -> DilithiumMode {
+  match group["parameterSet"].as_str().unwrap() {
+    "ML-DSA-44" => DilithiumMode::MlDsa44,
+    "ML-DSA-65" => DilithiumMode::MlDsa65,
+    "ML-DSA-87" => DilithiumMode::MlDsa87,
+    other => panic!("unknown parameter set {}", other),
+  }
+}
+
+fn shake<H: Default + Update + ExtendableOutput>(
+  msg: &[u8],
+  len: usize,
+) -> Vec<u8> {
+  let mut hasher = H::default();
+  hasher.update(msg);
+  let mut digest = vec![0u8; len];
+  hasher.finalize_xof().read(&mut digest);
+  digest
+}
+
+/// The pre-hash a test asks for, along with the digest of its message
+fn prehash(test: &Value) -> Option<(PreHash, Vec<u8>)> {
+  let msg = bytes(&test["message"]);
+  let prehash = match test["hashAlg"].as_str().unwrap() {
+    "none" => return None,
+    "SHA2-224" => (PreHash::Sha2_224, sha2::Sha224::digest(&msg).to_vec()),
+    "SHA2-256" => (PreHash::Sha2_256, sha2::Sha256::digest(&msg).to_vec()),
+    "SHA2-384" => (PreHash::Sha2_384, sha2::Sha384::digest(&msg).to_vec()),
+    "SHA2-512" => (PreHash::Sha2_512, sha2::Sha512::digest(&msg).to_vec()),
+    "SHA2-512/224" => (
+      PreHash::Sha2_512_224,
+      sha2::Sha512_224::digest(&msg).to_vec(),
+    ),
+    "SHA2-512/256" => (
+      PreHash::Sha2_512_256,
+      sha2::Sha512_256::digest(&msg).to_vec(),
+    ),
+    "SHA3-224" => (PreHash::Sha3_224, sha3::Sha3_224::digest(&msg).to_vec()),
+    "SHA3-256" => (PreHash::Sha3_256, sha3::Sha3_256::digest(&msg).to_vec()),
+    "SHA3-384" => (PreHash::Sha3_384, sha3::Sha3_384::digest(&msg).to_vec()),
+    "SHA3-512" => (PreHash::Sha3_512, sha3::Sha3_512::digest(&msg).to_vec()),
+    "SHAKE-128" => (PreHash::Shake128, shake::<sha3::Shake128>(&msg, 32)),
+    "SHAKE-256" => (PreHash::Shake256, shake::<sha3::Shake256>(&msg, 64)),
+    other => panic!("unknown hash {}", other),
+  };
+  Some(prehash)
+}
+
+fn groups<'a>(vectors: &'a Value) -> impl Iterator<Item = &'a Value> {
+  vectors["testGroups"].as_array().unwrap().iter()
+}
+
+fn tests<'a>(group: &'a Value) -> impl Iterator<Item = &'a Value> {
+  group["tests"].as_array().unwrap().iter()
+}
+
+#[test]
+fn keygen() {
+  for group in groups(&vectors("ML-DSA-keyGen-FIPS204.json")) {
+    let mode = mode(group);
+    for test in tests(group) {
+      let mut seed = [0u8; SEEDBYTES];
+      seed.copy_from_slice(&bytes(&test["seed"]));
+      let keys = Keypair::from_seed(mode, &seed);
+      assert_eq!(keys.public, bytes(&test["pk"]), "tcId {}", test["tcId"]);
+      assert_eq!(keys.expose_secret(), &bytes(&test["sk"])[..]);
+    }
+  }
+}
+
+#[test]
+fn siggen() {
+  for group in groups(&vectors("ML-DSA-sigGen-FIPS204.json")) {
+    let mode = mode(group);
+    for test in tests(group) {
+      // Only the secret key is given, the public one isn't needed
+      let keys = Keypair::restore_from_keys(
+        mode,
+        vec![0u8; mode.public_key_bytes()],
+        bytes(&test["sk"]),
+      )
+      .unwrap();
+      let signing = match group["deterministic"].as_bool().unwrap() {
+        true => SigningMode::Deterministic,
+        false => {
+          let mut rnd = [0u8; RNDBYTES];
+          rnd.copy_from_slice(&bytes(&test["rnd"]));
+          SigningMode::Hedged(Some(rnd))
+        }
+      };
+      let (msg, ctx) = (bytes(&test["message"]), bytes(&test["context"]));
+      let sig = match prehash(test) {
+        None => keys.sign_ctx(&msg, &ctx, signing),
+        Some((prehash, digest)) => {
+          keys.sign_prehashed(&digest, prehash, &ctx, signing)
+        }
+      };
+      let sig = sig.unwrap();
+      assert_eq!(sig, bytes(&test["signature"]), "tcId {}", test["tcId"]);
+    }
+  }
+}
+
+#[test]
+fn sigver() {
+  for group in groups(&vectors("ML-DSA-sigVer-FIPS204.json")) {
+    let mode = mode(group);
+    for test in tests(group) {
+      let (pk, sig) = (bytes(&test["pk"]), bytes(&test["signature"]));
+      let (msg, ctx) = (bytes(&test["message"]), bytes(&test["context"]));
+      let result = match prehash(test) {
+        None => verify_ctx(mode, &sig, &msg, &ctx, &pk),
+        Some((prehash, digest)) => {
+          verify_prehashed(mode, &sig, &digest, prehash, &ctx, &pk)
+        }
+      };
+      assert_eq!(
+        result.is_ok(),
+        test["testPassed"].as_bool().unwrap(),
+        "tcId {}: {}",
+        test["tcId"],
+        test["reason"]
+      );
+    }
+  }
+}
@@ -3,16 +3,123 @@ use pqc_dilithium::*;
 #[test]
 fn sign_then_verify_valid() {
   let msg = b"Hello";
-  let keys = Keypair::generate();
+  let keys = Keypair::generate(DilithiumMode::default());
   let signature = keys.sign(msg);
-  assert!(verify(&signature, msg, &keys.public).is_ok())
+  assert!(verify(keys.mode, &signature, msg, &keys.public).is_ok())
 }
 
 #[test]
 fn sign_then_verify_invalid() {
   let msg = b"Hello";
-  let keys = Keypair::generate();
+  let keys = Keypair::generate(DilithiumMode::default());
   let mut signature = keys.sign(msg);
   signature[..4].copy_from_slice(&[255u8; 4]);
-  assert!(verify(&signature, msg, &keys.public).is_err())
+  assert!(verify(keys.mode, &signature, msg, &keys.public).is_err())
+}
+
+#[test]
+fn every_mode_in_one_run() {
+  let msg = b"Hello";
+  for mode in DilithiumMode::ALL {
+    let keys = Keypair::generate(mode);
+    let signature = keys.sign(msg);
+    assert_eq!(signature.len(), mode.signature_bytes());
+    assert!(verify(mode, &signature, msg, &keys.public).is_ok());
+    for other in DilithiumMode::ALL.iter().filter(|m| **m != mode) {
+      assert!(verify(*other, &signature, msg, &keys.public).is_err());
+    }
+  }
+}
+
+#[test]
+fn signing_modes() {
+  let msg = b"Hello";
+  let keys = Keypair::generate(DilithiumMode::default());
+  let deterministic = keys.sign_with(msg, SigningMode::Deterministic);
+  assert_eq!(
+    deterministic,
+    keys.sign_with(msg, SigningMode::Deterministic)
+  );
+
+  let hedged = keys.sign_with(msg, SigningMode::Hedged(None));
+  assert_ne!(hedged, keys.sign_with(msg, SigningMode::Hedged(None)));
+  assert_ne!(hedged, deterministic);
+
+  // Supplied randomness makes hedged signatures reproducible
+  let rnd = [7u8; RNDBYTES];
+  let supplied = keys.sign_with(msg, SigningMode::Hedged(Some(rnd)));
+  assert_eq!(
+    supplied,
+    keys.sign_with(msg, SigningMode::Hedged(Some(rnd)))
+  );
+  assert_ne!(supplied, deterministic);
+
+  // Plain signing is hedged
+  assert_eq!(SigningMode::default(), SigningMode::Hedged(None));
+  assert_ne!(keys.sign(msg), keys.sign(msg));
+
+  for sig in [deterministic, hedged, supplied].iter() {
+    assert!(verify(keys.mode, sig, msg, &keys.public).is_ok());
+  }
+}
+
+#[test]
+fn restore_checks_lengths() {
+  let keys = Keypair::generate(DilithiumMode::Dilithium2);
+  let public = keys.public.clone();
+  let secret = keys.expose_secret().to_vec();
+  let restored = Keypair::restore_from_keys(
+    DilithiumMode::Dilithium2,
+    public.clone(),
+    secret.clone(),
+  )
+  .unwrap();
+  assert_eq!(restored, keys);
+  assert!(Keypair::restore_from_keys(
+    DilithiumMode::Dilithium5,
+    public,
+    secret
+  )
+  .is_err());
+}
+
+#[test]
+fn contexts_and_prehashes() {
+  let msg = b"Hello";
+  let keys = Keypair::generate(DilithiumMode::MlDsa44);
+  let signing = SigningMode::Deterministic;
+
+  // An empty context string is what plain signing uses
+  let sig = keys.sign_ctx(msg, &[], signing).unwrap();
+  assert_eq!(sig, keys.sign_with(msg, signing));
+  assert!(verify(keys.mode, &sig, msg, &keys.public).is_ok());
+
+  let sig = keys.sign_ctx(msg, b"context", signing).unwrap();
+  assert!(verify_ctx(keys.mode, &sig, msg, b"context", &keys.public).is_ok());
+  assert!(verify_ctx(keys.mode, &sig, msg, b"other", &keys.public).is_err());
+  let long = [0u8; MAX_CONTEXT_BYTES + 1];
+  assert!(keys.sign_ctx(msg, &long, signing).is_err());
+
+  // Pre-hashed and pure signatures over the same bytes differ
+  let digest = [1u8; 32];
+  let sig = keys
+    .sign_prehashed(&digest, PreHash::Sha2_256, &[], signing)
+    .unwrap();
+  let verify_digest = |prehash| {
+    verify_prehashed(keys.mode, &sig, &digest, prehash, &[], &keys.public)
+  };
+  assert!(verify_digest(PreHash::Sha2_256).is_ok());
+  assert!(verify_digest(PreHash::Sha3_256).is_err());
+  assert!(verify(keys.mode, &sig, &digest, &keys.public).is_err());
+  assert!(keys
+    .sign_prehashed(&digest, PreHash::Sha2_512, &[], signing)
+    .is_err());
+
+  // The round 3 modes have neither
+  let keys = Keypair::generate(DilithiumMode::Dilithium2);
+  assert!(keys.sign_ctx(msg, &[], signing).is_ok());
+  assert!(keys.sign_ctx(msg, b"context", signing).is_err());
+  assert!(keys
+    .sign_prehashed(&digest, PreHash::Sha2_256, &[], signing)
+    .is_err());
 }
@@ -1,61 +1,85 @@
-#![cfg(all(dilithium_kat, not(feature = "random_signing")))]
+#![cfg(dilithium_kat)]
 
 use pqc_core::load::*;
 use pqc_dilithium::*;
 use std::path::PathBuf;
 
-const MODE: u8 = if cfg!(feature = "mode2") {
-  2
-} else if cfg!(feature = "mode5") {
-  5
-} else {
-  3
-};
-
 const AES: &str = if cfg!(feature = "aes") { "-AES" } else { "" };
 
+// The round 3 KATs only cover the legacy modes, ML-DSA has its own vectors
+const LEGACY: [DilithiumMode; 3] = [
+  DilithiumMode::Dilithium2,
+  DilithiumMode::Dilithium3,
+  DilithiumMode::Dilithium5,
+];
+
+fn number(mode: DilithiumMode) -> u8 {
+  match mode {
+    DilithiumMode::Dilithium2 => 2,
+    DilithiumMode::Dilithium3 => 3,
+    DilithiumMode::Dilithium5 => 5,
+    _ => unreachable!(),
+  }
+}
+
+fn filename(mode: DilithiumMode) -> String {
+  format!("PQCsignKAT_Dilithium{}{}.rsp", number(mode), AES)
+}
+
 #[test]
 fn keypair() {
-  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-  let filename = format!("PQCsignKAT_Dilithium{}{}.rsp", MODE, AES);
-  let katvec = kats(&mut path.clone(), &filename);
-  let bufvec = bufs(&mut path, "SeedBuffer_Dilithium");
-  for (i, kat) in katvec.iter().enumerate() {
-    let pk = kat.pk.clone();
-    let sk = kat.sk.clone();
-    let mut pk2 = [0u8; PUBLICKEYBYTES];
-    let mut sk2 = [0u8; SECRETKEYBYTES];
-    crypto_sign_keypair(&mut pk2, &mut sk2, Some(&bufvec[i]));
-    assert_eq!(pk, pk2);
-    assert_eq!(sk, sk2);
+  for mode in LEGACY {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let katvec = kats(&mut path.clone(), &filename(mode));
+    let bufvec = bufs(&mut path, "SeedBuffer_Dilithium");
+    for (i, kat) in katvec.iter().enumerate() {
+      let pk = kat.pk.clone();
+      let sk = kat.sk.clone();
+      let mut pk2 = vec![0u8; mode.public_key_bytes()];
+      let mut sk2 = vec![0u8; mode.secret_key_bytes()];
+      crypto_sign_keypair(mode, &mut pk2, &mut sk2, Some(&bufvec[i]));
+      assert_eq!(pk, pk2);
+      assert_eq!(sk, sk2);
+    }
   }
 }
 
 #[test]
 pub fn sign() {
-  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-  let filename = format!("PQCsignKAT_Dilithium{}{}.rsp", MODE, AES);
-  let katvec = kats(&mut path, &filename);
-  for kat in katvec {
-    let sm = kat.sm.clone();
-    let msg = kat.msg.clone();
-    let sk = kat.sk.clone();
-    let mut sig = vec![0u8; SIGNBYTES];
-    crypto_sign_signature(&mut sig, &msg, &sk);
-    assert_eq!(sm[..SIGNBYTES], sig);
+  for mode in LEGACY {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let katvec = kats(&mut path, &filename(mode));
+    let signbytes = mode.signature_bytes();
+    for kat in katvec {
+      let sm = kat.sm.clone();
+      let msg = kat.msg.clone();
+      let sk = kat.sk.clone();
+      let mut sig = vec![0u8; signbytes];
+      crypto_sign_signature(
+        mode,
+        SigningMode::Deterministic,
+        &mut sig,
+        &[],
+        &msg,
+        &sk,
+      );
+      assert_eq!(sm[..signbytes], sig);
+    }
   }
 }
 
 #[test]
 pub fn verify() {
-  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-  let filename = format!("PQCsignKAT_Dilithium{}{}.rsp", MODE, AES);
-  let katvec = kats(&mut path, &filename);
-  for kat in katvec {
-    let sm = kat.sm.clone();
-    let msg = kat.msg.clone();
-    let pk = kat.pk.clone();
-    let res = crypto_sign_verify(&sm[..SIGNBYTES], &msg, &pk);
-    assert!(res.is_ok());
+  for mode in LEGACY {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let katvec = kats(&mut path, &filename(mode));
+    let signbytes = mode.signature_bytes();
+    for kat in katvec {
+      let sm = kat.sm.clone();
+      let msg = kat.msg.clone();
+      let pk = kat.pk.clone();
+      let res = crypto_sign_verify(mode, &sm[..signbytes], &[], &msg, &pk);
+      assert!(res.is_ok());
+    }
   }
 }
@@ -12,15 +12,12 @@ announce(){
 RUSTFLAGS=${RUSTFLAGS:-""}
 RUSTFLAGS+=" --cfg dilithium_kat"
 
-MODE=("mode2" "mode3" "mode5")
+# Every mode is exercised in a single run, so only the features are varied
+announce "Dilithium"
+RUSTFLAGS=$RUSTFLAGS cargo test
 
-for mode in ${MODE[@]}; do
-  announce "Dilithium $mode"
-  RUSTFLAGS=$RUSTFLAGS cargo test --features "$mode"
+announce "Dilithium AES"
+RUSTFLAGS=$RUSTFLAGS cargo test --features "aes"
 
-  announce "Dilithium $mode AES"
-  RUSTFLAGS=$RUSTFLAGS cargo test --features "$mode aes"
-
-  announce "Dilithium $mode Random Signing"
-  RUSTFLAGS=$RUSTFLAGS cargo test --features "$mode random_signing"
-done
+announce "Dilithium Random Signing"
+RUSTFLAGS=$RUSTFLAGS cargo test --features "random_signing"
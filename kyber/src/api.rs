@@ -0,0 +1,120 @@
+use crate::{
+  params::*,
+  error::KyberError,
+  RngCore, CryptoRng,
+  kem::*,
+};
+
+/// A shared secret
+pub type SharedSecret = [u8; KYBER_SSBYTES];
+
+/// The result of encapsulating a public key, ie the ciphertext and the
+/// shared secret
+pub type Encapsulated = Result<(Vec<u8>, SharedSecret), KyberError>;
+
+/// The result of decapsulating a ciphertext
+pub type Decapsulated = Result<SharedSecret, KyberError>;
+
+/// Keypair generation at the given security level, with a provided RNG.
+///
+/// ### Example
+/// ```
+/// # use pqc_kyber::*;
+/// let mut rng = rand::thread_rng();
+/// let keys = keypair(KyberLevel::Kyber768, &mut rng);
+/// assert_eq!(keys.public.len(), KyberLevel::Kyber768.public_key_bytes());
+/// ```
+pub fn keypair<R>(level: KyberLevel, rng: &mut R) -> Keypair
+  where R: RngCore + CryptoRng
+{
+  let mut public = vec![0u8; level.public_key_bytes()];
+  let mut secret = vec![0u8; level.secret_key_bytes()];
+  crypto_kem_keypair(level, &mut public, &mut secret, rng, None);
+  Keypair { level, public, secret }
+}
+
+/// Encapsulates a public key of the given security level, returning the
+/// ciphertext to send and the shared secret. ML-KEM public keys also have
+/// to pass the modulus check of FIPS 203.
+///
+/// ### Example
+/// ```
+/// # use pqc_kyber::*;
+/// # fn main() -> Result<(), KyberError> {
+/// let mut rng = rand::thread_rng();
+/// let keys = keypair(KyberLevel::Kyber512, &mut rng);
+/// let (ciphertext, shared_secret) =
+///   encapsulate(KyberLevel::Kyber512, &keys.public, &mut rng)?;
+/// # Ok(())}
+/// ```
+pub fn encapsulate<R>(level: KyberLevel, pk: &[u8], rng: &mut R) -> Encapsulated
+  where R: CryptoRng + RngCore
+{
+  if pk.len() != level.public_key_bytes() {
+    return Err(KyberError::InvalidInput)
+  }
+  if level.is_ml_kem() && !crypto_kem_check_pk(level, pk) {
+    return Err(KyberError::InvalidInput)
+  }
+  let mut ct = vec![0u8; level.ciphertext_bytes()];
+  let mut ss = [0u8; KYBER_SSBYTES];
+  crypto_kem_enc(level, &mut ct, &mut ss, pk, rng, None);
+  Ok((ct, ss))
+}
+
+/// Decapsulates ciphertext with a secret key of the given security
+/// level, the result will contain a KyberError if decapsulation fails.
+///
+/// ML-KEM rejects implicitly instead: a ciphertext that doesn't
+/// decapsulate yields a pseudo-random shared secret derived from the
+/// secret key. Besides wrong sizes, only the secret key hash check of
+/// FIPS 203 makes it fail.
+///
+/// ### Example
+/// ```
+/// # use pqc_kyber::*;
+/// # fn main() -> Result<(), KyberError> {
+/// let mut rng = rand::thread_rng();
+/// let keys = keypair(KyberLevel::Kyber1024, &mut rng);
+/// let (ct, ss1) = encapsulate(KyberLevel::Kyber1024, &keys.public, &mut rng)?;
+/// let ss2 = decapsulate(KyberLevel::Kyber1024, &ct, &keys.secret)?;
+/// assert_eq!(ss1, ss2);
+/// #  Ok(())}
+/// ```
+pub fn decapsulate(level: KyberLevel, ct: &[u8], sk: &[u8]) -> Decapsulated
+{
+  if ct.len() != level.ciphertext_bytes() || sk.len() != level.secret_key_bytes() {
+    return Err(KyberError::InvalidInput)
+  }
+  if level.is_ml_kem() && !crypto_kem_check_sk(level, sk) {
+    return Err(KyberError::InvalidInput)
+  }
+  let mut ss = [0u8; KYBER_SSBYTES];
+  match crypto_kem_dec(level, &mut ss, ct, sk) {
+    Ok(_) => Ok(ss),
+    Err(e) => Err(e)
+  }
+}
+
+/// A public/secret keypair for use with Kyber.
+///
+/// Byte lengths of the keys are determined by the security level chosen.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Keypair {
+    pub level: KyberLevel,
+    pub public: Vec<u8>,
+    pub secret: Vec<u8>
+}
+
+impl Keypair {
+  /// Securely generates a new keypair at the given security level
+  /// ```
+  /// # use pqc_kyber::*;
+  /// let mut rng = rand::thread_rng();
+  /// let keys = Keypair::generate(KyberLevel::Kyber1024, &mut rng);
+  /// assert_eq!(keys.secret.len(), KyberLevel::Kyber1024.secret_key_bytes());
+  /// ```
+  pub fn generate<R: CryptoRng + RngCore>(level: KyberLevel, rng: &mut R) -> Keypair {
+    keypair(level, rng)
+  }
+}
@@ -0,0 +1,179 @@
+use crate::rng::randombytes;
+use rand_core::{RngCore, CryptoRng};
+use crate::{
+  params::*,
+  indcpa::*,
+  symmetric::*,
+  error::KyberError,
+  verify::*
+};
+
+// Name:        crypto_kem_keypair
+//
+// Description: Generates public and private key
+//              for CCA-secure Kyber key encapsulation mechanism
+//
+// Arguments:   - KyberLevel level: security level
+//              - [u8] pk: output public key (an already allocated array of the level's public_key_bytes)
+//              - [u8] sk: output private key (an already allocated array of the level's secret_key_bytes)
+pub fn crypto_kem_keypair<R>(
+  level: KyberLevel, pk: &mut[u8], sk: &mut[u8], _rng: &mut R, _seed: Option<(&[u8], &[u8])>
+)
+  where R: RngCore + CryptoRng
+{
+  let pk_start = level.secret_key_bytes() - (2 * KYBER_SYMBYTES);
+  let sk_start = level.secret_key_bytes() - KYBER_SYMBYTES;
+  let end = level.indcpa_publickeybytes() + level.indcpa_secretkeybytes();
+
+  indcpa_keypair(level, pk, sk, _seed, _rng);
+
+  sk[level.indcpa_secretkeybytes()..end]
+    .copy_from_slice(&pk[..level.indcpa_publickeybytes()]);
+  hash_h(&mut sk[pk_start..], pk, level.public_key_bytes());
+
+  if let Some(s) = _seed {
+    sk[sk_start..].copy_from_slice(s.1)
+  } else {
+    randombytes(&mut sk[sk_start..],KYBER_SYMBYTES, _rng);
+  }
+}
+
+// Name:        crypto_kem_enc
+//
+// Description: Generates cipher text and shared
+//              secret for given public key
+//
+// Arguments:   - KyberLevel level: security level
+//              - [u8] ct:       output cipher text (an already allocated array of the level's ciphertext_bytes)
+//              - [u8] ss:       output shared secret (an already allocated array of CRYPTO_BYTES bytes)
+//              - const [u8] pk: input public key (an already allocated array of the level's public_key_bytes)
+pub fn crypto_kem_enc<R>(
+  level: KyberLevel, ct: &mut[u8], ss: &mut[u8], pk: &[u8], _rng: &mut R,_seed: Option<&[u8]>
+)
+  where R: RngCore + CryptoRng
+{
+  let mut kr = [0u8; 2*KYBER_SYMBYTES];
+  let mut buf = [0u8; 2*KYBER_SYMBYTES];
+  let mut randbuf = [0u8; 2*KYBER_SYMBYTES];
+
+  // Deterministic randbuf for KAT's
+  if let Some(s) = _seed {
+    randbuf[..KYBER_SYMBYTES].copy_from_slice(s);
+  } else {
+    randombytes(&mut randbuf, KYBER_SYMBYTES, _rng);
+  }
+
+  // Don't release system RNG output, ML-KEM uses it as is
+  if level.is_ml_kem() {
+    buf[..KYBER_SYMBYTES].copy_from_slice(&randbuf[..KYBER_SYMBYTES]);
+  } else {
+    hash_h(&mut buf, &randbuf, KYBER_SYMBYTES);
+  }
+
+  // Multitarget countermeasure for coins + contributory KEM
+  hash_h(&mut buf[KYBER_SYMBYTES..], pk, level.public_key_bytes());
+  hash_g(&mut kr, &buf, 2*KYBER_SYMBYTES);
+
+  // coins are in kr[KYBER_SYMBYTES..]
+  indcpa_enc(level, ct, &buf, pk, &kr[KYBER_SYMBYTES..]);
+
+  if level.is_ml_kem() {
+    // pre-k is the shared secret
+    ss[..KYBER_SSBYTES].copy_from_slice(&kr[..KYBER_SYMBYTES]);
+    return;
+  }
+
+  // overwrite coins in kr with H(c)
+  hash_h(&mut kr[KYBER_SYMBYTES..], ct, level.ciphertext_bytes());
+
+  // hash concatenation of pre-k and H(c) to k
+  kdf(ss, &kr, 2*KYBER_SYMBYTES);
+}
+
+// Name:        crypto_kem_dec
+//
+// Description: Generates shared secret for given
+//              cipher text and private key
+//
+// Arguments:   - KyberLevel level: security level
+//              - [u8] ss:       output shared secret (an already allocated array of CRYPTO_BYTES bytes)
+//              - const [u8] ct: input cipher text (an already allocated array of the level's ciphertext_bytes)
+//              - const [u8] sk: input private key (an already allocated array of the level's secret_key_bytes)
+//
+// On failure, ss will contain a pseudo-random value. For ML-KEM that is
+// the implicit rejection key of FIPS 203, and no error is returned.
+pub fn crypto_kem_dec(
+  level: KyberLevel, ss: &mut[u8], ct: &[u8], sk: &[u8]
+)
+-> Result<(), KyberError>
+{
+  let mut buf = [0u8; 2*KYBER_SYMBYTES];
+  let mut kr = [0u8; 2*KYBER_SYMBYTES];
+  let mut cmp = vec![0u8; level.ciphertext_bytes()];
+  let pk = &sk[level.indcpa_secretkeybytes()..][..level.indcpa_publickeybytes()];
+
+  indcpa_dec(level, &mut buf, ct, sk);
+
+  // Multitarget countermeasure for coins + contributory KEM
+  let start = level.secret_key_bytes() - 2*KYBER_SYMBYTES;
+  let end = level.secret_key_bytes() - KYBER_SYMBYTES;
+  buf[KYBER_SYMBYTES..].copy_from_slice(&sk[start..end]);
+  hash_g(&mut kr, &buf, 2*KYBER_SYMBYTES);
+
+  // coins are in kr[KYBER_SYMBYTES..]
+  indcpa_enc(level, &mut cmp, &buf, pk, &kr[KYBER_SYMBYTES..]);
+  let fail = verify(ct, &cmp, level.ciphertext_bytes());
+
+  if level.is_ml_kem() {
+    // Overwrite pre-k with SHAKE256(z || c) on re-encryption failure
+    let mut rejection = [0u8; KYBER_SSBYTES];
+    rkprf(&mut rejection, &sk[end..], ct);
+    cmov(&mut kr, &rejection, KYBER_SYMBYTES, fail);
+    ss[..KYBER_SSBYTES].copy_from_slice(&kr[..KYBER_SYMBYTES]);
+    return Ok(());
+  }
+
+  // overwrite coins in kr with H(c)
+  hash_h(&mut kr[KYBER_SYMBYTES..], ct, level.ciphertext_bytes());
+  // Overwrite pre-k with z on re-encryption failure
+  cmov(&mut kr, &sk[end..], KYBER_SYMBYTES, fail);
+  // hash concatenation of pre-k and H(c) to k
+  kdf(ss, &kr, 2*KYBER_SYMBYTES);
+
+  match fail {
+    0 => Ok(()),
+    _ => Err(KyberError::Decapsulation)
+  }
+}
+
+// Name:        crypto_kem_check_pk
+//
+// Description: Modulus check of FIPS 203 on an encapsulation key, ie
+//              whether every packed coefficient is reduced mod q
+//
+// Arguments:   - KyberLevel level: security level
+//              - const [u8] pk: input public key (an already allocated array of the level's public_key_bytes)
+pub fn crypto_kem_check_pk(level: KyberLevel, pk: &[u8]) -> bool
+{
+  pk[..level.polyvecbytes()].chunks(3).all(|b| {
+    let a0 = (b[0] as u16) | ((b[1] as u16 & 0xf) << 8);
+    let a1 = ((b[1] >> 4) as u16) | ((b[2] as u16) << 4);
+    (a0 as usize) < KYBER_Q && (a1 as usize) < KYBER_Q
+  })
+}
+
+// Name:        crypto_kem_check_sk
+//
+// Description: Hash check of FIPS 203 on a decapsulation key, ie whether
+//              the embedded hash matches the embedded public key
+//
+// Arguments:   - KyberLevel level: security level
+//              - const [u8] sk: input private key (an already allocated array of the level's secret_key_bytes)
+pub fn crypto_kem_check_sk(level: KyberLevel, sk: &[u8]) -> bool
+{
+  let mut h = [0u8; KYBER_SYMBYTES];
+  let pk = &sk[level.indcpa_secretkeybytes()..][..level.indcpa_publickeybytes()];
+  let start = level.secret_key_bytes() - 2*KYBER_SYMBYTES;
+  hash_h(&mut h, pk, level.public_key_bytes());
+  verify(&h, &sk[start..start + KYBER_SYMBYTES], KYBER_SYMBYTES) == 0
+}
@@ -0,0 +1,47 @@
+//! # Kyber
+//!
+//! A rust implementation of the Kyber algorithm - fork with the security
+//! level picked at runtime instead of through cargo features, so a single
+//! build can handle Kyber-512, Kyber-768 and Kyber-1024 keys alike, as
+//! well as their FIPS 203 counterparts ML-KEM-512, ML-KEM-768 and
+//! ML-KEM-1024.
+//!
+//! ```
+//! # use pqc_kyber::*;
+//! # fn main() -> Result<(), KyberError> {
+//! # let mut rng = rand::thread_rng();
+//! let keys_bob = keypair(KyberLevel::Kyber768, &mut rng);
+//!
+//! // Alice encapsulates a shared secret using Bob's public key
+//! let (ciphertext, shared_secret_alice) =
+//!   encapsulate(keys_bob.level, &keys_bob.public, &mut rng)?;
+//!
+//! // Bob decapsulates a shared secret using the ciphertext sent by Alice
+//! let shared_secret_bob =
+//!   decapsulate(keys_bob.level, &ciphertext, &keys_bob.secret)?;
+//!
+//! assert_eq!(shared_secret_alice, shared_secret_bob);
+//! # Ok(()) }
+//! ```
+
+#![allow(clippy::many_single_char_names)]
+
+mod api;
+mod cbd;
+mod error;
+mod fips202;
+mod indcpa;
+mod kem;
+mod ntt;
+mod params;
+mod poly;
+mod polyvec;
+mod reduce;
+mod rng;
+mod symmetric;
+mod verify;
+
+pub use api::*;
+pub use error::KyberError;
+pub use params::*;
+pub use rand_core::{CryptoRng, RngCore};
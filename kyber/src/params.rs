@@ -0,0 +1,118 @@
+pub(crate) const KYBER_N: usize = 256;
+pub(crate) const KYBER_Q: usize = 3329;
+
+pub(crate) const KYBER_ETA2: usize = 2;
+
+// Size of the hashes and seeds
+pub const KYBER_SYMBYTES: usize = 32;
+
+/// Size of the shared key
+pub const KYBER_SSBYTES: usize = 32;
+
+pub(crate) const KYBER_POLYBYTES: usize = 384;
+
+/// The security level of Kyber. Unlike upstream, where it's fixed at
+/// compile time through features, it's picked at runtime, so a single
+/// build can talk to peers using any of them.
+///
+/// * Kyber-512 aims at security roughly equivalent to AES-128
+/// * Kyber-768 aims at security roughly equivalent to AES-192
+/// * Kyber-1024 aims at security roughly equivalent to AES-256
+///
+/// The `MlKem*` levels are the same parameter sets as standardised in
+/// FIPS 203. Keys and ciphertexts have the same sizes, but key generation
+/// and the derivation of the shared secret differ, so the two families
+/// don't interoperate.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum KyberLevel {
+  Kyber512,
+  Kyber768,
+  Kyber1024,
+  MlKem512,
+  MlKem768,
+  MlKem1024,
+}
+
+impl KyberLevel {
+  /// Every level, the round 3 ones first, each family from the smallest
+  /// to the largest
+  pub const ALL: [KyberLevel; 6] = [
+    KyberLevel::Kyber512,
+    KyberLevel::Kyber768,
+    KyberLevel::Kyber1024,
+    KyberLevel::MlKem512,
+    KyberLevel::MlKem768,
+    KyberLevel::MlKem1024,
+  ];
+
+  /// Whether this is one of the FIPS 203 parameter sets
+  pub const fn is_ml_kem(self) -> bool {
+    matches!(
+      self,
+      KyberLevel::MlKem512 | KyberLevel::MlKem768 | KyberLevel::MlKem1024
+    )
+  }
+
+  /// Rank of the module, ie the number of polynomials in a vector
+  pub const fn k(self) -> usize {
+    match self {
+      KyberLevel::Kyber512 | KyberLevel::MlKem512 => 2,
+      KyberLevel::Kyber768 | KyberLevel::MlKem768 => 3,
+      KyberLevel::Kyber1024 | KyberLevel::MlKem1024 => 4,
+    }
+  }
+
+  pub(crate) const fn eta1(self) -> usize {
+    match self.k() {
+      2 => 3,
+      _ => 2,
+    }
+  }
+
+  pub(crate) const fn polycompressedbytes(self) -> usize {
+    match self.k() {
+      4 => 160,
+      _ => 128,
+    }
+  }
+
+  pub(crate) const fn polyveccompressedbytes(self) -> usize {
+    match self.k() {
+      4 => self.k() * 352,
+      _ => self.k() * 320,
+    }
+  }
+
+  pub(crate) const fn polyvecbytes(self) -> usize {
+    self.k() * KYBER_POLYBYTES
+  }
+
+  pub(crate) const fn indcpa_publickeybytes(self) -> usize {
+    self.polyvecbytes() + KYBER_SYMBYTES
+  }
+
+  pub(crate) const fn indcpa_secretkeybytes(self) -> usize {
+    self.polyvecbytes()
+  }
+
+  pub(crate) const fn indcpa_bytes(self) -> usize {
+    self.polyveccompressedbytes() + self.polycompressedbytes()
+  }
+
+  /// Size in bytes of the public key
+  pub const fn public_key_bytes(self) -> usize {
+    self.indcpa_publickeybytes()
+  }
+
+  /// Size in bytes of the secret key
+  pub const fn secret_key_bytes(self) -> usize {
+    self.indcpa_secretkeybytes()
+      + self.indcpa_publickeybytes()
+      + 2 * KYBER_SYMBYTES
+  }
+
+  /// Size in bytes of the ciphertext
+  pub const fn ciphertext_bytes(self) -> usize {
+    self.indcpa_bytes()
+  }
+}
@@ -0,0 +1,219 @@
+#!/usr/bin/env python3
+"""Regenerates the ML-KEM vectors in this folder, in the layout of the
+ACVP-Server JSON files (internalProjection, with the expected results
+merged in), from the FIPS 203 implementation of OpenSSL 3.5 or later.
+
+    python3 generate.py
+
+Everything is derived from a fixed seed, so the output is reproducible.
+"""
+
+import hashlib
+import json
+import os
+import re
+import subprocess
+import tempfile
+
+PARAMETER_SETS = ["ML-KEM-512", "ML-KEM-768", "ML-KEM-1024"]
+
+Q = 3329
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+TMP = tempfile.mkdtemp()
+
+
+class Drbg:
+    """Deterministic bytes, so regenerating gives the same files."""
+
+    def __init__(self, label):
+        self.state = hashlib.sha256(label.encode()).digest()
+
+    def bytes(self, n):
+        out = b""
+        while len(out) < n:
+            self.state = hashlib.sha256(self.state).digest()
+            out += self.state
+        return out[:n]
+
+    def below(self, n):
+        return int.from_bytes(self.bytes(4), "big") % n
+
+
+def openssl(*args, stdin=None):
+    return subprocess.run(
+        ["openssl", *args], input=stdin, capture_output=True, check=True
+    ).stdout
+
+
+def path(name):
+    return os.path.join(TMP, name)
+
+
+def read(name):
+    with open(path(name), "rb") as f:
+        return f.read()
+
+
+def keygen(parameter_set, d, z):
+    openssl(
+        "genpkey", "-algorithm", parameter_set,
+        "-pkeyopt", "hexseed:" + (d + z).hex(), "-out", path("key.pem"),
+    )
+    openssl("pkey", "-in", path("key.pem"), "-pubout", "-out", path("pub.pem"))
+    text = openssl("pkey", "-in", path("key.pem"), "-text", "-noout").decode()
+    fields = {}
+    for name, body in re.findall(r"^(\w+):\n((?:\s+[0-9a-f:]+\n)+)", text, re.M):
+        fields[name] = bytes.fromhex(re.sub(r"[\s:]", "", body))
+    return fields["ek"], fields["dk"]
+
+
+def encapsulate(m):
+    """Encapsulates to the last generated key with the given randomness."""
+    openssl(
+        "pkeyutl", "-encap", "-pubin", "-inkey", path("pub.pem"),
+        "-out", path("ct"), "-secret", path("ss"),
+        "-pkeyopt", "hexikme:" + m.hex(),
+    )
+    return read("ct"), read("ss")
+
+
+def decapsulate(c):
+    """Decapsulates with the last generated key."""
+    with open(path("ct"), "wb") as f:
+        f.write(c)
+    openssl(
+        "pkeyutl", "-decap", "-inkey", path("key.pem"),
+        "-in", path("ct"), "-secret", path("ss"),
+    )
+    return read("ss")
+
+
+def document(mode, groups):
+    return {
+        "vsId": 0,
+        "algorithm": "ML-KEM",
+        "mode": mode,
+        "revision": "FIPS203",
+        "isSample": True,
+        "testGroups": groups,
+    }
+
+
+def write(name, doc):
+    with open(os.path.join(HERE, name), "w") as f:
+        json.dump(doc, f, indent=2)
+        f.write("\n")
+
+
+def keygen_vectors():
+    rng, groups, tc = Drbg("keyGen"), [], 0
+    for tg, parameter_set in enumerate(PARAMETER_SETS, 1):
+        tests = []
+        for _ in range(5):
+            tc += 1
+            d, z = rng.bytes(32), rng.bytes(32)
+            ek, dk = keygen(parameter_set, d, z)
+            tests.append({
+                "tcId": tc, "deferred": False,
+                "z": z.hex().upper(), "d": d.hex().upper(),
+                "ek": ek.hex().upper(), "dk": dk.hex().upper(),
+            })
+        groups.append({
+            "tgId": tg, "testType": "AFT", "parameterSet": parameter_set,
+            "tests": tests,
+        })
+    return document("keyGen", groups)
+
+
+def unreduced(ek, rng):
+    """The key with one coefficient of t replaced by one >= q."""
+    ek = bytearray(ek)
+    i = 3 * rng.below((len(ek) - 32) // 3)
+    a = Q + rng.below(4096 - Q)
+    ek[i] = a & 0xFF
+    ek[i + 1] = (ek[i + 1] & 0xF0) | (a >> 8)
+    return bytes(ek)
+
+
+def encap_decap_vectors():
+    rng, groups, tg, tc = Drbg("encapDecap"), [], 0, 0
+    for parameter_set in PARAMETER_SETS:
+        tg += 1
+        tests = []
+        for _ in range(5):
+            tc += 1
+            ek, _ = keygen(parameter_set, rng.bytes(32), rng.bytes(32))
+            m = rng.bytes(32)
+            c, k = encapsulate(m)
+            tests.append({
+                "tcId": tc, "deferred": False, "ek": ek.hex().upper(),
+                "m": m.hex().upper(), "c": c.hex().upper(), "k": k.hex().upper(),
+            })
+        groups.append({
+            "tgId": tg, "testType": "AFT", "parameterSet": parameter_set,
+            "function": "encapsulation", "tests": tests,
+        })
+
+    for parameter_set in PARAMETER_SETS:
+        tg += 1
+        tests = []
+        for reason in ["valid decapsulation", "modified ciphertext"] * 3:
+            tc += 1
+            _, dk = keygen(parameter_set, rng.bytes(32), rng.bytes(32))
+            c, _ = encapsulate(rng.bytes(32))
+            if reason == "modified ciphertext":
+                c = bytearray(c)
+                c[rng.below(len(c))] ^= 1 << rng.below(8)
+                c = bytes(c)
+            k = decapsulate(c)
+            tests.append({
+                "tcId": tc, "deferred": False, "dk": dk.hex().upper(),
+                "c": c.hex().upper(), "k": k.hex().upper(), "reason": reason,
+            })
+        groups.append({
+            "tgId": tg, "testType": "VAL", "parameterSet": parameter_set,
+            "function": "decapsulation", "tests": tests,
+        })
+
+    for parameter_set in PARAMETER_SETS:
+        tg += 1
+        tests = []
+        for reason in ["valid encapsulation key", "modulus overflow"] * 2:
+            tc += 1
+            ek, _ = keygen(parameter_set, rng.bytes(32), rng.bytes(32))
+            if reason == "modulus overflow":
+                ek = unreduced(ek, rng)
+            tests.append({
+                "tcId": tc, "deferred": False, "ek": ek.hex().upper(),
+                "testPassed": reason.startswith("valid"), "reason": reason,
+            })
+        groups.append({
+            "tgId": tg, "testType": "VAL", "parameterSet": parameter_set,
+            "function": "encapsulationKeyCheck", "tests": tests,
+        })
+
+    for parameter_set in PARAMETER_SETS:
+        tg += 1
+        tests = []
+        for reason in ["valid decapsulation key", "modified H"] * 2:
+            tc += 1
+            _, dk = keygen(parameter_set, rng.bytes(32), rng.bytes(32))
+            if reason == "modified H":
+                dk = bytearray(dk)
+                dk[-64 + rng.below(32)] ^= 1 << rng.below(8)
+                dk = bytes(dk)
+            tests.append({
+                "tcId": tc, "deferred": False, "dk": dk.hex().upper(),
+                "testPassed": reason.startswith("valid"), "reason": reason,
+            })
+        groups.append({
+            "tgId": tg, "testType": "VAL", "parameterSet": parameter_set,
+            "function": "decapsulationKeyCheck", "tests": tests,
+        })
+    return document("encapDecap", groups)
+
+
+if __name__ == "__main__":
+    write("ML-KEM-keyGen-FIPS203.json", keygen_vectors())
+    write("ML-KEM-encapDecap-FIPS203.json", encap_decap_vectors())
@@ -0,0 +1,149 @@
+use pqc_kyber::*;
+use serde_json::Value;
+use std::path::PathBuf;
+
+fn vectors(name: &str) -> Value {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.extend(["tests", "ACVP", name].iter());
+  let file = std::fs::read_to_string(path).unwrap();
+  serde_json::from_str(&file).unwrap()
+}
+
+fn bytes(value: &Value) -> Vec<u8> {
+  let hex = value.as_str().unwrap();
+  (0..hex.len())
+    .step_by(2)
+    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+    .collect()
+}
+
+fn level(group: &Value) -> KyberLevel {
+  match group["parameterSet"].as_str().unwrap() {
+    "ML-KEM-512" => KyberLevel::MlKem512,
+    "ML-KEM-768" => KyberLevel::MlKem768,
+    "ML-KEM-1024" => KyberLevel::MlKem1024,
+    other => panic!("unknown parameter set {}", other),
+  }
+}
+
+fn groups<'a>(
+  vectors: &'a Value,
+  function: &'a str,
+) -> impl Iterator<Item = &'a Value> {
+  vectors["testGroups"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .filter(move |group| group["function"] == function)
+}
+
+fn tests(group: &Value) -> impl Iterator<Item = &Value> {
+  group["tests"].as_array().unwrap().iter()
+}
+
+/// Hands out the randomness of a test vector, in order, in place of a
+/// real RNG
+struct Replay(Vec<u8>);
+
+impl RngCore for Replay {
+  fn next_u32(&mut self) -> u32 {
+    rand_core::impls::next_u32_via_fill(self)
+  }
+
+  fn next_u64(&mut self) -> u64 {
+    rand_core::impls::next_u64_via_fill(self)
+  }
+
+  fn fill_bytes(&mut self, dest: &mut [u8]) {
+    let rest = self.0.split_off(dest.len());
+    dest.copy_from_slice(&self.0);
+    self.0 = rest;
+  }
+
+  fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+    self.fill_bytes(dest);
+    Ok(())
+  }
+}
+
+impl CryptoRng for Replay {}
+
+#[test]
+fn keygen() {
+  let vectors = vectors("ML-KEM-keyGen-FIPS203.json");
+  for group in vectors["testGroups"].as_array().unwrap() {
+    let level = level(group);
+    for test in tests(group) {
+      let mut rng = Replay([bytes(&test["d"]), bytes(&test["z"])].concat());
+      let keys = keypair(level, &mut rng);
+      assert_eq!(keys.public, bytes(&test["ek"]), "tcId {}", test["tcId"]);
+      assert_eq!(keys.secret, bytes(&test["dk"]), "tcId {}", test["tcId"]);
+    }
+  }
+}
+
+#[test]
+fn encapsulation() {
+  let vectors = vectors("ML-KEM-encapDecap-FIPS203.json");
+  for group in groups(&vectors, "encapsulation") {
+    let level = level(group);
+    for test in tests(group) {
+      let mut rng = Replay(bytes(&test["m"]));
+      let (c, k) = encapsulate(level, &bytes(&test["ek"]), &mut rng).unwrap();
+      assert_eq!(c, bytes(&test["c"]), "tcId {}", test["tcId"]);
+      assert_eq!(&k[..], &bytes(&test["k"])[..], "tcId {}", test["tcId"]);
+    }
+  }
+}
+
+#[test]
+fn decapsulation() {
+  let vectors = vectors("ML-KEM-encapDecap-FIPS203.json");
+  for group in groups(&vectors, "decapsulation") {
+    let level = level(group);
+    for test in tests(group) {
+      let (c, dk) = (bytes(&test["c"]), bytes(&test["dk"]));
+      let k = decapsulate(level, &c, &dk).unwrap();
+      assert_eq!(
+        &k[..],
+        &bytes(&test["k"])[..],
+        "tcId {}: {}",
+        test["tcId"],
+        test["reason"]
+      );
+    }
+  }
+}
+
+#[test]
+fn key_checks() {
+  let mut rng = rand::thread_rng();
+  let vectors = vectors("ML-KEM-encapDecap-FIPS203.json");
+  for group in groups(&vectors, "encapsulationKeyCheck") {
+    let level = level(group);
+    for test in tests(group) {
+      let result = encapsulate(level, &bytes(&test["ek"]), &mut rng);
+      assert_eq!(
+        result.is_ok(),
+        test["testPassed"].as_bool().unwrap(),
+        "tcId {}: {}",
+        test["tcId"],
+        test["reason"]
+      );
+    }
+  }
+  for group in groups(&vectors, "decapsulationKeyCheck") {
+    let level = level(group);
+    let ct = vec![0u8; level.ciphertext_bytes()];
+    for test in tests(group) {
+      let result = decapsulate(level, &ct, &bytes(&test["dk"]));
+      assert_eq!(
+        result.is_ok(),
+        test["testPassed"].as_bool().unwrap(),
+        "tcId {}: {}",
+        test["tcId"],
+        test["reason"]
+      );
+    }
+  }
+}
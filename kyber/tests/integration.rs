@@ -0,0 +1,102 @@
+use pqc_kyber::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+#[test]
+fn keypair_encapsulate_decapsulate() {
+  let mut rng = rand::thread_rng();
+  for level in KyberLevel::ALL {
+    let keys = keypair(level, &mut rng);
+    assert_eq!(keys.public.len(), level.public_key_bytes());
+    assert_eq!(keys.secret.len(), level.secret_key_bytes());
+    let (ct, ss1) = encapsulate(level, &keys.public, &mut rng).unwrap();
+    assert_eq!(ct.len(), level.ciphertext_bytes());
+    let ss2 = decapsulate(level, &ct, &keys.secret).unwrap();
+    assert_eq!(ss1, ss2);
+  }
+}
+
+#[test]
+fn invalid_ciphertext() {
+  let mut rng = rand::thread_rng();
+  for level in KyberLevel::ALL.iter().filter(|level| !level.is_ml_kem()) {
+    let keys = keypair(*level, &mut rng);
+    let (mut ct, _) = encapsulate(*level, &keys.public, &mut rng).unwrap();
+    ct[..4].copy_from_slice(&[255u8; 4]);
+    assert!(decapsulate(*level, &ct, &keys.secret).is_err());
+  }
+}
+
+#[test]
+fn ml_kem_rejects_implicitly() {
+  let mut rng = rand::thread_rng();
+  for level in KyberLevel::ALL.iter().filter(|level| level.is_ml_kem()) {
+    let keys = keypair(*level, &mut rng);
+    let (mut ct, ss) = encapsulate(*level, &keys.public, &mut rng).unwrap();
+    ct[..4].copy_from_slice(&[255u8; 4]);
+    let rejected = decapsulate(*level, &ct, &keys.secret).unwrap();
+    assert_ne!(rejected, ss);
+    // The rejection key only depends on the secret key and the ciphertext
+    assert_eq!(decapsulate(*level, &ct, &keys.secret).unwrap(), rejected);
+  }
+}
+
+#[test]
+fn kyber_and_ml_kem_differ() {
+  let mut rng = ChaCha20Rng::seed_from_u64(0);
+  let kyber = keypair(KyberLevel::Kyber768, &mut rng);
+  let mut rng = ChaCha20Rng::seed_from_u64(0);
+  let ml_kem = keypair(KyberLevel::MlKem768, &mut rng);
+  assert_eq!(kyber.public.len(), ml_kem.public.len());
+  assert_ne!(kyber.public, ml_kem.public);
+}
+
+#[test]
+fn mismatched_levels() {
+  let mut rng = rand::thread_rng();
+  let keys = keypair(KyberLevel::Kyber1024, &mut rng);
+  assert_eq!(
+    encapsulate(KyberLevel::Kyber768, &keys.public, &mut rng),
+    Err(KyberError::InvalidInput)
+  );
+}
+
+// Shared secrets upstream produces for each level, with the same
+// deterministic RNG, so the runtime levels stay compatible with the
+// compile-time ones.
+#[test]
+fn matches_upstream() {
+  let expected: [(KyberLevel, [u8; KYBER_SSBYTES]); 3] = [
+    (
+      KyberLevel::Kyber512,
+      [
+        0xe0, 0x2c, 0x7d, 0x3b, 0x38, 0x52, 0x32, 0xfe, 0xd9, 0xb4, 0xdb, 0xe1,
+        0x04, 0xbd, 0xbd, 0xa6, 0x24, 0x99, 0x7c, 0x19, 0x07, 0xc5, 0xc5, 0xa3,
+        0x38, 0xa5, 0xca, 0xfe, 0x45, 0xdc, 0xc8, 0xfa,
+      ],
+    ),
+    (
+      KyberLevel::Kyber768,
+      [
+        0x90, 0x09, 0x66, 0xc2, 0x9a, 0xb6, 0xf3, 0x5b, 0x9a, 0xa2, 0xc3, 0x92,
+        0x7d, 0xc6, 0xe0, 0xfb, 0x3a, 0x7c, 0xda, 0xb1, 0x10, 0xca, 0x7f, 0xc1,
+        0x1a, 0xb3, 0x00, 0x17, 0x94, 0xfd, 0xb8, 0x75,
+      ],
+    ),
+    (
+      KyberLevel::Kyber1024,
+      [
+        0x84, 0xcd, 0x0e, 0xe7, 0x4d, 0x79, 0x35, 0xde, 0xa1, 0xe6, 0x52, 0x4f,
+        0x70, 0x03, 0xdd, 0xc7, 0xd2, 0x25, 0x3d, 0xd6, 0x6d, 0x12, 0x42, 0xec,
+        0x84, 0x40, 0x68, 0xcc, 0x82, 0x9d, 0xcd, 0x63,
+      ],
+    ),
+  ];
+  for (level, ss) in expected.iter() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let keys = keypair(*level, &mut rng);
+    let (ct, ss1) = encapsulate(*level, &keys.public, &mut rng).unwrap();
+    assert_eq!(&ss1, ss);
+    assert_eq!(&decapsulate(*level, &ct, &keys.secret).unwrap(), ss);
+  }
+}
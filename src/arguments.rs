@@ -1,7 +1,20 @@
 // -- imports --
 use crate::core::*;
 use clap::Parser;
-use std::error::Error;
+use std::{
+    error::Error,
+    fs::File,
+    io::{
+        self,
+        BufWriter,
+        Read,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+};
 
 // -- clap options --
 /// Mirai's experimental, quantum-safe successor to GPG
@@ -13,18 +26,82 @@ pub struct Args {
     #[arg(short, long)]
     pub gen_key: bool,
 
+    /// Makes `--gen-key` generate a hybrid key set, which adds X25519
+    /// and Ed25519 keys to the Kyber and Dilithium ones. Messages
+    /// encrypted to it stay safe as long as either algorithm does, and
+    /// its signatures need both to be broken before they can be forged
+    #[arg(long)]
+    pub hybrid: bool,
+
+    /// Makes `--gen-key` generate a Kyber key of the given security
+    /// level (ml-kem-512, ml-kem-768 or ml-kem-1024, or 512, 768 or 1024
+    /// for the older round 3 Kyber) instead of ML-KEM-1024. Smaller
+    /// levels mean smaller keys and messages, for peers that can't afford
+    /// the bigger ones, and still work with keys of every other level
+    #[arg(long, value_name = "LEVEL", value_parser = parse_kyber_level)]
+    pub kyber_level: Option<KdtKyberLevel>,
+
+    /// Makes `--gen-key` generate a Dilithium key of the given parameter
+    /// set (44, 65 or 87 for ML-DSA, 2, 3 or 5 for the older round 3
+    /// Dilithium) instead of ML-DSA-65. Signatures of every level can be
+    /// verified side by side
+    #[arg(long, value_name = "LEVEL", value_parser = parse_dilithium_level)]
+    pub dilithium_level: Option<KdtDilithiumLevel>,
+
+    /// Makes `--gen-key` generate an SLH-DSA key instead of a Dilithium
+    /// one, of the given parameter set (128s, 128f, 192s, 192f, 256s or
+    /// 256f, 256s if left out). Its signatures only rest on the security
+    /// of SHAKE256, for documents that must still verify decades from
+    /// now, but they're much bigger and slower to make
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        num_args = 0..=1,
+        default_missing_value = "256s",
+        value_parser = parse_slh_dsa_level
+    )]
+    pub slh_dsa: Option<KdtSlhDsaLevel>,
+
     /// Imports a KDT public key from stdin and stores it in the
     /// local public key database
     #[arg(short, long)]
     pub import: bool,
 
-    /// Launches KDT in decryption mode
-    #[arg(short, long, value_name = "PRIVATE_KEY_ID")]
-    pub decrypt: Option<String>,
+    /// Launches KDT in decryption mode. The private key id is
+    /// optional: without it, KDT picks whichever owned key set the
+    /// message was encrypted to
+    #[arg(short, long, value_name = "PRIVATE_KEY_ID", num_args = 0..=1)]
+    pub decrypt: Option<Option<String>>,
 
-    /// Launches KDT in encryption mode
-    #[arg(short, long, value_name = "PUBLIC_KEY_ID")]
-    pub encrypt: Option<String>,
+    /// Launches KDT in passphrase encryption mode, for recipients
+    /// who don't have a KDT key
+    #[arg(short = 'c', long)]
+    pub symmetric: bool,
+
+    /// Launches KDT in encryption mode. Pass several comma-separated
+    /// ids to encrypt the same message to multiple recipients
+    #[arg(
+        short,
+        long,
+        value_name = "PUBLIC_KEY_ID",
+        num_args = 1..,
+        value_delimiter = ','
+    )]
+    pub encrypt: Option<Vec<String>>,
+
+    /// Makes `--encrypt` leave the recipients' key ids out of the
+    /// message, so it doesn't reveal who it's for. Recipients have to
+    /// try each of their owned key sets to decrypt it, so don't pass a
+    /// private key id to `--decrypt` unless you know which one it is
+    #[arg(long)]
+    pub anonymous: bool,
+
+    /// Makes `--encrypt` or `--symmetric` seal the message with the given
+    /// AEAD instead of AES-256-GCM. ChaCha20-Poly1305 is faster on CPUs
+    /// without AES instructions, and XChaCha20-Poly1305's longer nonces
+    /// never realistically collide
+    #[arg(long, value_name = "AEAD", value_parser = parse_aead)]
+    pub aead: Option<KdtAead>,
 
     /// Lists all keys in the public key database
     #[arg(long)]
@@ -49,14 +126,82 @@ pub struct Args {
     #[arg(long, value_name = "PRIVATE_KEY_ID")]
     pub del_keyset: Option<String>,
 
-    /// Signs a message with the given private key
+    /// Signs a message with the given private key. Combine it with
+    /// `--encrypt` to sign the message, then encrypt the signed result
     #[arg(short, long, value_name = "PRIVATE_KEY_ID")]
     pub sign: Option<String>,
 
     /// Verifies the integrity of the given signed message
-    /// against the given public key
-    #[arg(short, long, value_name = "PUBLIC_KEY_ID")]
-    pub verify: Option<String>,
+    /// against the given public key. The public key id is optional:
+    /// without it, KDT looks for the signer in the public key database
+    #[arg(short, long, value_name = "PUBLIC_KEY_ID", num_args = 0..=1)]
+    pub verify: Option<Option<String>>,
+
+    /// Makes `--sign` write a detached signature over the raw bytes of
+    /// the input instead of a signed message, and makes `--verify` check
+    /// one. Signatures are written next to the `--input` file with a
+    /// `.kdtsig` extension, unless `--output` says otherwise. Use this
+    /// for release artifacts and anything else that isn't text
+    #[arg(long)]
+    pub detach: bool,
+
+    /// Makes `--verify` check the input against the signing policy in
+    /// `FILE`, which lists the public key ids whose signatures count and
+    /// how many of them are needed, and report whoever's missing. KDT
+    /// exits with status 1 unless the policy is satisfied
+    #[arg(long, value_name = "FILE")]
+    pub policy: Option<PathBuf>,
+
+    /// Makes `--sign` add a signature to an already signed message, or
+    /// with `--detach`, to an existing detached signature, keeping the
+    /// signatures of everyone else who signed it
+    #[arg(long)]
+    pub cosign: bool,
+
+    /// Adds a `KEY=VALUE` notation to the signed metadata of the
+    /// signature `--sign` makes. Can be passed several times
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_notation)]
+    pub notation: Vec<(String, String)>,
+
+    /// Makes `--sign` hash the message with the given algorithm (sha512
+    /// or shake256) and sign the digest, instead of signing the message
+    /// itself, as FIPS 204 HashML-DSA for ML-DSA keys. Combined with
+    /// `--detach`, files of any size are signed and verified without
+    /// loading them into memory
+    #[arg(long, value_name = "ALGORITHM", value_parser = parse_hash)]
+    pub hash: Option<KdtSignatureHash>,
+
+    /// Binds the signature `--sign` makes to the context string `TEXT`,
+    /// of at most 255 bytes, so it can't be passed off as made for
+    /// another purpose. It's recorded in the signature, and shown by
+    /// `--verify`
+    #[arg(long, value_name = "TEXT", value_parser = parse_context)]
+    pub context: Option<String>,
+
+    /// The detached signature `--verify --detach` checks, instead of the
+    /// `.kdtsig` file next to the `--input` file
+    #[arg(long, value_name = "FILE")]
+    pub signature: Option<PathBuf>,
+
+    /// Encrypts or decrypts in streaming mode, which works on raw
+    /// bytes in fixed-size chunks instead of loading the whole input
+    /// into memory. Use this for large files
+    #[arg(long)]
+    pub stream: bool,
+
+    /// Encrypts or decrypts in binary mode, which reads the input and
+    /// writes the output as raw bytes. Use this for anything that isn't
+    /// text, like archives or images
+    #[arg(long)]
+    pub binary: bool,
+
+    /// Reads the input from `FILE` instead of stdin
+    #[arg(long, value_name = "FILE")]
+    pub input: Option<PathBuf>,
+
+    /// Writes the output to `FILE` instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
 }
 
 impl Args {
@@ -67,6 +212,7 @@ impl Args {
             self.list_keys,
             self.list_key_pairs,
             self.encrypt.is_some(),
+            self.symmetric,
             self.export_pubkey.is_some(),
             self.del_pubkey.is_some(),
             self.del_keyset.is_some(),
@@ -81,9 +227,219 @@ impl Args {
 
     #[inline(always)]
     pub fn fail_if_invalid(&self) -> Result<(), Box<dyn Error>> {
-        if self.get_num_called() > 1 {
+        // Signing and encrypting at once is the only combination allowed.
+        let max_called = if self.is_sign_and_encrypt() { 2 } else { 1 };
+        if self.get_num_called() > max_called {
             return Err(Box::new(KdtErr::TooManyArgs));
         }
+        if (self.is_sign_and_encrypt() && (self.stream || self.binary))
+            || (self.symmetric && self.stream)
+        {
+            return Err(Box::new(KdtErr::UnsupportedCombination));
+        }
+        if self.hybrid && !self.gen_key {
+            return Err(Box::new(KdtErr::HybridNeedsGenKey));
+        }
+        if self.kyber_level.is_some() && !self.gen_key {
+            return Err(Box::new(KdtErr::KyberLevelNeedsGenKey));
+        }
+        if self.dilithium_level.is_some() && !self.gen_key {
+            return Err(Box::new(KdtErr::DilithiumLevelNeedsGenKey));
+        }
+        if self.slh_dsa.is_some() && !self.gen_key {
+            return Err(Box::new(KdtErr::SlhDsaNeedsGenKey));
+        }
+        if self.slh_dsa.is_some() && self.dilithium_level.is_some() {
+            return Err(Box::new(KdtErr::UnsupportedCombination));
+        }
+        if self.detach && (self.sign.is_none() && self.verify.is_none()) {
+            return Err(Box::new(KdtErr::DetachNeedsSignOrVerify));
+        }
+        if self.detach && self.is_sign_and_encrypt() {
+            return Err(Box::new(KdtErr::UnsupportedCombination));
+        }
+        if !self.notation.is_empty() && self.sign.is_none() {
+            return Err(Box::new(KdtErr::NotationNeedsSign));
+        }
+        if self.hash.is_some() && self.sign.is_none() {
+            return Err(Box::new(KdtErr::HashNeedsSign));
+        }
+        if self.context.is_some() && self.sign.is_none() {
+            return Err(Box::new(KdtErr::ContextNeedsSign));
+        }
+        if self.policy.is_some() && !matches!(self.verify, Some(None)) {
+            return Err(Box::new(KdtErr::PolicyNeedsVerify));
+        }
+        if self.cosign && self.sign.is_none() {
+            return Err(Box::new(KdtErr::CosignNeedsSign));
+        }
+        if self.cosign && self.is_sign_and_encrypt() {
+            return Err(Box::new(KdtErr::UnsupportedCombination));
+        }
+        if self.signature.is_some() && !(self.detach && (self.verify.is_some() || self.cosign)) {
+            return Err(Box::new(KdtErr::SignatureNeedsDetachedVerify));
+        }
+        if self.anonymous && self.encrypt.is_none() {
+            return Err(Box::new(KdtErr::AnonymousNeedsEncrypt));
+        }
+        if self.aead.is_some() && self.encrypt.is_none() && !self.symmetric {
+            return Err(Box::new(KdtErr::AeadNeedsEncrypt));
+        }
+        if (self.stream || self.binary)
+            && self.encrypt.is_none()
+            && self.decrypt.is_none()
+            && !self.symmetric
+        {
+            return Err(Box::new(KdtErr::NeedsCryptoMode));
+        }
         Ok(())
     }
+
+    /// Whether both `--sign` and `--encrypt` were passed, which signs
+    /// the message and then encrypts the signed result.
+    #[inline(always)]
+    pub fn is_sign_and_encrypt(&self) -> bool {
+        self.sign.is_some() && self.encrypt.is_some()
+    }
+
+    /// The key generation options picked on the command line.
+    #[inline(always)]
+    pub fn key_options(&self) -> KdtKeyOptions {
+        KdtKeyOptions::new()
+            .with_hybrid(self.hybrid)
+            .with_kyber_level(
+                self.kyber_level
+                    .unwrap_or(KdtKyberLevel::RECOMMENDED),
+            )
+            .with_dilithium_level(
+                self.dilithium_level
+                    .unwrap_or(KdtDilithiumLevel::RECOMMENDED),
+            )
+            .with_slh_dsa_level(self.slh_dsa)
+    }
+
+    /// The encryption options picked on the command line.
+    #[inline(always)]
+    pub fn encrypt_options(&self) -> KdtEncryptOptions {
+        KdtEncryptOptions::new()
+            .with_anonymous(self.anonymous)
+            .with_aead(self.aead.unwrap_or_default())
+    }
+
+    /// The signing options picked on the command line.
+    #[inline(always)]
+    pub fn sign_options(&self) -> KdtSignOptions {
+        KdtSignOptions::new()
+            .with_hash(self.hash.unwrap_or_default())
+            .with_notations(self.notation.clone())
+            .with_context(self.context.clone().unwrap_or_default())
+    }
+
+    /// Where `--sign --detach` writes the signature: the `--output` file,
+    /// the `--signature` file that `--cosign` added to, the `.kdtsig`
+    /// file next to the `--input` file, or stdout if there's none of
+    /// them.
+    pub fn detached_signature_output(&self) -> Option<PathBuf> {
+        self.output
+            .clone()
+            .or_else(|| self.signature.clone())
+            .or_else(|| {
+                self.input
+                    .as_ref()
+                    .map(|p| detached_signature_path(p))
+            })
+    }
+
+    /// Where `--verify --detach` and `--sign --cosign --detach` read the
+    /// signature from: the
+    /// `--signature` file, or the `.kdtsig` file next to the `--input`
+    /// file.
+    pub fn detached_signature_input(&self) -> Option<PathBuf> {
+        self.signature.clone().or_else(|| {
+            self.input
+                .as_ref()
+                .map(|p| detached_signature_path(p))
+        })
+    }
+
+    /// Whether KDT's own output would end up mixed into the raw bytes
+    /// written to stdout, in which case logging has to stay quiet.
+    #[inline(always)]
+    pub fn writes_raw_stdout(&self) -> bool {
+        (self.stream || self.binary) && self.output.is_none()
+    }
+}
+
+/// Parses the security level passed to `--kyber-level`.
+fn parse_kyber_level(name: &str) -> Result<KdtKyberLevel, String> {
+    KdtKyberLevel::from_name(name).ok_or_else(|| {
+        String::from("expected one of ml-kem-512, ml-kem-768, ml-kem-1024, 512, 768 or 1024")
+    })
+}
+
+/// Parses the parameter set passed to `--dilithium-level`.
+fn parse_dilithium_level(name: &str) -> Result<KdtDilithiumLevel, String> {
+    KdtDilithiumLevel::from_name(name)
+        .ok_or_else(|| String::from("expected one of 44, 65, 87, 2, 3 or 5"))
+}
+
+/// Parses the parameter set passed to `--slh-dsa`.
+fn parse_slh_dsa_level(name: &str) -> Result<KdtSlhDsaLevel, String> {
+    KdtSlhDsaLevel::from_name(name)
+        .ok_or_else(|| String::from("expected one of 128s, 128f, 192s, 192f, 256s or 256f"))
+}
+
+/// Parses the name of an AEAD passed to `--aead`.
+fn parse_aead(name: &str) -> Result<KdtAead, String> {
+    KdtAead::from_name(name).ok_or_else(|| {
+        String::from("expected one of aes-256-gcm, chacha20-poly1305 or xchacha20-poly1305")
+    })
+}
+
+/// Parses the name of a hash algorithm passed to `--hash`.
+fn parse_hash(name: &str) -> Result<KdtSignatureHash, String> {
+    KdtSignatureHash::from_name(name)
+        .ok_or_else(|| String::from("expected one of sha512, shake256 or none"))
+}
+
+/// Checks the context string passed to `--context`.
+fn parse_context(context: &str) -> Result<String, String> {
+    match context.len() <= MAX_CONTEXT_SIZE {
+        true => Ok(context.to_owned()),
+        false => Err(format!("expected at most {} bytes", MAX_CONTEXT_SIZE)),
+    }
+}
+
+/// Parses a `KEY=VALUE` notation passed to `--notation`.
+fn parse_notation(notation: &str) -> Result<(String, String), String> {
+    match notation.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_owned(), value.to_owned())),
+        _ => Err(String::from("expected KEY=VALUE")),
+    }
+}
+
+/// The path of the detached signature of the file at `path`, ie `path`
+/// with `.kdtsig` appended.
+fn detached_signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(DETACHED_SIGNATURE_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Opens the file at `path` for reading, or stdin if there isn't one.
+pub fn open_input(path: &Option<PathBuf>) -> Result<Box<dyn Read>, Box<dyn Error>> {
+    match path {
+        Some(p) => Ok(Box::new(File::open(p)?)),
+        None => Ok(Box::new(io::stdin().lock())),
+    }
+}
+
+/// Creates the file at `path` for writing, or uses stdout if there
+/// isn't one.
+pub fn open_output(path: &Option<PathBuf>) -> Result<Box<dyn Write>, Box<dyn Error>> {
+    match path {
+        Some(p) => Ok(Box::new(BufWriter::new(File::create(p)?))),
+        None => Ok(Box::new(BufWriter::new(io::stdout().lock()))),
+    }
 }
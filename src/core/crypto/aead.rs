@@ -0,0 +1,147 @@
+// -- imports --
+use aes_gcm::{
+    aead::{
+        rand_core::RngCore,
+        Aead,
+        KeyInit,
+        OsRng,
+        Payload,
+    },
+    Aes256Gcm,
+};
+use chacha20poly1305::{
+    ChaCha20Poly1305,
+    XChaCha20Poly1305,
+};
+use generic_array::GenericArray;
+use std::fmt;
+
+// -- AEAD constants --
+/// Size of the keys every supported AEAD takes.
+pub const AEAD_KEY_SIZE: usize = 32;
+
+/// Size of the authentication tag every supported AEAD appends.
+pub const AEAD_TAG_SIZE: usize = 16;
+
+// -- AEAD algorithms --
+/// Authenticated ciphers a message's content can be sealed with.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum KdtAead {
+    /// AES-256 in Galois/Counter Mode, with a 96 bit nonce. Fast
+    /// wherever the CPU has AES instructions.
+    #[default]
+    Aes256Gcm,
+
+    /// ChaCha20-Poly1305, with a 96 bit nonce. Fast and constant-time
+    /// even without AES instructions.
+    ChaCha20Poly1305,
+
+    /// XChaCha20-Poly1305, with a 192 bit nonce. Random nonces that big
+    /// never realistically collide, no matter how much a key is used.
+    XChaCha20Poly1305,
+}
+
+impl KdtAead {
+    /// Numeric id of the algorithm, as stored in containers.
+    pub fn id(&self) -> u8 {
+        match self {
+            Self::Aes256Gcm => 1,
+            Self::ChaCha20Poly1305 => 2,
+            Self::XChaCha20Poly1305 => 3,
+        }
+    }
+
+    /// Restores the algorithm from its numeric id.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Self::Aes256Gcm),
+            2 => Some(Self::ChaCha20Poly1305),
+            3 => Some(Self::XChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    /// Restores the algorithm from its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "aes-256-gcm" => Some(Self::Aes256Gcm),
+            "chacha20-poly1305" => Some(Self::ChaCha20Poly1305),
+            "xchacha20-poly1305" => Some(Self::XChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    /// Size of the nonces the algorithm takes.
+    pub fn nonce_size(&self) -> usize {
+        match self {
+            Self::Aes256Gcm | Self::ChaCha20Poly1305 => 12,
+            Self::XChaCha20Poly1305 => 24,
+        }
+    }
+
+    /// Generates a random key for the algorithm.
+    pub fn generate_key(&self) -> Vec<u8> {
+        let mut key = vec![0u8; AEAD_KEY_SIZE];
+        OsRng.fill_bytes(&mut key);
+        key
+    }
+
+    /// Generates a random nonce for the algorithm.
+    pub fn generate_nonce(&self) -> Vec<u8> {
+        let mut nonce = vec![0u8; self.nonce_size()];
+        OsRng.fill_bytes(&mut nonce);
+        nonce
+    }
+
+    /// Encrypts and authenticates `msg`, and authenticates `aad` along
+    /// with it. The key and nonce have to have the algorithm's sizes.
+    pub fn seal(&self, key: &[u8], nonce: &[u8], msg: &[u8], aad: &[u8]) -> Vec<u8> {
+        // The nonce sizes differ, so each cipher takes its own view of it.
+        let payload = Payload { msg, aad };
+        match self {
+            Self::Aes256Gcm => Aes256Gcm::new_from_slice(key)
+                .unwrap()
+                .encrypt(GenericArray::from_slice(nonce), payload),
+            Self::ChaCha20Poly1305 => ChaCha20Poly1305::new_from_slice(key)
+                .unwrap()
+                .encrypt(GenericArray::from_slice(nonce), payload),
+            Self::XChaCha20Poly1305 => XChaCha20Poly1305::new_from_slice(key)
+                .unwrap()
+                .encrypt(GenericArray::from_slice(nonce), payload),
+        }
+        .unwrap()
+    }
+
+    /// Checks and decrypts what `KdtAead::seal` made. Returns `None` if
+    /// anything doesn't authenticate, or if the key or nonce have the
+    /// wrong size.
+    pub fn open(&self, key: &[u8], nonce: &[u8], msg: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        if key.len() != AEAD_KEY_SIZE || nonce.len() != self.nonce_size() {
+            return None;
+        }
+        // The nonce sizes differ, so each cipher takes its own view of it.
+        let payload = Payload { msg, aad };
+        match self {
+            Self::Aes256Gcm => Aes256Gcm::new_from_slice(key)
+                .ok()?
+                .decrypt(GenericArray::from_slice(nonce), payload),
+            Self::ChaCha20Poly1305 => ChaCha20Poly1305::new_from_slice(key)
+                .ok()?
+                .decrypt(GenericArray::from_slice(nonce), payload),
+            Self::XChaCha20Poly1305 => XChaCha20Poly1305::new_from_slice(key)
+                .ok()?
+                .decrypt(GenericArray::from_slice(nonce), payload),
+        }
+        .ok()
+    }
+}
+
+impl fmt::Display for KdtAead {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Aes256Gcm => write!(f, "aes-256-gcm"),
+            Self::ChaCha20Poly1305 => write!(f, "chacha20-poly1305"),
+            Self::XChaCha20Poly1305 => write!(f, "xchacha20-poly1305"),
+        }
+    }
+}
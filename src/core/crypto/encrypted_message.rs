@@ -2,72 +2,387 @@
 use crate::core::*;
 use std::fmt;
 
-pub struct KdtEncryptedMessage {
+// -- container record tags --
+/// Id of the AEAD the content is sealed with.
+pub const MESSAGE_AEAD: u8 = 1;
+/// Nonce of the content.
+pub const MESSAGE_NONCE: u8 = 2;
+/// The encrypted content itself.
+pub const MESSAGE_CIPHERTEXT: u8 = 3;
+/// A recipient, as nested records. Repeated once per recipient.
+pub const MESSAGE_RECIPIENT: u8 = 4;
+/// Passphrase key derivation parameters, as nested records.
+pub const MESSAGE_PASSPHRASE: u8 = 5;
+
+/// Id of the KEM the recipient's shared secret comes from.
+pub const RECIPIENT_KEM: u8 = 1;
+/// The KEM ciphertext.
+pub const RECIPIENT_ENCRYPTED_SECRET: u8 = 2;
+/// The wrapped content key.
+pub const RECIPIENT_WRAPPED_KEY: u8 = 3;
+/// Nonce of the wrapped content key.
+pub const RECIPIENT_NONCE: u8 = 4;
+/// Ephemeral X25519 public key. Only there for hybrid recipients.
+pub const RECIPIENT_EPHEMERAL_KEY: u8 = 5;
+/// Id of the recipient's public key, as a hint for picking the right
+/// owned key set when decrypting.
+pub const RECIPIENT_KEY_ID: u8 = 6;
+
+/// Id of the passphrase KDF. Argon2id is the only one so far.
+pub const PASSPHRASE_KDF: u8 = 1;
+/// Random salt.
+pub const PASSPHRASE_SALT: u8 = 2;
+/// Memory cost, as a big-endian `u32`.
+pub const PASSPHRASE_M_COST_RECORD: u8 = 3;
+/// Time cost, as a big-endian `u32`.
+pub const PASSPHRASE_T_COST_RECORD: u8 = 4;
+/// Parallelism, as a big-endian `u32`.
+pub const PASSPHRASE_P_COST_RECORD: u8 = 5;
+
+/// Id of Argon2id in the `PASSPHRASE_KDF` record.
+pub const KDF_ARGON2ID: u8 = 1;
+
+/// Label of the armor around encrypted messages.
+pub const MESSAGE_ARMOR_LABEL: &str = "KDT MESSAGE";
+
+// -- per-recipient key wrapping --
+/// A single recipient's copy of the message's content key. Every
+/// recipient gets their own Kyber encapsulation, and the content key
+/// is wrapped with the shared secret that encapsulation establishes.
+pub struct KdtRecipient {
+    /// Which KEM the shared secret comes from.
+    pub kem: KdtKem,
+
     /// The shared secret established by Kyber, encrypted
-    /// asymmetrically so only you can see it.
+    /// asymmetrically so only this recipient can see it.
     pub encrypted_secret: Vec<u8>,
 
+    /// The content key, sealed with the message's AEAD under a key derived from
+    /// the shared secret above. Empty for legacy single-recipient
+    /// messages, where the shared secret itself was the content key.
+    pub wrapped_key: Vec<u8>,
+
+    /// The AEAD nonce used to wrap the content key.
+    pub nonce: Vec<u8>,
+
+    /// The ephemeral X25519 public key used for hybrid recipients. Empty
+    /// when the recipient's key isn't hybrid.
+    pub ephemeral_key: Vec<u8>,
+
+    /// Id of the public key this entry was made for, if the message
+    /// says. Lets the recipient find their key without trying all of
+    /// them.
+    pub key_id: Option<String>,
+}
+
+impl KdtRecipient {
+    /// Creates a new `KdtRecipient` object from the given KEM, encrypted
+    /// secret, wrapped content key, unencrypted nonce, and ephemeral
+    /// X25519 public key.
+    pub fn new(
+        kem: KdtKem, encrypted_secret: Vec<u8>, wrapped_key: Vec<u8>, nonce: Vec<u8>,
+        ephemeral_key: Vec<u8>,
+    ) -> Self {
+        Self {
+            kem,
+            encrypted_secret,
+            wrapped_key,
+            nonce,
+            ephemeral_key,
+            key_id: None,
+        }
+    }
+
+    /// Sets the id of the public key this entry was made for.
+    #[inline(always)]
+    pub fn with_key_id(mut self, key_id: String) -> Self {
+        self.key_id = Some(key_id);
+        self
+    }
+
+    /// Whether this entry may have been made for the given public key,
+    /// ie it either names that key or doesn't name any.
+    pub fn may_be_for(&self, pubkey: &PubKeyPair) -> bool {
+        match &self.key_id {
+            Some(key_id) => *key_id == pubkey.id,
+            None => true,
+        }
+    }
+
+    /// Whether the content key was wrapped with a secret combined from
+    /// both Kyber and X25519, rather than Kyber alone.
+    #[inline(always)]
+    pub fn is_hybrid(&self) -> bool {
+        self.kem.is_hybrid()
+    }
+
+    /// The container records describing this recipient.
+    pub fn to_records(&self) -> KdtRecords {
+        let mut records = KdtRecords::new();
+        records.push(RECIPIENT_KEM, [self.kem.id()]);
+        records.push(RECIPIENT_ENCRYPTED_SECRET, self.encrypted_secret.clone());
+        records.push(RECIPIENT_WRAPPED_KEY, self.wrapped_key.clone());
+        records.push(RECIPIENT_NONCE, self.nonce.clone());
+        if self.is_hybrid() {
+            records.push(RECIPIENT_EPHEMERAL_KEY, self.ephemeral_key.clone());
+        }
+        if let Some(key_id) = &self.key_id {
+            records.push(RECIPIENT_KEY_ID, key_id.as_bytes());
+        }
+        records
+    }
+
+    /// Restores a recipient from its container records.
+    pub fn from_records(records: &KdtRecords) -> Result<Self, Box<dyn Error>> {
+        let kem =
+            KdtKem::from_id(records.require_u8(RECIPIENT_KEM)?).ok_or(KdtErr::UnknownAlgorithm)?;
+        let ephemeral_key = if kem.is_hybrid() {
+            records.require(RECIPIENT_EPHEMERAL_KEY)?.to_vec()
+        } else {
+            Vec::new()
+        };
+
+        let mut recipient = Self::new(
+            kem,
+            records
+                .require(RECIPIENT_ENCRYPTED_SECRET)?
+                .to_vec(),
+            records.require(RECIPIENT_WRAPPED_KEY)?.to_vec(),
+            records.require(RECIPIENT_NONCE)?.to_vec(),
+            ephemeral_key,
+        );
+        recipient.key_id = records
+            .get(RECIPIENT_KEY_ID)
+            .map(|id| String::from_utf8_lossy(id).to_string());
+
+        Ok(recipient)
+    }
+}
+
+// -- passphrase key derivation parameters --
+/// Everything needed to derive a message's content key from a
+/// passphrase again. Argon2id is memory-hard, so these parameters make
+/// every guess expensive for an attacker.
+pub struct KdtPassphraseParams {
+    /// Random salt, unique to every message.
+    pub salt: Vec<u8>,
+
+    /// Memory cost, in KiB.
+    pub m_cost: u32,
+
+    /// Time cost, ie the amount of passes over the memory.
+    pub t_cost: u32,
+
+    /// Degree of parallelism.
+    pub p_cost: u32,
+}
+
+impl KdtPassphraseParams {
+    /// Creates a new `KdtPassphraseParams` object from the given salt and
+    /// Argon2id costs.
+    pub fn new(salt: Vec<u8>, m_cost: u32, t_cost: u32, p_cost: u32) -> Self {
+        Self {
+            salt,
+            m_cost,
+            t_cost,
+            p_cost,
+        }
+    }
+
+    /// The container records describing these parameters.
+    pub fn to_records(&self) -> KdtRecords {
+        let mut records = KdtRecords::new();
+        records.push(PASSPHRASE_KDF, [KDF_ARGON2ID]);
+        records.push(PASSPHRASE_SALT, self.salt.clone());
+        records.push(PASSPHRASE_M_COST_RECORD, self.m_cost.to_be_bytes());
+        records.push(PASSPHRASE_T_COST_RECORD, self.t_cost.to_be_bytes());
+        records.push(PASSPHRASE_P_COST_RECORD, self.p_cost.to_be_bytes());
+        records
+    }
+
+    /// Restores the parameters from their container records.
+    pub fn from_records(records: &KdtRecords) -> Result<Self, Box<dyn Error>> {
+        if records.require_u8(PASSPHRASE_KDF)? != KDF_ARGON2ID {
+            return Err(Box::new(KdtErr::UnknownAlgorithm));
+        }
+
+        Ok(Self::new(
+            records.require(PASSPHRASE_SALT)?.to_vec(),
+            records.require_u32(PASSPHRASE_M_COST_RECORD)?,
+            records.require_u32(PASSPHRASE_T_COST_RECORD)?,
+            records.require_u32(PASSPHRASE_P_COST_RECORD)?,
+        ))
+    }
+}
+
+// -- encrypted message --
+pub struct KdtEncryptedMessage {
+    /// Format version and algorithms of the message.
+    pub header: KdtMessageHeader,
+
+    /// Everyone who can decrypt this message, each with their own
+    /// wrapped copy of the content key. Empty for messages encrypted
+    /// with a passphrase.
+    pub recipients: Vec<KdtRecipient>,
+
+    /// How the content key was derived from a passphrase, for messages
+    /// encrypted with one instead of public keys.
+    pub passphrase: Option<KdtPassphraseParams>,
+
     /// The actual encrypted data.
     pub encrypted_message: Vec<u8>,
 
-    /// The AES-GCM nonce. From
+    /// The AEAD nonce. From
     /// https://cryptography.io/en/latest/hazmat/primitives/symmetric-encryption/#cryptography.hazmat.primitives.ciphers.algorithms.ChaCha20:
     /// `The nonce does not need to be kept secret and may be included with the ciphertext.`
     pub nonce: Vec<u8>,
+
+    /// The records of a parsed message minus the ciphertext, in the order
+    /// they were read and including those this version doesn't know, as
+    /// the writer authenticated them. `None` for messages made here.
+    pub authenticated_records: Option<KdtRecords>,
 }
 
 impl KdtEncryptedMessage {
-    /// Creates a new `Message` object from the given encrypted secret,
+    /// Creates a new `Message` object from the given recipient list,
     /// encrypted message, and unencrypted nonce.
-    pub fn new(encrypted_secret: Vec<u8>, encrypted_message: Vec<u8>, nonce: Vec<u8>) -> Self {
+    pub fn new(recipients: Vec<KdtRecipient>, encrypted_message: Vec<u8>, nonce: Vec<u8>) -> Self {
         Self {
-            encrypted_secret,
+            header: KdtMessageHeader::new(KdtAead::default()),
+            recipients,
+            passphrase: None,
             encrypted_message,
             nonce,
+            authenticated_records: None,
         }
     }
 
-    /// Restores a `Message` object from the given message string.
+    /// Creates a new `Message` object for a message encrypted with a
+    /// passphrase, from the key derivation parameters, encrypted message,
+    /// and unencrypted nonce.
+    pub fn with_passphrase(
+        passphrase: KdtPassphraseParams, encrypted_message: Vec<u8>, nonce: Vec<u8>,
+    ) -> Self {
+        Self {
+            header: KdtMessageHeader::new(KdtAead::default()),
+            recipients: Vec::new(),
+            passphrase: Some(passphrase),
+            encrypted_message,
+            nonce,
+            authenticated_records: None,
+        }
+    }
+
+    /// Sets the AEAD the content is sealed with.
+    #[inline(always)]
+    pub fn with_aead(mut self, aead: KdtAead) -> Self {
+        self.header.aead = aead;
+        self
+    }
+
+    /// Whether this message was encrypted with a passphrase rather than
+    /// against public keys.
+    #[inline(always)]
+    pub fn is_passphrase_protected(&self) -> bool {
+        self.passphrase.is_some()
+    }
+
+    /// The container holding this message.
+    pub fn to_container(&self) -> KdtContainer {
+        let mut records = KdtRecords::new();
+        records.push(MESSAGE_AEAD, [self.header.aead.id()]);
+        records.push(MESSAGE_NONCE, self.nonce.clone());
+        for recipient in &self.recipients {
+            records.push(MESSAGE_RECIPIENT, recipient.to_records().encode());
+        }
+        if let Some(params) = &self.passphrase {
+            records.push(MESSAGE_PASSPHRASE, params.to_records().encode());
+        }
+        records.push(MESSAGE_CIPHERTEXT, self.encrypted_message.clone());
+        KdtContainer::new(KdtContainerKind::Message, records)
+    }
+
+    /// Everything that gets authenticated along with the content without
+    /// being encrypted: the whole container, minus the ciphertext. For a
+    /// parsed message, that's the records as they were read, so records
+    /// added by newer writers are covered too. Legacy messages didn't
+    /// authenticate anything extra.
+    pub fn associated_data(&self) -> Vec<u8> {
+        if self.header.is_legacy() {
+            return Vec::new();
+        }
+        let mut container = self.to_container();
+        container.records = match &self.authenticated_records {
+            Some(records) => records.clone(),
+            None => container.records.without(MESSAGE_CIPHERTEXT),
+        };
+        container.to_bytes()
+    }
+
+    /// Restores a `Message` object from the given message string. Doesn't
+    /// validate input, so it *will* panic if you pass invalid inputs. See
+    /// `KdtEncryptedMessage::parse` for a version that doesn't.
     pub fn from_str(message: String) -> Self {
-        let message_split: Vec<String> = message
-            .chars()
-            .skip(27)
-            .take(message.len() - 27 - 26)
-            .collect::<String>()
-            .replace('\n', "")
-            .split('*')
-            .map(String::from)
-            .collect();
+        Self::parse(&message).unwrap()
+    }
 
-        Self {
-            encrypted_secret: Base64::decode_string(&message_split[0]),
-            encrypted_message: Base64::decode_string(&message_split[1]),
-            nonce: Base64::decode_string(&message_split[2]),
+    /// Restores a `Message` object from the given message string, in
+    /// either the current container format or the legacy KDT 0.1 one.
+    pub fn parse(message: &str) -> Result<Self, Box<dyn Error>> {
+        let body = Armor::unwrap(message);
+
+        // Legacy messages are made up of exactly one encrypted secret, the
+        // encrypted message and the nonce, separated by asterisks.
+        if body.contains('*') {
+            let message_split: Vec<&str> = body.split('*').collect();
+            if message_split.len() != 3 {
+                return Err(Box::new(KdtErr::BadContainer));
+            }
+            return Ok(Self {
+                header: KdtMessageHeader::legacy(),
+                recipients: vec![KdtRecipient::new(
+                    KdtKem::Kyber(KdtKyberLevel::Kyber1024),
+                    Base64::try_decode_string(message_split[0])?,
+                    Vec::new(),
+                    Vec::new(),
+                    Vec::new(),
+                )],
+                passphrase: None,
+                encrypted_message: Base64::try_decode_string(message_split[1])?,
+                nonce: Base64::try_decode_string(message_split[2])?,
+                authenticated_records: None,
+            });
         }
+
+        let container =
+            KdtContainer::from_bytes(&Base64::try_decode_string(body)?, KdtContainerKind::Message)?;
+        let records = &container.records;
+        let aead =
+            KdtAead::from_id(records.require_u8(MESSAGE_AEAD)?).ok_or(KdtErr::UnknownAlgorithm)?;
+
+        Ok(Self {
+            header: KdtMessageHeader {
+                version: container.version,
+                aead,
+            },
+            recipients: records
+                .get_all(MESSAGE_RECIPIENT)
+                .map(|r| KdtRecipient::from_records(&KdtRecords::decode(r)?))
+                .collect::<Result<_, _>>()?,
+            passphrase: records
+                .get(MESSAGE_PASSPHRASE)
+                .map(|p| KdtPassphraseParams::from_records(&KdtRecords::decode(p)?))
+                .transpose()?,
+            encrypted_message: records.require(MESSAGE_CIPHERTEXT)?.to_vec(),
+            nonce: records.require(MESSAGE_NONCE)?.to_vec(),
+            authenticated_records: Some(records.without(MESSAGE_CIPHERTEXT)),
+        })
     }
 }
 
 impl fmt::Display for KdtEncryptedMessage {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let encoded_secret = Base64::encode_bytes(&self.encrypted_secret);
-        let encoded_message = Base64::encode_bytes(&self.encrypted_message);
-        let encoded_nonce = Base64::encode_bytes(&self.nonce);
-
-        let message = format!("{}*{}*{}", encoded_secret, encoded_message, encoded_nonce,)
-            .chars()
-            .enumerate()
-            .flat_map(|(i, c)| {
-                if (i + 1) % 64 == 0 {
-                    vec![c, '\n']
-                } else {
-                    vec![c]
-                }
-            })
-            .collect::<String>();
-        write!(
-            f,
-            "-----BEGIN KDT MESSAGE-----\n{}\n-----END KDT MESSAGE-----",
-            message.trim()
-        )
+        let encoded = Base64::encode_bytes(&self.to_container().to_bytes());
+        write!(f, "{}", Armor::wrap(MESSAGE_ARMOR_LABEL, &encoded))
     }
 }
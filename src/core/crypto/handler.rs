@@ -1,75 +1,575 @@
 // -- imports --
 use crate::core::*;
-use aes_gcm::{
-    aead::{
-        Aead,
-        AeadCore,
-        KeyInit,
-        OsRng,
-    },
-    Aes256Gcm,
-    Key,
+use aes_gcm::aead::{
+    rand_core::RngCore,
+    OsRng,
+};
+use argon2::{
+    Algorithm,
+    Argon2,
+    Params,
+    Version,
 };
-use generic_array::GenericArray;
+use hkdf::Hkdf;
 use pqc_kyber::{
     decapsulate,
     encapsulate,
 };
-use std::error::Error;
+use sha2::Sha256;
+use std::{
+    error::Error,
+    io::{
+        Read,
+        Write,
+    },
+};
+use x25519_dalek::{
+    EphemeralSecret as X25519EphemeralKey,
+    PublicKey as X25519PublicKey,
+    StaticSecret as X25519SecretKey,
+};
+
+// -- hybrid key combination --
+/// Domain separation label for the hybrid Kyber + X25519 combiner, so
+/// its output can never collide with a key derived for anything else.
+/// The bytes are fixed and the same at every Kyber level: the
+/// `Kyber1024` in them only dates from when that was the one level.
+pub const HYBRID_COMBINER_LABEL: &[u8] = b"KDT hybrid Kyber1024+X25519 v1";
+
+// -- key wrapping --
+/// Domain separation label for deriving a recipient's key wrapping key
+/// from their shared secret.
+pub const KEY_WRAP_LABEL: &[u8] = b"KDT key wrap v2";
+
+// -- passphrase key derivation defaults --
+/// Size of the random salt used for every passphrase-encrypted message.
+pub const PASSPHRASE_SALT_SIZE: usize = 16;
+
+/// Argon2id memory cost in KiB (64 MiB), per the second recommended
+/// option in RFC 9106.
+pub const PASSPHRASE_M_COST: u32 = 64 * 1024;
+
+/// Argon2id time cost, per the second recommended option in RFC 9106.
+pub const PASSPHRASE_T_COST: u32 = 3;
+
+/// Argon2id degree of parallelism, per the second recommended option in
+/// RFC 9106.
+pub const PASSPHRASE_P_COST: u32 = 4;
+
+/// Largest Argon2id memory cost in KiB (1 GiB) KDT derives a key with.
+/// The costs come from the message, before anything is authenticated,
+/// so without a bound a forged one could make KDT exhaust its memory.
+pub const PASSPHRASE_MAX_M_COST: u32 = 16 * PASSPHRASE_M_COST;
+
+/// Largest Argon2id time cost KDT derives a key with.
+pub const PASSPHRASE_MAX_T_COST: u32 = 4 * PASSPHRASE_T_COST;
+
+/// Largest Argon2id degree of parallelism KDT derives a key with.
+pub const PASSPHRASE_MAX_P_COST: u32 = 4 * PASSPHRASE_P_COST;
 
 // -- base crypto handling --
 /// Core cryptography handler for KDT. Handles everything when
-/// it comes to AES and Kyber. Signatures are handled by the
+/// it comes to the AEADs and Kyber. Signatures are handled by the
 /// `KdtSignageHandler` though.
 pub struct KdtCryptoHandler;
 
 impl KdtCryptoHandler {
-    /// Encrypts a string of text against the provided Kyber
-    /// public key. We use AES in the backend here because
+    /// Encrypts a string of text against the provided public
+    /// key. We use AES in the backend here because
     /// the way Kyber works is that it establishes a shared
     /// symmetric key inside of the asymmetric stuff. Magic!
     pub fn encrypt_text(
-        text: String, pubkey: Vec<u8>,
+        text: String, pubkey: PubKeyPair,
     ) -> Result<KdtEncryptedMessage, Box<dyn Error>> {
-        let mut rng = rand::thread_rng();
-        let (encrypted_secret, secret_bytes) = encapsulate(&pubkey, &mut rng)?;
-        let key = Key::<Aes256Gcm>::from_slice(&secret_bytes);
-        let cipher = Aes256Gcm::new(key);
-        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-        let encrypted_message = cipher.encrypt(&nonce, text.as_ref()).unwrap();
-        let nonce = nonce.into_iter().collect::<Vec<u8>>();
+        Self::encrypt_text_multi(text, vec![pubkey])
+    }
 
-        Ok(KdtEncryptedMessage::new(
-            encrypted_secret.to_vec(),
-            encrypted_message,
-            nonce,
-        ))
+    /// Encrypts a string of text against every provided public key at
+    /// once. See `KdtCryptoHandler::encrypt_bytes`.
+    pub fn encrypt_text_multi(
+        text: String, pubkeys: Vec<PubKeyPair>,
+    ) -> Result<KdtEncryptedMessage, Box<dyn Error>> {
+        Self::encrypt_bytes(text.as_bytes(), pubkeys)
+    }
+
+    /// Encrypts arbitrary bytes against every provided public key at
+    /// once. The bytes are only encrypted once, under a random content
+    /// key, and that content key is then wrapped separately for each
+    /// recipient with their own Kyber (or hybrid) shared secret.
+    pub fn encrypt_bytes(
+        bytes: &[u8], pubkeys: Vec<PubKeyPair>,
+    ) -> Result<KdtEncryptedMessage, Box<dyn Error>> {
+        Self::encrypt_bytes_with_options(bytes, pubkeys, KdtEncryptOptions::new())
+    }
+
+    /// Encrypts arbitrary bytes against every provided public key at
+    /// once, with the given options. See `KdtCryptoHandler::encrypt_bytes`.
+    pub fn encrypt_bytes_with_options(
+        bytes: &[u8], pubkeys: Vec<PubKeyPair>, options: KdtEncryptOptions,
+    ) -> Result<KdtEncryptedMessage, Box<dyn Error>> {
+        if pubkeys.is_empty() {
+            return Err(Box::new(KdtErr::NoRecipients));
+        }
+        let header = KdtMessageHeader::new(options.aead);
+        let content_key = options.aead.generate_key();
+        let nonce = options.aead.generate_nonce();
+        let recipients = pubkeys
+            .iter()
+            .map(|pubkey| Self::wrap_key(&content_key, pubkey, &header, options))
+            .collect::<Result<Vec<_>, _>>()?;
+        // The recipients are part of the associated data, so the content
+        // can only be sealed once they're all in place.
+        let mut message =
+            KdtEncryptedMessage::new(recipients, Vec::new(), nonce).with_aead(options.aead);
+        message.encrypted_message = options.aead.seal(
+            &content_key,
+            &message.nonce,
+            bytes,
+            &message.associated_data(),
+        );
+
+        Ok(message)
     }
 
     /// Decrypts a pre-deserialized `Message` object with the
-    /// provided private key. Note that, as stated above, this uses
-    /// AES under the hood because of the magic way Kyber works -
-    /// a shared symmetric key is established using the asymmetric
+    /// provided owned key set. Note that, as stated above, this uses
+    /// AES (or ChaCha20) under the hood because of the magic way Kyber
+    /// works - a shared symmetric key is established using the asymmetric
     /// keys, and then both parties can encrypt sensitive data
     /// with that! Pure magic, obviously.
-    pub fn decrypt_msg(message: KdtEncryptedMessage, privkey: Vec<u8>) -> String {
-        // Uses the private key we have to decrypt the symmetric
-        // shared secret.
-        let secret_bytes = decapsulate(&message.encrypted_secret, &privkey)
-            .expect("You used the wrong private key!");
-        let key = Key::<Aes256Gcm>::from_slice(&secret_bytes);
-        let cipher = Aes256Gcm::new(key);
-        let nonce = message.nonce;
+    pub fn decrypt_msg(
+        message: KdtEncryptedMessage, keyset: &OwnedKeySet,
+    ) -> Result<String, Box<dyn Error>> {
+        let bytes = Self::decrypt_bytes(message, keyset)?;
 
         // Converts the raw text bytes to a UTF-8 encoded string.
-        String::from_utf8_lossy(
-            &cipher
-                .decrypt(
-                    &GenericArray::clone_from_slice(&nonce),
-                    message.encrypted_message.as_ref(),
-                )
-                .unwrap(),
+        Ok(String::from_utf8_lossy(&bytes).into())
+    }
+
+    /// Decrypts a pre-deserialized `Message` object with the provided
+    /// owned key set, and returns the plaintext exactly as it was
+    /// encrypted.
+    pub fn decrypt_bytes(
+        message: KdtEncryptedMessage, keyset: &OwnedKeySet,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        Self::decrypt_bytes_with_keysets(message, std::slice::from_ref(keyset))
+    }
+
+    /// Decrypts a pre-deserialized `Message` object with whichever of the
+    /// provided owned key sets it was encrypted to. Recipient entries
+    /// that name a different key are skipped without any decapsulation,
+    /// so a message usually only costs one attempt no matter how many
+    /// key sets are passed. Anonymous entries don't name any key, so
+    /// every key set gets tried on them until one fits.
+    pub fn decrypt_bytes_with_keysets(
+        message: KdtEncryptedMessage, keysets: &[OwnedKeySet],
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let aead = message.header.aead;
+        if message.nonce.len() != aead.nonce_size() {
+            return Err(Box::new(KdtErr::BadContainer));
+        }
+        let associated_data = message.associated_data();
+        for keyset in keysets {
+            // Uses the private key we have to recover the content key
+            // from whichever recipient entry was made for us.
+            let content_keys = message
+                .recipients
+                .iter()
+                .filter(|r| r.may_be_for(&keyset.pubkey_pair))
+                .filter_map(|r| Self::unwrap_key(r, keyset, &message.header).ok());
+            for content_key in content_keys {
+                match aead.open(
+                    &content_key,
+                    &message.nonce,
+                    &message.encrypted_message,
+                    &associated_data,
+                ) {
+                    Some(bytes) => return Ok(bytes),
+                    // Legacy entries can't tell whether they were made for
+                    // this key, so all a failure means is that they weren't.
+                    None if message.header.is_legacy() => continue,
+                    None => return Err(Box::new(KdtErr::DecryptionFailed)),
+                }
+            }
+        }
+
+        Err(Box::new(KdtErr::NotForThisKey))
+    }
+
+    /// Encrypts arbitrary bytes with a key derived from a passphrase
+    /// instead of a public key, so the recipient doesn't need a KDT key
+    /// at all. The key is derived with Argon2id under a fresh random salt,
+    /// using KDT's default cost parameters, and the bytes are sealed with
+    /// the given AEAD.
+    pub fn encrypt_bytes_with_passphrase(
+        bytes: &[u8], passphrase: &str, aead: KdtAead,
+    ) -> Result<KdtEncryptedMessage, Box<dyn Error>> {
+        let mut salt = vec![0u8; PASSPHRASE_SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        let params = KdtPassphraseParams::new(
+            salt,
+            PASSPHRASE_M_COST,
+            PASSPHRASE_T_COST,
+            PASSPHRASE_P_COST,
+        );
+        Self::encrypt_bytes_with_params(bytes, passphrase, params, aead)
+    }
+
+    /// Encrypts arbitrary bytes with a key derived from a passphrase using
+    /// the given Argon2id parameters, and seals them with the given AEAD.
+    pub fn encrypt_bytes_with_params(
+        bytes: &[u8], passphrase: &str, params: KdtPassphraseParams, aead: KdtAead,
+    ) -> Result<KdtEncryptedMessage, Box<dyn Error>> {
+        let content_key = Self::derive_passphrase_key(passphrase, &params)?;
+        let nonce = aead.generate_nonce();
+        // The key derivation parameters are authenticated too, so nobody
+        // can quietly weaken them.
+        let mut message =
+            KdtEncryptedMessage::with_passphrase(params, Vec::new(), nonce).with_aead(aead);
+        message.encrypted_message = aead.seal(
+            &content_key,
+            &message.nonce,
+            bytes,
+            &message.associated_data(),
+        );
+
+        Ok(message)
+    }
+
+    /// Decrypts a message that was encrypted with a passphrase, and
+    /// returns the plaintext exactly as it was encrypted.
+    pub fn decrypt_bytes_with_passphrase(
+        message: KdtEncryptedMessage, passphrase: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let params = message
+            .passphrase
+            .as_ref()
+            .ok_or(KdtErr::NotPassphraseProtected)?;
+        let content_key = Self::derive_passphrase_key(passphrase, params)?;
+
+        Ok(message
+            .header
+            .aead
+            .open(
+                &content_key,
+                &message.nonce,
+                &message.encrypted_message,
+                &message.associated_data(),
+            )
+            .ok_or(KdtErr::WrongPassphrase)?)
+    }
+
+    /// Encrypts everything `reader` yields to every provided public key,
+    /// and writes the result to `writer`. The input is sealed in
+    /// fixed-size chunks, so memory use doesn't grow with the size of the
+    /// input. Every chunk gets its own nonce, and the last one is marked
+    /// as such, so truncated or reordered streams are rejected when
+    /// decrypting.
+    pub fn encrypt_stream<R: Read, W: Write>(
+        reader: &mut R, writer: &mut W, pubkeys: Vec<PubKeyPair>,
+    ) -> Result<(), Box<dyn Error>> {
+        Self::encrypt_stream_with_options(reader, writer, pubkeys, KdtEncryptOptions::new())
+    }
+
+    /// Encrypts everything `reader` yields to every provided public key,
+    /// with the given options. See `KdtCryptoHandler::encrypt_stream`.
+    pub fn encrypt_stream_with_options<R: Read, W: Write>(
+        reader: &mut R, writer: &mut W, pubkeys: Vec<PubKeyPair>, options: KdtEncryptOptions,
+    ) -> Result<(), Box<dyn Error>> {
+        if pubkeys.is_empty() {
+            return Err(Box::new(KdtErr::NoRecipients));
+        }
+        let message_header = KdtMessageHeader::new(options.aead);
+        let content_key = options.aead.generate_key();
+        let recipients = pubkeys
+            .iter()
+            .map(|pubkey| Self::wrap_key(&content_key, pubkey, &message_header, options))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut nonce_prefix = vec![0u8; stream_nonce_prefix_size(options.aead)];
+        OsRng.fill_bytes(&mut nonce_prefix);
+        let header = KdtStreamHeader::new(message_header, recipients, nonce_prefix);
+        header.write_to(writer)?;
+        let associated_data = header.associated_data();
+
+        // We always read one chunk ahead, so we know whether the chunk
+        // we're about to seal is the last one.
+        let mut chunk = vec![0u8; STREAM_CHUNK_SIZE];
+        let mut next_chunk = vec![0u8; STREAM_CHUNK_SIZE];
+        let mut len = read_full(reader, &mut chunk)?;
+        let mut counter = 0u32;
+        loop {
+            let next_len = if len == STREAM_CHUNK_SIZE {
+                read_full(reader, &mut next_chunk)?
+            } else {
+                0
+            };
+            let last = next_len == 0;
+            let nonce = header.chunk_nonce(counter, last);
+            let sealed = options
+                .aead
+                .seal(&content_key, &nonce, &chunk[..len], &associated_data);
+            writer.write_all(&sealed)?;
+            if last {
+                break;
+            }
+            std::mem::swap(&mut chunk, &mut next_chunk);
+            len = next_len;
+            counter = counter
+                .checked_add(1)
+                .ok_or(KdtErr::StreamTooLong)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Decrypts a stream made by `KdtCryptoHandler::encrypt_stream` with
+    /// the provided owned key set, writing the plaintext to `writer` one
+    /// chunk at a time. Fails as soon as a chunk doesn't authenticate, or
+    /// if the stream ends before its final chunk.
+    pub fn decrypt_stream<R: Read, W: Write>(
+        reader: &mut R, writer: &mut W, keyset: &OwnedKeySet,
+    ) -> Result<(), Box<dyn Error>> {
+        Self::decrypt_stream_with_keysets(reader, writer, std::slice::from_ref(keyset))
+    }
+
+    /// Decrypts a stream made by `KdtCryptoHandler::encrypt_stream` with
+    /// whichever of the provided owned key sets it was encrypted to. See
+    /// `KdtCryptoHandler::decrypt_bytes_with_keysets`.
+    pub fn decrypt_stream_with_keysets<R: Read, W: Write>(
+        reader: &mut R, writer: &mut W, keysets: &[OwnedKeySet],
+    ) -> Result<(), Box<dyn Error>> {
+        let header = KdtStreamHeader::read_from(reader)?;
+        let content_key = keysets
+            .iter()
+            .find_map(|keyset| {
+                header
+                    .recipients
+                    .iter()
+                    .filter(|r| r.may_be_for(&keyset.pubkey_pair))
+                    .find_map(|r| Self::unwrap_key(r, keyset, &header.header).ok())
+            })
+            .ok_or(KdtErr::NotForThisKey)?;
+        let aead = header.header.aead;
+        let associated_data = header.associated_data();
+
+        let sealed_chunk_size = STREAM_CHUNK_SIZE + AEAD_TAG_SIZE;
+        let mut chunk = vec![0u8; sealed_chunk_size];
+        let mut next_chunk = vec![0u8; sealed_chunk_size];
+        let mut len = read_full(reader, &mut chunk)?;
+        let mut counter = 0u32;
+        loop {
+            if len < AEAD_TAG_SIZE {
+                return Err(Box::new(KdtErr::StreamTruncated));
+            }
+            let next_len = if len == sealed_chunk_size {
+                read_full(reader, &mut next_chunk)?
+            } else {
+                0
+            };
+            let last = next_len == 0;
+            let nonce = header.chunk_nonce(counter, last);
+            let plaintext = aead
+                .open(&content_key, &nonce, &chunk[..len], &associated_data)
+                .ok_or(KdtErr::StreamCorrupted)?;
+            writer.write_all(&plaintext)?;
+            if last {
+                break;
+            }
+            std::mem::swap(&mut chunk, &mut next_chunk);
+            len = next_len;
+            counter = counter
+                .checked_add(1)
+                .ok_or(KdtErr::StreamTooLong)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Derives a 256 bit content key from a passphrase with Argon2id.
+    /// Costs above the `PASSPHRASE_MAX_*` bounds are refused.
+    fn derive_passphrase_key(
+        passphrase: &str, params: &KdtPassphraseParams,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        if params.m_cost > PASSPHRASE_MAX_M_COST
+            || params.t_cost > PASSPHRASE_MAX_T_COST
+            || params.p_cost > PASSPHRASE_MAX_P_COST
+        {
+            return Err(Box::new(KdtErr::BadPassphraseParams));
+        }
+        let argon2_params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))
+            .map_err(|_| KdtErr::BadPassphraseParams)?;
+        let mut key = vec![0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params)
+            .hash_password_into(passphrase.as_bytes(), &params.salt, &mut key)
+            .map_err(|_| KdtErr::BadPassphraseParams)?;
+        Ok(key)
+    }
+
+    /// Wraps the content key for a single recipient by encrypting it
+    /// with a key derived from the shared secret of a fresh Kyber
+    /// encapsulation, at the level of the recipient's key. For hybrid
+    /// keys, that secret is first combined with the result of an X25519
+    /// key exchange against a fresh ephemeral key. The message header
+    /// is authenticated along with it. Unless the recipients are
+    /// anonymous, the entry names the key it was made for.
+    fn wrap_key(
+        content_key: &[u8], pubkey: &PubKeyPair, header: &KdtMessageHeader,
+        options: KdtEncryptOptions,
+    ) -> Result<KdtRecipient, Box<dyn Error>> {
+        let mut rng = rand::thread_rng();
+        let kem = pubkey.kem();
+        let (encrypted_secret, kyber_secret) =
+            encapsulate(kem.level().params(), &pubkey.crypto_key, &mut rng)?;
+        let (secret_bytes, ephemeral_key) = match &pubkey.hybrid_crypto_key {
+            Some(hybrid_key) => {
+                let recipient_key = X25519PublicKey::from(Self::x25519_bytes(hybrid_key)?);
+                let ephemeral_secret = X25519EphemeralKey::random_from_rng(&mut rng);
+                let ephemeral_key = X25519PublicKey::from(&ephemeral_secret)
+                    .to_bytes()
+                    .to_vec();
+                let x25519_secret = ephemeral_secret.diffie_hellman(&recipient_key);
+                // A low-order recipient key would make the X25519 half
+                // of the secret predictable.
+                if !x25519_secret.was_contributory() {
+                    return Err(Box::new(KdtErr::BadHybridKey));
+                }
+                let secret_bytes = Self::combine_secrets(
+                    &kyber_secret,
+                    x25519_secret.as_bytes(),
+                    &encrypted_secret,
+                    &ephemeral_key,
+                    hybrid_key,
+                );
+                (secret_bytes, ephemeral_key)
+            }
+            None => (kyber_secret.to_vec(), Vec::new()),
+        };
+        let wrapping_key = Self::derive_wrapping_key(&secret_bytes, &encrypted_secret, &pubkey.id);
+        let nonce = header.aead.generate_nonce();
+        let wrapped_key = header
+            .aead
+            .seal(&wrapping_key, &nonce, content_key, &header.to_bytes());
+
+        let recipient = KdtRecipient::new(kem, encrypted_secret, wrapped_key, nonce, ephemeral_key);
+        if options.anonymous {
+            return Ok(recipient);
+        }
+
+        Ok(recipient.with_key_id(pubkey.id.clone()))
+    }
+
+    /// Tries to recover the content key from a recipient entry with the
+    /// given owned key set. Kyber rejects implicitly, ie decapsulating
+    /// with the wrong key doesn't fail but yields a random secret, so it's
+    /// the authenticated key wrapping that tells whether the entry was
+    /// made for this key. If it wasn't, this returns
+    /// `KdtErr::NotForThisKey`. Legacy entries can't be checked here, so
+    /// their shared secret is returned as-is.
+    fn unwrap_key(
+        recipient: &KdtRecipient, keyset: &OwnedKeySet, header: &KdtMessageHeader,
+    ) -> Result<Vec<u8>, KdtErr> {
+        // Entries of a different level can't be meant for this key.
+        let level = keyset.privkey_pair.kyber_level;
+        if recipient.kem.level() != level {
+            return Err(KdtErr::NotForThisKey);
+        }
+        let kyber_secret = decapsulate(
+            level.params(),
+            &recipient.encrypted_secret,
+            &keyset.privkey_pair.crypto_key,
         )
-        .into()
+        .map_err(|_| KdtErr::NotForThisKey)?;
+        if header.is_legacy() {
+            return Ok(kyber_secret.to_vec());
+        }
+        let secret_bytes = if recipient.is_hybrid() {
+            // Hybrid entries can only be meant for hybrid keys.
+            let (hybrid_key, hybrid_secret) = match (
+                &keyset.pubkey_pair.hybrid_crypto_key,
+                &keyset.privkey_pair.hybrid_crypto_key,
+            ) {
+                (Some(hybrid_key), Some(hybrid_secret)) => (hybrid_key, hybrid_secret),
+                _ => return Err(KdtErr::NotForThisKey),
+            };
+            let secret_key = X25519SecretKey::from(
+                Self::x25519_bytes(hybrid_secret).map_err(|_| KdtErr::NotForThisKey)?,
+            );
+            let ephemeral_key = X25519PublicKey::from(
+                Self::x25519_bytes(&recipient.ephemeral_key).map_err(|_| KdtErr::NotForThisKey)?,
+            );
+            let x25519_secret = secret_key.diffie_hellman(&ephemeral_key);
+            Self::combine_secrets(
+                &kyber_secret,
+                x25519_secret.as_bytes(),
+                &recipient.encrypted_secret,
+                &recipient.ephemeral_key,
+                hybrid_key,
+            )
+        } else {
+            kyber_secret.to_vec()
+        };
+        let wrapping_key = Self::derive_wrapping_key(
+            &secret_bytes,
+            &recipient.encrypted_secret,
+            &keyset.pubkey_pair.id,
+        );
+        header
+            .aead
+            .open(
+                &wrapping_key,
+                &recipient.nonce,
+                &recipient.wrapped_key,
+                &header.to_bytes(),
+            )
+            .ok_or(KdtErr::NotForThisKey)
+    }
+
+    /// Derives the key that wraps the content key for a single recipient
+    /// from their shared secret, with HKDF-SHA256. The KEM ciphertext and
+    /// the recipient's public key id are part of the info string, so the
+    /// wrapped key only opens for the encapsulation and the recipient it
+    /// was made for.
+    fn derive_wrapping_key(
+        shared_secret: &[u8], encrypted_secret: &[u8], recipient_id: &str,
+    ) -> Vec<u8> {
+        let info = [KEY_WRAP_LABEL, encrypted_secret, recipient_id.as_bytes()].concat();
+        let mut key = vec![0u8; 32];
+        Hkdf::<Sha256>::new(None, shared_secret)
+            .expand(&info, &mut key)
+            .unwrap();
+        key
+    }
+
+    /// Combines the Kyber and X25519 shared secrets into a single 256 bit
+    /// key with HKDF-SHA256. Both secrets go into the input keying
+    /// material, so the result stays secret as long as either of them
+    /// does. The Kyber ciphertext and both X25519 public keys are bound
+    /// into the info string, in the same spirit as X-Wing, so a secret
+    /// can't be replayed against a different encapsulation.
+    fn combine_secrets(
+        kyber_secret: &[u8], x25519_secret: &[u8], encrypted_secret: &[u8], ephemeral_key: &[u8],
+        recipient_key: &[u8],
+    ) -> Vec<u8> {
+        let ikm = [kyber_secret, x25519_secret].concat();
+        let info = [
+            HYBRID_COMBINER_LABEL,
+            encrypted_secret,
+            ephemeral_key,
+            recipient_key,
+        ]
+        .concat();
+        let mut key = vec![0u8; 32];
+        Hkdf::<Sha256>::new(None, &ikm)
+            .expand(&info, &mut key)
+            .unwrap();
+        key
+    }
+
+    /// Checks that `bytes` has the size of an X25519 key, and turns it
+    /// into the array `x25519_dalek` expects.
+    fn x25519_bytes(bytes: &[u8]) -> Result<[u8; 32], Box<dyn Error>> {
+        Ok(<[u8; 32]>::try_from(bytes).map_err(|_| KdtErr::BadHybridKey)?)
     }
 }
@@ -0,0 +1,216 @@
+// -- imports --
+use crate::core::*;
+use pqc_kyber::KyberLevel;
+use std::fmt;
+
+// -- message format versions --
+/// The original KDT 0.1 format. The Kyber shared secret was used
+/// directly as the content key, and nothing but the ciphertext itself
+/// was authenticated.
+pub const MESSAGE_VERSION_LEGACY: u8 = 1;
+
+/// The message format version this build of KDT writes, ie the version
+/// of the container messages are stored in.
+pub const MESSAGE_VERSION: u8 = CONTAINER_VERSION;
+
+// -- Kyber security levels --
+/// Kyber parameter sets a key set can be generated with. Every level is
+/// supported at runtime, so keys of any level can talk to each other.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum KdtKyberLevel {
+    /// Kyber-512, roughly as strong as AES-128. Smaller keys and
+    /// ciphertexts, for peers that can't afford the bigger ones.
+    Kyber512,
+
+    /// Kyber-768, roughly as strong as AES-192.
+    Kyber768,
+
+    /// Kyber-1024, roughly as strong as AES-256. What every key set was
+    /// before the level could be picked, hence the serde default.
+    #[default]
+    Kyber1024,
+
+    /// ML-KEM-512, the FIPS 203 standard of Kyber-512.
+    MlKem512,
+
+    /// ML-KEM-768, the FIPS 203 standard of Kyber-768.
+    MlKem768,
+
+    /// ML-KEM-1024, the FIPS 203 standard of Kyber-1024, and what new key
+    /// sets get.
+    MlKem1024,
+}
+
+impl KdtKyberLevel {
+    /// The level new key sets are generated with, unless asked otherwise.
+    pub const RECOMMENDED: Self = Self::MlKem1024;
+
+    /// Restores the level from its name, which is either the bare number
+    /// of a Kyber parameter set (eg "768") or the full name of any (eg
+    /// "kyber768" or "ml-kem-768").
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "512" | "kyber512" => Some(Self::Kyber512),
+            "768" | "kyber768" => Some(Self::Kyber768),
+            "1024" | "kyber1024" => Some(Self::Kyber1024),
+            "ml-kem-512" => Some(Self::MlKem512),
+            "ml-kem-768" => Some(Self::MlKem768),
+            "ml-kem-1024" => Some(Self::MlKem1024),
+            _ => None,
+        }
+    }
+
+    /// The matching parameter set of the Kyber library.
+    #[inline(always)]
+    pub fn params(&self) -> KyberLevel {
+        match self {
+            Self::Kyber512 => KyberLevel::Kyber512,
+            Self::Kyber768 => KyberLevel::Kyber768,
+            Self::Kyber1024 => KyberLevel::Kyber1024,
+            Self::MlKem512 => KyberLevel::MlKem512,
+            Self::MlKem768 => KyberLevel::MlKem768,
+            Self::MlKem1024 => KyberLevel::MlKem1024,
+        }
+    }
+}
+
+impl fmt::Display for KdtKyberLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Kyber512 => write!(f, "kyber512"),
+            Self::Kyber768 => write!(f, "kyber768"),
+            Self::Kyber1024 => write!(f, "kyber1024"),
+            Self::MlKem512 => write!(f, "ml-kem-512"),
+            Self::MlKem768 => write!(f, "ml-kem-768"),
+            Self::MlKem1024 => write!(f, "ml-kem-1024"),
+        }
+    }
+}
+
+// -- key encapsulation mechanisms --
+/// Ways a recipient's shared secret can be established.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KdtKem {
+    /// Kyber on its own.
+    Kyber(KdtKyberLevel),
+
+    /// Kyber combined with an X25519 key exchange, for hybrid keys.
+    KyberX25519(KdtKyberLevel),
+}
+
+impl KdtKem {
+    /// Creates the mechanism keys of the given level use, combined with
+    /// X25519 or not.
+    #[inline(always)]
+    pub fn new(level: KdtKyberLevel, hybrid: bool) -> Self {
+        match hybrid {
+            true => Self::KyberX25519(level),
+            false => Self::Kyber(level),
+        }
+    }
+
+    /// Numeric id of the mechanism, as stored in containers. Kyber-1024
+    /// keeps the ids it had before the level could be picked.
+    pub fn id(&self) -> u8 {
+        match self {
+            Self::Kyber(KdtKyberLevel::Kyber1024) => 1,
+            Self::KyberX25519(KdtKyberLevel::Kyber1024) => 2,
+            Self::Kyber(KdtKyberLevel::Kyber512) => 3,
+            Self::Kyber(KdtKyberLevel::Kyber768) => 4,
+            Self::KyberX25519(KdtKyberLevel::Kyber512) => 5,
+            Self::KyberX25519(KdtKyberLevel::Kyber768) => 6,
+            Self::Kyber(KdtKyberLevel::MlKem512) => 7,
+            Self::Kyber(KdtKyberLevel::MlKem768) => 8,
+            Self::Kyber(KdtKyberLevel::MlKem1024) => 9,
+            Self::KyberX25519(KdtKyberLevel::MlKem512) => 10,
+            Self::KyberX25519(KdtKyberLevel::MlKem768) => 11,
+            Self::KyberX25519(KdtKyberLevel::MlKem1024) => 12,
+        }
+    }
+
+    /// Restores the mechanism from its numeric id.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Self::Kyber(KdtKyberLevel::Kyber1024)),
+            2 => Some(Self::KyberX25519(KdtKyberLevel::Kyber1024)),
+            3 => Some(Self::Kyber(KdtKyberLevel::Kyber512)),
+            4 => Some(Self::Kyber(KdtKyberLevel::Kyber768)),
+            5 => Some(Self::KyberX25519(KdtKyberLevel::Kyber512)),
+            6 => Some(Self::KyberX25519(KdtKyberLevel::Kyber768)),
+            7 => Some(Self::Kyber(KdtKyberLevel::MlKem512)),
+            8 => Some(Self::Kyber(KdtKyberLevel::MlKem768)),
+            9 => Some(Self::Kyber(KdtKyberLevel::MlKem1024)),
+            10 => Some(Self::KyberX25519(KdtKyberLevel::MlKem512)),
+            11 => Some(Self::KyberX25519(KdtKyberLevel::MlKem768)),
+            12 => Some(Self::KyberX25519(KdtKyberLevel::MlKem1024)),
+            _ => None,
+        }
+    }
+
+    /// The Kyber level of the mechanism.
+    #[inline(always)]
+    pub fn level(&self) -> KdtKyberLevel {
+        match self {
+            Self::Kyber(level) | Self::KyberX25519(level) => *level,
+        }
+    }
+
+    /// Whether the mechanism involves X25519 as well as Kyber.
+    #[inline(always)]
+    pub fn is_hybrid(&self) -> bool {
+        matches!(self, Self::KyberX25519(_))
+    }
+}
+
+impl fmt::Display for KdtKem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Kyber(level) => write!(f, "{}", level),
+            Self::KyberX25519(level) => write!(f, "{}+x25519", level),
+        }
+    }
+}
+
+// -- message header --
+/// Describes how a message was put together. Every field ends up in
+/// the associated data of the message's AEAD operations, so changing
+/// any of them makes decryption fail rather than quietly changing how
+/// the message is read.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KdtMessageHeader {
+    /// Format version of the message.
+    pub version: u8,
+
+    /// Cipher the content (and every wrapped content key) is sealed with.
+    pub aead: KdtAead,
+}
+
+impl KdtMessageHeader {
+    /// Creates a header for a new message in the current format.
+    pub fn new(aead: KdtAead) -> Self {
+        Self {
+            version: MESSAGE_VERSION,
+            aead,
+        }
+    }
+
+    /// The implied header of a KDT 0.1 message, which didn't have one.
+    pub fn legacy() -> Self {
+        Self {
+            version: MESSAGE_VERSION_LEGACY,
+            aead: KdtAead::Aes256Gcm,
+        }
+    }
+
+    /// Whether this is a KDT 0.1 message.
+    #[inline(always)]
+    pub fn is_legacy(&self) -> bool {
+        self.version == MESSAGE_VERSION_LEGACY
+    }
+
+    /// The binary form of the header, used as associated data when
+    /// wrapping content keys.
+    pub fn to_bytes(self) -> Vec<u8> {
+        [CONTAINER_MAGIC, &[self.version, self.aead.id()]].concat()
+    }
+}
@@ -1,5 +1,16 @@
+// -- compiler flags --
+#![allow(dead_code)]
+
+pub mod aead;
 pub mod encrypted_message;
 pub mod handler;
+pub mod header;
+pub mod options;
+pub mod stream;
 
+pub use aead::*;
 pub use encrypted_message::*;
 pub use handler::*;
+pub use header::*;
+pub use options::*;
+pub use stream::*;
@@ -0,0 +1,39 @@
+// -- imports --
+use crate::core::*;
+
+// -- encryption options --
+/// Knobs for public key encryption. The defaults are what KDT does when
+/// nothing else is asked for.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct KdtEncryptOptions {
+    /// Leaves the recipients' public key ids out of the message, so it
+    /// doesn't reveal who it's for. Recipients then have to try every
+    /// owned key set they have to decrypt it.
+    pub anonymous: bool,
+
+    /// The AEAD the content (and every wrapped content key) is sealed
+    /// with.
+    pub aead: KdtAead,
+}
+
+impl KdtEncryptOptions {
+    /// Creates the default options.
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the recipients stay anonymous.
+    #[inline(always)]
+    pub fn with_anonymous(mut self, anonymous: bool) -> Self {
+        self.anonymous = anonymous;
+        self
+    }
+
+    /// Sets the AEAD to seal the message with.
+    #[inline(always)]
+    pub fn with_aead(mut self, aead: KdtAead) -> Self {
+        self.aead = aead;
+        self
+    }
+}
@@ -0,0 +1,176 @@
+// -- imports --
+use crate::core::*;
+use sha2::{
+    Digest,
+    Sha256,
+};
+use std::io::{
+    self,
+    Read,
+    Write,
+};
+
+// -- stream constants --
+/// Amount of plaintext sealed into every chunk of a stream. Only the
+/// last chunk may be shorter than this.
+pub const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Size of the chunk counter and final-chunk marker at the end of every
+/// chunk nonce.
+pub const STREAM_NONCE_SUFFIX_SIZE: usize = 5;
+
+/// Largest stream header we're willing to read. Even with hundreds of
+/// recipients, a header stays well below this.
+pub const STREAM_MAX_HEADER_SIZE: usize = 1 << 20;
+
+// -- container record tags --
+/// Id of the AEAD the chunks are sealed with.
+pub const STREAM_AEAD: u8 = 1;
+/// The random per-stream nonce prefix.
+pub const STREAM_NONCE_PREFIX: u8 = 2;
+/// A recipient, as nested records. Repeated once per recipient.
+pub const STREAM_RECIPIENT: u8 = 3;
+
+// -- stream header --
+/// Header written in front of every encrypted KDT stream. It holds
+/// everything needed to recover the content key, and the nonce prefix
+/// every chunk nonce is derived from.
+pub struct KdtStreamHeader {
+    /// Format version and algorithms of the stream.
+    pub header: KdtMessageHeader,
+
+    /// Everyone who can decrypt this stream, each with their own
+    /// wrapped copy of the content key.
+    pub recipients: Vec<KdtRecipient>,
+
+    /// Random per-stream nonce prefix.
+    pub nonce_prefix: Vec<u8>,
+
+    /// The serialized header exactly as it was read, records this
+    /// version doesn't know included. `None` for headers made here.
+    pub raw: Option<Vec<u8>>,
+}
+
+impl KdtStreamHeader {
+    /// Creates a new `KdtStreamHeader` from the given message header,
+    /// recipients and nonce prefix.
+    pub fn new(
+        header: KdtMessageHeader, recipients: Vec<KdtRecipient>, nonce_prefix: Vec<u8>,
+    ) -> Self {
+        Self {
+            header,
+            recipients,
+            nonce_prefix,
+            raw: None,
+        }
+    }
+
+    /// The container holding this header.
+    pub fn to_container(&self) -> KdtContainer {
+        let mut records = KdtRecords::new();
+        records.push(STREAM_AEAD, [self.header.aead.id()]);
+        records.push(STREAM_NONCE_PREFIX, self.nonce_prefix.clone());
+        for recipient in &self.recipients {
+            records.push(STREAM_RECIPIENT, recipient.to_records().encode());
+        }
+        KdtContainer::new(KdtContainerKind::Stream, records)
+    }
+
+    /// Writes the binary form of the header to `writer`. It's a regular
+    /// container, except that the records are prefixed with their total
+    /// length as a big-endian `u32`, so readers know where the header
+    /// ends and the chunks begin.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let bytes = self.to_container().to_bytes();
+        let (prefix, records) = bytes.split_at(CONTAINER_MAGIC.len() + 2);
+        writer.write_all(prefix)?;
+        writer.write_all(&(records.len() as u32).to_be_bytes())?;
+        writer.write_all(records)
+    }
+
+    /// Reads a header back from the start of a KDT stream.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, Box<dyn Error>> {
+        let mut prefix = [0u8; CONTAINER_MAGIC.len() + 2];
+        reader.read_exact(&mut prefix)?;
+        if !prefix.starts_with(CONTAINER_MAGIC) {
+            return Err(Box::new(KdtErr::BadStreamHeader));
+        }
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len > STREAM_MAX_HEADER_SIZE {
+            return Err(Box::new(KdtErr::BadStreamHeader));
+        }
+        let mut records = vec![0u8; len];
+        reader.read_exact(&mut records)?;
+
+        let raw = [&prefix[..], &records].concat();
+        let container = KdtContainer::from_bytes(&raw, KdtContainerKind::Stream)?;
+        let records = &container.records;
+        let header = KdtMessageHeader {
+            version: container.version,
+            aead: KdtAead::from_id(records.require_u8(STREAM_AEAD)?)
+                .ok_or(KdtErr::UnknownAlgorithm)?,
+        };
+        let recipients = records
+            .get_all(STREAM_RECIPIENT)
+            .map(|r| KdtRecipient::from_records(&KdtRecords::decode(r)?))
+            .collect::<Result<_, _>>()?;
+        let nonce_prefix = records.require(STREAM_NONCE_PREFIX)?.to_vec();
+        if nonce_prefix.len() != stream_nonce_prefix_size(header.aead) {
+            return Err(Box::new(KdtErr::BadStreamHeader));
+        }
+
+        Ok(Self {
+            raw: Some(raw),
+            ..Self::new(header, recipients, nonce_prefix)
+        })
+    }
+
+    /// Associated data for every chunk of the stream: a hash of the whole
+    /// serialized header, as it was read if it was. Hashing it keeps the
+    /// per-chunk overhead small, no matter how many recipients the stream
+    /// has.
+    pub fn associated_data(&self) -> Vec<u8> {
+        match &self.raw {
+            Some(raw) => Sha256::digest(raw).to_vec(),
+            None => Sha256::digest(self.to_container().to_bytes()).to_vec(),
+        }
+    }
+
+    /// Builds the nonce for the chunk at position `counter`. Following
+    /// the STREAM construction, it's the nonce prefix, then the counter
+    /// as a big-endian `u32`, then a byte that is `1` only for the last
+    /// chunk. Reordered chunks and truncated streams therefore fail
+    /// authentication.
+    pub fn chunk_nonce(&self, counter: u32, last: bool) -> Vec<u8> {
+        let mut nonce = self.nonce_prefix.clone();
+        nonce.extend_from_slice(&counter.to_be_bytes());
+        nonce.push(last as u8);
+        nonce
+    }
+}
+
+// -- binary helpers --
+/// Size of the random nonce prefix stored in the header of a stream
+/// sealed with the given AEAD. The rest of every chunk nonce is the chunk
+/// counter and the final-chunk marker.
+pub fn stream_nonce_prefix_size(aead: KdtAead) -> usize {
+    aead.nonce_size() - STREAM_NONCE_SUFFIX_SIZE
+}
+
+/// Reads from `reader` until `buf` is full or the reader runs dry, and
+/// returns the amount of bytes read. Unlike `Read::read_exact`, hitting
+/// the end of the input isn't an error.
+pub fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
@@ -0,0 +1,40 @@
+// -- ascii armor --
+/// GPG-style armor around base64 data: the data broken into lines of 64
+/// characters, between a `-----BEGIN <LABEL>-----` and an
+/// `-----END <LABEL>-----` line.
+pub struct Armor;
+
+impl Armor {
+    /// Armors `body` with the given label.
+    pub fn wrap(label: &str, body: &str) -> String {
+        let body = body
+            .chars()
+            .enumerate()
+            // Inserts a new line at every nth (n = multiple of 64)
+            // character, just like GPG.
+            .flat_map(|(i, c)| {
+                if (i + 1) % 64 == 0 {
+                    vec![c, '\n']
+                } else {
+                    vec![c]
+                }
+            })
+            .collect::<String>();
+        format!(
+            "-----BEGIN {}-----\n{}\n-----END {}-----",
+            label,
+            body.trim_end(),
+            label
+        )
+    }
+
+    /// Strips the armor lines off `armored`, and joins what's left back
+    /// into a single line.
+    pub fn unwrap(armored: &str) -> String {
+        armored
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with("-----"))
+            .collect()
+    }
+}
@@ -3,7 +3,10 @@ use base64::{
     engine::general_purpose,
     Engine as _,
 };
-use std::fmt;
+use std::{
+    error::Error,
+    fmt,
+};
 
 // -- simple base64 interface --
 /// Incredibly minimalist base64 conversion object. Can
@@ -18,11 +21,10 @@ impl Base64 {
         general_purpose::STANDARD.encode(b)
     }
 
-    /// Converts a base64 string to a bytearray.
+    /// Converts a base64 string to a bytearray, without panicking on
+    /// invalid input.
     #[inline(always)]
-    pub fn decode_string<S: fmt::Display>(s: S) -> Vec<u8> {
-        general_purpose::STANDARD
-            .decode(s.to_string())
-            .unwrap()
+    pub fn try_decode_string<S: fmt::Display>(s: S) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(general_purpose::STANDARD.decode(s.to_string())?)
     }
 }
@@ -0,0 +1,215 @@
+// -- imports --
+use crate::core::*;
+
+// -- container constants --
+/// Magic bytes every KDT container starts with.
+pub const CONTAINER_MAGIC: &[u8] = b"KDT";
+
+/// The container format version this build of KDT writes. Version 1 is
+/// the `*`-separated text format of KDT 0.1, which had no container.
+pub const CONTAINER_VERSION: u8 = 2;
+
+/// Largest record value we're willing to read, so a corrupted length
+/// can't make us allocate arbitrary amounts of memory. Only ciphertexts
+/// ever come close, and huge inputs belong in streams anyway.
+pub const MAX_RECORD_SIZE: usize = 1 << 30;
+
+// -- container kinds --
+/// What a container holds.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KdtContainerKind {
+    /// An encrypted message.
+    Message = 1,
+
+    /// The signature block of a signed message.
+    Signature = 2,
+
+    /// A public key pair.
+    PubKey = 3,
+
+    /// A private key pair.
+    PrivKey = 4,
+
+    /// The header of an encrypted stream.
+    Stream = 5,
+
+    /// A signature over some data that's kept separately.
+    DetachedSignature = 6,
+}
+
+impl KdtContainerKind {
+    /// Restores the kind from its numeric id.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Self::Message),
+            2 => Some(Self::Signature),
+            3 => Some(Self::PubKey),
+            4 => Some(Self::PrivKey),
+            5 => Some(Self::Stream),
+            6 => Some(Self::DetachedSignature),
+            _ => None,
+        }
+    }
+}
+
+// -- records --
+/// A single TLV record: a tag saying what the value is, then the value
+/// itself, prefixed with its length as a big-endian `u32`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct KdtRecord {
+    pub tag: u8,
+    pub value: Vec<u8>,
+}
+
+/// An ordered list of records. Readers look records up by tag and skip
+/// tags they don't know, which is what lets optional fields be added
+/// without breaking older versions of KDT.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct KdtRecords(pub Vec<KdtRecord>);
+
+impl KdtRecords {
+    /// Creates an empty record list.
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Appends a record.
+    pub fn push<V: Into<Vec<u8>>>(&mut self, tag: u8, value: V) {
+        self.0.push(KdtRecord {
+            tag,
+            value: value.into(),
+        });
+    }
+
+    /// The value of the first record with the given tag, if any.
+    pub fn get(&self, tag: u8) -> Option<&[u8]> {
+        self.get_all(tag).next()
+    }
+
+    /// The values of every record with the given tag, in order.
+    pub fn get_all(&self, tag: u8) -> impl Iterator<Item = &[u8]> {
+        self.0
+            .iter()
+            .filter(move |r| r.tag == tag)
+            .map(|r| r.value.as_slice())
+    }
+
+    /// Like `KdtRecords::get`, but the record has to be there.
+    pub fn require(&self, tag: u8) -> Result<&[u8], Box<dyn Error>> {
+        Ok(self.get(tag).ok_or(KdtErr::BadContainer)?)
+    }
+
+    /// Reads a required single-byte record, eg an algorithm id.
+    pub fn require_u8(&self, tag: u8) -> Result<u8, Box<dyn Error>> {
+        match self.require(tag)? {
+            [value] => Ok(*value),
+            _ => Err(Box::new(KdtErr::BadContainer)),
+        }
+    }
+
+    /// Reads a required big-endian `u32` record.
+    pub fn require_u32(&self, tag: u8) -> Result<u32, Box<dyn Error>> {
+        let bytes = <[u8; 4]>::try_from(self.require(tag)?).map_err(|_| KdtErr::BadContainer)?;
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    /// The binary form of the records.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for record in &self.0 {
+            bytes.push(record.tag);
+            bytes.extend_from_slice(&(record.value.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&record.value);
+        }
+        bytes
+    }
+
+    /// Restores records from their binary form.
+    pub fn decode(mut bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut records = Self::new();
+        while !bytes.is_empty() {
+            if bytes.len() < 5 {
+                return Err(Box::new(KdtErr::BadContainer));
+            }
+            let tag = bytes[0];
+            let len = u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]) as usize;
+            bytes = &bytes[5..];
+            if len > bytes.len() || len > MAX_RECORD_SIZE {
+                return Err(Box::new(KdtErr::BadContainer));
+            }
+            records.push(tag, &bytes[..len]);
+            bytes = &bytes[len..];
+        }
+        Ok(records)
+    }
+
+    /// A copy of the records, minus every record with the given tag.
+    pub fn without(&self, tag: u8) -> Self {
+        Self(
+            self.0
+                .iter()
+                .filter(|r| r.tag != tag)
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+// -- container --
+/// Self-describing binary container every KDT object is stored in. It
+/// starts with `CONTAINER_MAGIC`, the format version and the kind of
+/// object, followed by the object's records.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct KdtContainer {
+    pub version: u8,
+    pub kind: KdtContainerKind,
+    pub records: KdtRecords,
+}
+
+impl KdtContainer {
+    /// Creates a container of the current version.
+    pub fn new(kind: KdtContainerKind, records: KdtRecords) -> Self {
+        Self {
+            version: CONTAINER_VERSION,
+            kind,
+            records,
+        }
+    }
+
+    /// Whether `bytes` look like a KDT container, as opposed to data from
+    /// KDT 0.1.
+    pub fn is_container(bytes: &[u8]) -> bool {
+        bytes.len() > CONTAINER_MAGIC.len() + 1 && bytes.starts_with(CONTAINER_MAGIC)
+    }
+
+    /// The binary form of the container.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [
+            CONTAINER_MAGIC,
+            &[self.version, self.kind as u8],
+            &self.records.encode(),
+        ]
+        .concat()
+    }
+
+    /// Restores a container of the given kind from its binary form.
+    /// Containers of a version we can't read are rejected outright.
+    pub fn from_bytes(bytes: &[u8], kind: KdtContainerKind) -> Result<Self, Box<dyn Error>> {
+        if !Self::is_container(bytes) {
+            return Err(Box::new(KdtErr::BadContainer));
+        }
+        let version = bytes[CONTAINER_MAGIC.len()];
+        if version != CONTAINER_VERSION {
+            return Err(Box::new(KdtErr::UnsupportedVersion(version)));
+        }
+        if KdtContainerKind::from_id(bytes[CONTAINER_MAGIC.len() + 1]) != Some(kind) {
+            return Err(Box::new(KdtErr::BadContainer));
+        }
+
+        Ok(Self {
+            version,
+            kind,
+            records: KdtRecords::decode(&bytes[CONTAINER_MAGIC.len() + 2..])?,
+        })
+    }
+}
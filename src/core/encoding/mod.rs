@@ -1,3 +1,7 @@
+pub mod armor;
 pub mod base64;
+pub mod container;
 
+pub use self::armor::*;
 pub use self::base64::*;
+pub use self::container::*;
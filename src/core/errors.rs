@@ -13,6 +13,42 @@ pub enum KdtErr {
     DbDumpFailed,
     KeyAlreadyExists,
     BadKeyId,
+    NoRecipients,
+    NotForThisKey,
+    BadStreamHeader,
+    StreamTruncated,
+    StreamCorrupted,
+    StreamTooLong,
+    DecryptionFailed,
+    NeedsCryptoMode,
+    UnsupportedCombination,
+    NotPassphraseProtected,
+    WrongPassphrase,
+    BadPassphraseParams,
+    PassphraseMismatch,
+    EmptyPassphrase,
+    BadHybridKey,
+    HybridNeedsGenKey,
+    KyberLevelNeedsGenKey,
+    DilithiumLevelNeedsGenKey,
+    SlhDsaNeedsGenKey,
+    AnonymousNeedsEncrypt,
+    AeadNeedsEncrypt,
+    DetachNeedsSignOrVerify,
+    SignatureNeedsDetachedVerify,
+    NoDetachedSignature,
+    NotationNeedsSign,
+    HashNeedsSign,
+    ContextNeedsSign,
+    ContextTooLong,
+    CosignNeedsSign,
+    AlreadySigned,
+    PolicyNeedsVerify,
+    BadPolicy,
+    BadContainer,
+    BadSignedMessage,
+    UnsupportedVersion(u8),
+    UnknownAlgorithm,
 }
 
 impl fmt::Display for KdtErr {
@@ -20,13 +56,116 @@ impl fmt::Display for KdtErr {
         match self {
             Self::TooManyArgs => write!(
                 f,
-                "Too many arguments were passed! You can only use one argument at a time."
+                "Too many arguments were passed! You can only use one argument at a time, apart from combining `--sign` with `--encrypt`."
             ),
             Self::PubDbOpenFailed => write!(f, "Failed to open public keys database!"),
             Self::PrivDbOpenFailed => write!(f, "Failed to open private keys database!"),
             Self::DbDumpFailed => write!(f, "Failed to dump to database!"),
             Self::KeyAlreadyExists => write!(f, "This key already exists in the database!"),
             Self::BadKeyId => write!(f, "The key id you passed is invalid!"),
+            Self::NoRecipients => write!(f, "You need at least one recipient to encrypt to!"),
+            Self::NotForThisKey => {
+                write!(f, "The message wasn't encrypted to any of the private keys that were tried!")
+            }
+            Self::BadStreamHeader => write!(f, "The input isn't a valid KDT stream!"),
+            Self::StreamTruncated => write!(f, "The KDT stream ended before its last chunk!"),
+            Self::StreamCorrupted => write!(
+                f,
+                "A chunk of the KDT stream failed authentication! It was either tampered with, reordered or truncated."
+            ),
+            Self::StreamTooLong => write!(f, "The KDT stream has too many chunks!"),
+            Self::DecryptionFailed => write!(
+                f,
+                "Failed to decrypt the message! It was either tampered with or encrypted with the wrong key."
+            ),
+            Self::NeedsCryptoMode => write!(
+                f,
+                "Streaming and binary mode only work together with encryption or decryption mode!"
+            ),
+            Self::UnsupportedCombination => {
+                write!(f, "This combination of arguments isn't supported!")
+            }
+            Self::NotPassphraseProtected => {
+                write!(f, "This message wasn't encrypted with a passphrase!")
+            }
+            Self::WrongPassphrase => write!(
+                f,
+                "Failed to decrypt the message! Either the passphrase is wrong or the message was tampered with."
+            ),
+            Self::BadPassphraseParams => {
+                write!(f, "The message's passphrase parameters are invalid!")
+            }
+            Self::PassphraseMismatch => write!(f, "The passphrases you entered don't match!"),
+            Self::EmptyPassphrase => write!(f, "The passphrase can't be empty!"),
+            Self::BadHybridKey => write!(f, "The key's X25519 part is invalid!"),
+            Self::BadContainer => write!(f, "The input isn't a valid KDT container!"),
+            Self::BadSignedMessage => write!(f, "The input isn't a valid KDT signed message!"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "The input uses version {} of the KDT format, which this version of KDT can't read!",
+                version
+            ),
+            Self::UnknownAlgorithm => {
+                write!(f, "The input uses an algorithm this version of KDT doesn't know!")
+            }
+            Self::HybridNeedsGenKey => {
+                write!(f, "`--hybrid` only makes sense together with `--gen-key`!")
+            }
+            Self::KyberLevelNeedsGenKey => {
+                write!(f, "`--kyber-level` only makes sense together with `--gen-key`!")
+            }
+            Self::DilithiumLevelNeedsGenKey => {
+                write!(f, "`--dilithium-level` only makes sense together with `--gen-key`!")
+            }
+            Self::SlhDsaNeedsGenKey => {
+                write!(f, "`--slh-dsa` only makes sense together with `--gen-key`!")
+            }
+            Self::AnonymousNeedsEncrypt => {
+                write!(f, "`--anonymous` only makes sense together with `--encrypt`!")
+            }
+            Self::AeadNeedsEncrypt => write!(
+                f,
+                "`--aead` only makes sense together with `--encrypt` or `--symmetric`!"
+            ),
+            Self::DetachNeedsSignOrVerify => {
+                write!(f, "`--detach` only makes sense together with `--sign` or `--verify`!")
+            }
+            Self::SignatureNeedsDetachedVerify => {
+                write!(
+                    f,
+                    "`--signature` only makes sense together with `--verify --detach` or `--sign --cosign --detach`!"
+                )
+            }
+            Self::NoDetachedSignature => write!(
+                f,
+                "Pass either the signed file with `--input` or the signature with `--signature`!"
+            ),
+            Self::NotationNeedsSign => {
+                write!(f, "`--notation` only makes sense together with `--sign`!")
+            }
+            Self::HashNeedsSign => {
+                write!(f, "`--hash` only makes sense together with `--sign`!")
+            }
+            Self::ContextNeedsSign => {
+                write!(f, "`--context` only makes sense together with `--sign`!")
+            }
+            Self::ContextTooLong => write!(
+                f,
+                "The context string can't be longer than {} bytes!",
+                crate::core::MAX_CONTEXT_SIZE
+            ),
+            Self::CosignNeedsSign => {
+                write!(f, "`--cosign` only makes sense together with `--sign`!")
+            }
+            Self::AlreadySigned => write!(f, "This key has already signed the input!"),
+            Self::PolicyNeedsVerify => write!(
+                f,
+                "`--policy` only makes sense together with `--verify`, without a public key id!"
+            ),
+            Self::BadPolicy => write!(
+                f,
+                "The policy isn't valid! It needs a threshold between 1 and the number of signers."
+            ),
         }
     }
 }
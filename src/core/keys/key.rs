@@ -1,7 +1,12 @@
 // -- imports --
 use crate::core::*;
+use ed25519_dalek::SigningKey as Ed25519SigningKey;
 use pqc_dilithium::Keypair as dilithium_keypair;
 use pqc_kyber::keypair as kyber_keypair;
+use x25519_dalek::{
+    PublicKey as X25519PublicKey,
+    StaticSecret as X25519SecretKey,
+};
 
 // -- fully controlled keyset (privkey pair + pubkey pair) --
 #[derive(Serialize, Deserialize, Clone)]
@@ -12,22 +17,72 @@ pub struct OwnedKeySet {
 
 impl OwnedKeySet {
     /// Generates a new key set (public, private; encryption, signage) on-demand.
+    /// Hybrid key sets also get X25519 and Ed25519 key pairs, so messages encrypted
+    /// to them stay confidential as long as either Kyber or X25519 holds up, and
+    /// their signatures can't be forged unless both Dilithium and Ed25519 break.
     /// No errors should occur here, but if they do they probably aren't our fault
     /// (take a look at the libraries we use - they're probably the culprit!)
-    pub fn generate(owner_name: String) -> Self {
-        let encryption_keys = kyber_keypair(&mut rand::thread_rng());
-        let signage_keys = dilithium_keypair::generate();
+    #[inline(always)]
+    pub fn generate(owner_name: String, hybrid: bool) -> Self {
+        Self::generate_with_options(owner_name, KdtKeyOptions::new().with_hybrid(hybrid))
+    }
+
+    /// Generates a new key set like `OwnedKeySet::generate`, with the
+    /// given options. The Kyber and Dilithium keys are made at the
+    /// requested levels, which both halves of the key set remember. Key
+    /// sets with an SLH-DSA level get an SLH-DSA key instead of the
+    /// Dilithium one.
+    pub fn generate_with_options(owner_name: String, options: KdtKeyOptions) -> Self {
+        let hybrid = options.hybrid;
+        let encryption_keys = kyber_keypair(options.kyber_level.params(), &mut rand::thread_rng());
+        let (signage_public, signage_secret) = match options.slh_dsa_level {
+            Some(level) => level.keypair(),
+            None => {
+                let keys = dilithium_keypair::generate(options.dilithium_level.params());
+                (keys.public.clone(), keys.expose_secret().to_vec())
+            }
+        };
+        let (hybrid_public, hybrid_secret) = if hybrid {
+            let secret = X25519SecretKey::random_from_rng(rand::thread_rng());
+            let public = X25519PublicKey::from(&secret);
+            (
+                Some(public.to_bytes().to_vec()),
+                Some(secret.to_bytes().to_vec()),
+            )
+        } else {
+            (None, None)
+        };
+        let (hybrid_verifying, hybrid_signing) = if hybrid {
+            let signing = Ed25519SigningKey::generate(&mut rand::thread_rng());
+            let verifying = signing.verifying_key();
+            (
+                Some(verifying.to_bytes().to_vec()),
+                Some(signing.to_bytes().to_vec()),
+            )
+        } else {
+            (None, None)
+        };
         let pubkey_pair = PubKeyPair::new(
-            encryption_keys.public.to_vec(),
-            signage_keys.public.to_vec(),
+            encryption_keys.public,
+            signage_public,
             owner_name.clone(),
         )
+        .with_kyber_level(options.kyber_level)
+        .with_dilithium_level(options.dilithium_level)
+        .with_slh_dsa_level(options.slh_dsa_level)
+        .with_hybrid_crypto_key(hybrid_public)
+        .with_hybrid_signage_key(hybrid_verifying)
         .init();
         let privkey_pair = PrivKeyPair::new(
-            encryption_keys.secret.to_vec(),
-            signage_keys.expose_secret().to_vec(),
+            encryption_keys.secret,
+            signage_secret,
             owner_name,
         )
+        .with_kyber_level(options.kyber_level)
+        .with_dilithium_level(options.dilithium_level)
+        .with_slh_dsa_level(options.slh_dsa_level)
+        .with_hybrid_crypto_key(hybrid_secret)
+        .with_hybrid_signage_key(hybrid_signing)
         .init();
 
         Self {
@@ -0,0 +1,173 @@
+// -- imports --
+use crate::core::*;
+use sha2::{
+    Digest,
+    Sha256,
+};
+
+// -- container record tags --
+/// Id of the KEM the key set encrypts with, which includes the level
+/// of its Kyber key.
+pub const KEY_KEM: u8 = 1;
+/// Kyber key bytes.
+pub const KEY_CRYPTO: u8 = 2;
+/// Id of the signature algorithm the key set signs with, which
+/// includes the level of its Dilithium or SLH-DSA key.
+pub const KEY_SIGNATURE_ALGORITHM: u8 = 3;
+/// Dilithium or SLH-DSA key bytes.
+pub const KEY_SIGNAGE: u8 = 4;
+/// Key owner's name, as UTF-8.
+pub const KEY_OWNER: u8 = 5;
+/// X25519 key bytes. Only there for hybrid key sets.
+pub const KEY_HYBRID_CRYPTO: u8 = 6;
+/// Ed25519 key bytes. Only there for hybrid key sets.
+pub const KEY_HYBRID_SIGNAGE: u8 = 7;
+
+// -- key material --
+/// Everything public and private key pairs have in common, and the way
+/// it's exchanged. Keys are stored in a container, armored with the
+/// given label, but the ids of key pairs are still derived from the
+/// `*`-separated KDT 0.1 format, so they stay the same across versions.
+/// See `KdtKeyMaterial::id`.
+pub struct KdtKeyMaterial {
+    pub crypto_key: Vec<u8>,
+    pub kyber_level: KdtKyberLevel,
+    pub signage_key: Vec<u8>,
+    pub dilithium_level: KdtDilithiumLevel,
+    pub slh_dsa_level: Option<KdtSlhDsaLevel>,
+    pub hybrid_crypto_key: Option<Vec<u8>>,
+    pub hybrid_signage_key: Option<Vec<u8>>,
+    pub owner: String,
+}
+
+impl KdtKeyMaterial {
+    /// The KEM keys with this material encrypt with.
+    pub fn kem(&self) -> KdtKem {
+        KdtKem::new(self.kyber_level, self.hybrid_crypto_key.is_some())
+    }
+
+    /// The signature algorithm keys with this material sign with.
+    pub fn signature_algorithm(&self) -> KdtSignatureAlgorithm {
+        KdtSignatureAlgorithm::of_key(
+            self.dilithium_level,
+            self.slh_dsa_level,
+            self.hybrid_signage_key.is_some(),
+        )
+    }
+
+    /// Whether key pairs with this material could've been made before
+    /// the levels could be picked, which is when ids were nothing but
+    /// the hash of the KDT 0.1 armor.
+    fn has_legacy_id(&self) -> bool {
+        self.kyber_level == KdtKyberLevel::Kyber1024
+            && self.dilithium_level == KdtDilithiumLevel::Dilithium3
+            && self.slh_dsa_level.is_none()
+    }
+
+    /// The id of key pairs with this material, armored with the given
+    /// label. It's the SHA-256 hash of the KDT 0.1 armor, followed by the
+    /// ids of the KEM and signature algorithm for everything but legacy
+    /// key sets, so keys of different algorithms never share an id, even
+    /// if their bytes happen to be the same.
+    pub fn id(&self, label: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.to_legacy_armor(label));
+        if !self.has_legacy_id() {
+            hasher.update([self.kem().id(), self.signature_algorithm().id()]);
+        }
+        format!("{:X}", hasher.finalize())
+    }
+
+    /// Armors the material in a container of the given kind.
+    pub fn to_armor(&self, kind: KdtContainerKind, label: &str) -> String {
+        let mut records = KdtRecords::new();
+        records.push(KEY_KEM, [self.kem().id()]);
+        records.push(KEY_CRYPTO, self.crypto_key.clone());
+        records.push(KEY_SIGNATURE_ALGORITHM, [self.signature_algorithm().id()]);
+        records.push(KEY_SIGNAGE, self.signage_key.clone());
+        records.push(KEY_OWNER, self.owner.as_bytes());
+        if let Some(hybrid_crypto_key) = &self.hybrid_crypto_key {
+            records.push(KEY_HYBRID_CRYPTO, hybrid_crypto_key.clone());
+        }
+        if let Some(hybrid_signage_key) = &self.hybrid_signage_key {
+            records.push(KEY_HYBRID_SIGNAGE, hybrid_signage_key.clone());
+        }
+        let container = KdtContainer::new(kind, records);
+        Armor::wrap(label, &Base64::encode_bytes(&container.to_bytes()))
+    }
+
+    /// Armors the material in the `*`-separated KDT 0.1 format. Only used
+    /// to compute key ids, see `KdtKeyMaterial::id`.
+    pub fn to_legacy_armor(&self, label: &str) -> String {
+        let crypto_key = Base64::encode_bytes(&self.crypto_key);
+        let signage_key = Base64::encode_bytes(&self.signage_key);
+        let owner = Base64::encode_bytes(self.owner.as_bytes());
+        // An asterisk separates the encryption key from the
+        // signing key during key exchanges. The X25519 and Ed25519 keys
+        // of hybrid keys come last, so non-hybrid keys keep their ids.
+        let mut keypair = format!("{}*{}*{}", crypto_key, signage_key, owner);
+        if let Some(hybrid_crypto_key) = &self.hybrid_crypto_key {
+            keypair = format!("{}*{}", keypair, Base64::encode_bytes(hybrid_crypto_key));
+        }
+        if let Some(hybrid_signage_key) = &self.hybrid_signage_key {
+            keypair = format!("{}*{}", keypair, Base64::encode_bytes(hybrid_signage_key));
+        }
+        Armor::wrap(label, &keypair)
+    }
+
+    /// Restores the material from an armored key, in either the container
+    /// format or the legacy KDT 0.1 one.
+    pub fn parse(armored: &str, kind: KdtContainerKind) -> Result<Self, Box<dyn Error>> {
+        let body = Armor::unwrap(armored);
+
+        if body.contains('*') {
+            let fields = body
+                .split('*')
+                .map(Base64::try_decode_string)
+                .collect::<Result<Vec<_>, _>>()?;
+            if fields.len() < 3 {
+                return Err(Box::new(KdtErr::BadContainer));
+            }
+            return Ok(Self {
+                crypto_key: fields[0].to_owned(),
+                // KDT 0.1 only ever made Kyber-1024 keys.
+                kyber_level: KdtKyberLevel::Kyber1024,
+                signage_key: fields[1].to_owned(),
+                // Nor anything but Dilithium3 ones.
+                dilithium_level: KdtDilithiumLevel::Dilithium3,
+                slh_dsa_level: None,
+                // Hybrid keys carry their X25519 and Ed25519 keys after the
+                // owner.
+                hybrid_crypto_key: fields.get(3).cloned(),
+                hybrid_signage_key: fields.get(4).cloned(),
+                owner: String::from_utf8_lossy(&fields[2]).to_string(),
+            });
+        }
+
+        let container = KdtContainer::from_bytes(&Base64::try_decode_string(body)?, kind)?;
+        let records = &container.records;
+        let kem = KdtKem::from_id(records.require_u8(KEY_KEM)?).ok_or(KdtErr::UnknownAlgorithm)?;
+        let algorithm =
+            KdtSignatureAlgorithm::from_id(records.require_u8(KEY_SIGNATURE_ALGORITHM)?)
+                .ok_or(KdtErr::UnknownAlgorithm)?;
+        let hybrid_crypto_key = match kem.is_hybrid() {
+            true => Some(records.require(KEY_HYBRID_CRYPTO)?.to_vec()),
+            false => None,
+        };
+        let hybrid_signage_key = match algorithm.is_hybrid() {
+            true => Some(records.require(KEY_HYBRID_SIGNAGE)?.to_vec()),
+            false => None,
+        };
+
+        Ok(Self {
+            crypto_key: records.require(KEY_CRYPTO)?.to_vec(),
+            kyber_level: kem.level(),
+            signage_key: records.require(KEY_SIGNAGE)?.to_vec(),
+            dilithium_level: algorithm.dilithium_level().unwrap_or_default(),
+            slh_dsa_level: algorithm.slh_dsa_level(),
+            hybrid_crypto_key,
+            hybrid_signage_key,
+            owner: String::from_utf8_lossy(records.require(KEY_OWNER)?).to_string(),
+        })
+    }
+}
@@ -4,10 +4,14 @@
 // -- local modules --
 pub mod database;
 pub mod key;
+pub mod material;
+pub mod options;
 pub mod privkey;
 pub mod pubkey;
 
 pub use database::*;
 pub use key::*;
+pub use material::*;
+pub use options::*;
 pub use privkey::*;
 pub use pubkey::*;
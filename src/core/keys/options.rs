@@ -0,0 +1,71 @@
+// -- imports --
+use crate::core::*;
+
+// -- key generation options --
+/// Knobs for key set generation. The defaults are what KDT does when
+/// nothing else is asked for.
+#[derive(Clone, Copy, Debug)]
+pub struct KdtKeyOptions {
+    /// Adds X25519 and Ed25519 keys to the Kyber and Dilithium ones.
+    pub hybrid: bool,
+
+    /// Security level of the Kyber key. New key sets are ML-KEM-1024,
+    /// unlike the Kyber-1024 ones that came before them.
+    pub kyber_level: KdtKyberLevel,
+
+    /// Parameter set of the Dilithium key. New key sets are ML-DSA-65,
+    /// unlike the Dilithium3 ones that came before them.
+    pub dilithium_level: KdtDilithiumLevel,
+
+    /// Parameter set of the SLH-DSA key to sign with instead of
+    /// Dilithium, if any.
+    pub slh_dsa_level: Option<KdtSlhDsaLevel>,
+}
+
+impl Default for KdtKeyOptions {
+    fn default() -> Self {
+        Self {
+            hybrid: false,
+            kyber_level: KdtKyberLevel::RECOMMENDED,
+            dilithium_level: KdtDilithiumLevel::RECOMMENDED,
+            slh_dsa_level: None,
+        }
+    }
+}
+
+impl KdtKeyOptions {
+    /// Creates the default options.
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the key set is hybrid.
+    #[inline(always)]
+    pub fn with_hybrid(mut self, hybrid: bool) -> Self {
+        self.hybrid = hybrid;
+        self
+    }
+
+    /// Sets the security level of the Kyber key.
+    #[inline(always)]
+    pub fn with_kyber_level(mut self, kyber_level: KdtKyberLevel) -> Self {
+        self.kyber_level = kyber_level;
+        self
+    }
+
+    /// Sets the parameter set of the Dilithium key.
+    #[inline(always)]
+    pub fn with_dilithium_level(mut self, dilithium_level: KdtDilithiumLevel) -> Self {
+        self.dilithium_level = dilithium_level;
+        self
+    }
+
+    /// Sets the parameter set of the SLH-DSA key, which makes the key set
+    /// sign with SLH-DSA instead of Dilithium.
+    #[inline(always)]
+    pub fn with_slh_dsa_level(mut self, slh_dsa_level: Option<KdtSlhDsaLevel>) -> Self {
+        self.slh_dsa_level = slh_dsa_level;
+        self
+    }
+}
@@ -4,21 +4,46 @@ use serde::{
     Deserialize,
     Serialize,
 };
-use sha2::{
-    Digest,
-    Sha256,
-};
 use std::fmt;
 
+/// Label of the armor around private keys.
+pub const PRIVKEY_ARMOR_LABEL: &str = "KDT PRIVKEY BLOCK";
+
 // -- private key pair (signing key + crypto key) --
 #[derive(Serialize, Deserialize, Clone)]
 pub struct PrivKeyPair {
     /// Kyber private key bytes
     pub crypto_key: Vec<u8>,
 
-    /// Dilithium private key bytes
+    /// Security level of the Kyber key. Older key sets are all
+    /// Kyber-1024, hence the serde default.
+    #[serde(default)]
+    pub kyber_level: KdtKyberLevel,
+
+    /// Dilithium private key bytes, or SLH-DSA ones for key sets with an
+    /// SLH-DSA level
     pub signage_key: Vec<u8>,
 
+    /// Parameter set of the Dilithium key. Older key sets are all
+    /// Dilithium3, hence the serde default.
+    #[serde(default)]
+    pub dilithium_level: KdtDilithiumLevel,
+
+    /// Parameter set of the SLH-DSA key, only present in key sets that
+    /// sign with SLH-DSA instead of Dilithium. Older key sets don't
+    /// have one, hence the serde default.
+    #[serde(default)]
+    pub slh_dsa_level: Option<KdtSlhDsaLevel>,
+
+    /// X25519 private key bytes, only present in hybrid key sets.
+    /// Older key sets don't have one, hence the serde default.
+    #[serde(default)]
+    pub hybrid_crypto_key: Option<Vec<u8>>,
+
+    /// Ed25519 private key bytes, only present in hybrid key sets.
+    #[serde(default)]
+    pub hybrid_signage_key: Option<Vec<u8>>,
+
     /// Key owner's name as a string
     pub owner: String,
 
@@ -38,46 +63,110 @@ impl PrivKeyPair {
     pub fn new(crypto_key: Vec<u8>, signage_key: Vec<u8>, owner: String) -> Self {
         Self {
             crypto_key,
+            kyber_level: KdtKyberLevel::default(),
             signage_key,
+            dilithium_level: KdtDilithiumLevel::default(),
+            slh_dsa_level: None,
+            hybrid_crypto_key: None,
+            hybrid_signage_key: None,
             owner,
             id: String::new(),
         }
     }
 
+    /// Sets the security level of the Kyber key, which is Kyber-1024
+    /// unless set otherwise.
+    #[inline(always)]
+    pub fn with_kyber_level(mut self, kyber_level: KdtKyberLevel) -> Self {
+        self.kyber_level = kyber_level;
+        self
+    }
+
+    /// Sets the parameter set of the Dilithium key, which is Dilithium3
+    /// unless set otherwise.
+    #[inline(always)]
+    pub fn with_dilithium_level(mut self, dilithium_level: KdtDilithiumLevel) -> Self {
+        self.dilithium_level = dilithium_level;
+        self
+    }
+
+    /// Sets the parameter set of the SLH-DSA key, which makes the key pair
+    /// sign with SLH-DSA instead of Dilithium.
+    #[inline(always)]
+    pub fn with_slh_dsa_level(mut self, slh_dsa_level: Option<KdtSlhDsaLevel>) -> Self {
+        self.slh_dsa_level = slh_dsa_level;
+        self
+    }
+
+    /// Sets the X25519 key that makes this a hybrid key pair. Like the
+    /// rest of the key material, this has to happen before the
+    /// `PrivKeyPair::init()` call, as it's part of the id.
+    #[inline(always)]
+    pub fn with_hybrid_crypto_key(mut self, hybrid_crypto_key: Option<Vec<u8>>) -> Self {
+        self.hybrid_crypto_key = hybrid_crypto_key;
+        self
+    }
+
+    /// Sets the Ed25519 key that makes this a hybrid signing key pair. Has
+    /// to happen before the `init()` call too.
+    #[inline(always)]
+    pub fn with_hybrid_signage_key(mut self, hybrid_signage_key: Option<Vec<u8>>) -> Self {
+        self.hybrid_signage_key = hybrid_signage_key;
+        self
+    }
+
+    /// Whether this key pair also carries an X25519 key, and therefore
+    /// takes part in hybrid Kyber + X25519 encryption.
+    #[inline(always)]
+    pub fn is_hybrid(&self) -> bool {
+        self.hybrid_crypto_key.is_some()
+    }
+
     /// Computes a hash for the key pair, then sets the id as the hash. This
     /// helps maintain distinctness between key ids.
     #[inline(always)]
     pub fn init(mut self) -> Self {
-        let mut hasher = Sha256::new();
-        hasher.update(self.to_string());
-        self.id = format!("{:X}", hasher.finalize());
+        self.id = self.material().id(PRIVKEY_ARMOR_LABEL);
         self
     }
 
     /// Derives a `PrivKeyPair` object from the provided KDT private
     /// key string. Doesn't validate input, so it *will* panic if you pass
-    /// invalid inputs.
+    /// invalid inputs. See `PrivKeyPair::parse` for a version that doesn't.
     pub fn from_str(privkey_str: String) -> Self {
-        let privkey: Vec<String> = privkey_str
-            .chars()
-            // Removes the `-----BEGIN KDT PRIVKEY BLOCK-----` header.
-            .skip(33)
-            // Removes the `-----END KDT PRIVKEY BLOCK-----` footer.
-            .take(privkey_str.len() - 33 - 32)
-            .collect::<String>()
-            // Turns the human-readable formatting to something that can be parsed
-            // programmatically.
-            .replace('\n', "")
-            // Splits the private key into a cryptographic key and signage key.
-            .split('*')
-            .map(String::from)
-            .collect();
+        Self::parse(&privkey_str).unwrap()
+    }
 
-        Self {
-            crypto_key: Base64::decode_string(privkey[0].to_owned()),
-            signage_key: Base64::decode_string(privkey[1].to_owned()),
-            owner: String::from_utf8_lossy(&Base64::decode_string(&privkey[2])).to_string(),
+    /// Derives a `PrivKeyPair` object from the provided KDT private
+    /// key string, in either the container format or the legacy KDT 0.1
+    /// one.
+    pub fn parse(privkey_str: &str) -> Result<Self, Box<dyn Error>> {
+        let material = KdtKeyMaterial::parse(privkey_str, KdtContainerKind::PrivKey)?;
+
+        Ok(Self {
+            crypto_key: material.crypto_key,
+            kyber_level: material.kyber_level,
+            signage_key: material.signage_key,
+            dilithium_level: material.dilithium_level,
+            slh_dsa_level: material.slh_dsa_level,
+            hybrid_crypto_key: material.hybrid_crypto_key,
+            hybrid_signage_key: material.hybrid_signage_key,
+            owner: material.owner,
             id: String::new(),
+        })
+    }
+
+    /// The key material of this key pair, minus the id.
+    pub fn material(&self) -> KdtKeyMaterial {
+        KdtKeyMaterial {
+            crypto_key: self.crypto_key.clone(),
+            kyber_level: self.kyber_level,
+            signage_key: self.signage_key.clone(),
+            dilithium_level: self.dilithium_level,
+            slh_dsa_level: self.slh_dsa_level,
+            hybrid_crypto_key: self.hybrid_crypto_key.clone(),
+            hybrid_signage_key: self.hybrid_signage_key.clone(),
+            owner: self.owner.clone(),
         }
     }
 }
@@ -85,29 +174,11 @@ impl PrivKeyPair {
 // -- human-readable key output impl --
 impl fmt::Display for PrivKeyPair {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let crypto_key = Base64::encode_bytes(&self.crypto_key);
-        let signage_key = Base64::encode_bytes(&self.signage_key);
-        let owner = Base64::encode_bytes(self.owner.as_bytes());
-        // An asterisk separates the encryption key from the
-        // signing key during key exchanges.
-        let keypair = format!("{}*{}*{}", crypto_key, signage_key, owner)
-            .chars()
-            .enumerate()
-            // This helps maintain readability when printing messages. It
-            // inserts a new line at every nth (n = multiple of 64) character.
-            // This is identical to GPG's output style.
-            .flat_map(|(i, c)| {
-                if (i + 1) % 64 == 0 {
-                    vec![c, '\n']
-                } else {
-                    vec![c]
-                }
-            })
-            .collect::<String>();
         write!(
             f,
-            "-----BEGIN KDT PRIVKEY BLOCK-----\n{}\n-----END KDT PRIVKEY BLOCK-----",
-            keypair.trim_end()
+            "{}",
+            self.material()
+                .to_armor(KdtContainerKind::PrivKey, PRIVKEY_ARMOR_LABEL)
         )
     }
 }
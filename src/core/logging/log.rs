@@ -91,4 +91,26 @@ impl Logger {
 
         i.trim().to_string()
     }
+
+    /// Gets user input from stdin as-is, for messages where whitespace
+    /// matters, like the ones about to be signed.
+    pub fn raw_input(&self) -> String {
+        let mut i = String::new();
+        io::stdout()
+            .flush()
+            .expect("Failed to flush standard output!");
+        io::stdin()
+            .read_to_string(&mut i)
+            .expect("Failed to read input!");
+
+        i
+    }
+
+    /// Prompts for a passphrase without echoing it. This reads from the
+    /// terminal rather than stdin, so it still works while stdin is
+    /// busy carrying a message.
+    pub fn passphrase<S: Display>(&self, prompt: S) -> String {
+        rpassword::prompt_password(format!("{} {} ", "(input)".bright_cyan(), prompt))
+            .expect("Failed to read passphrase!")
+    }
 }
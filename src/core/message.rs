@@ -1,3 +1,6 @@
+// -- compiler flags --
+#![allow(dead_code)]
+
 // -- imports --
 use std::fmt;
 
@@ -19,7 +19,6 @@ pub use message::*;
 pub use signing::*;
 
 // -- external imports --
-use pqc_dilithium::Keypair;
 use ron::{
     de::from_reader,
     ser::{
@@ -35,7 +34,10 @@ use std::{
     error::Error,
     fmt,
     fs::File,
-    io::Write,
+    io::{
+        Read,
+        Write,
+    },
     path::Path,
 };
 
@@ -97,8 +99,9 @@ impl CoreKdtHandler {
 
     /// Generates a new owned key set on demand, then
     /// appends that new keyset to the owned key database.
-    pub fn gen_key(&mut self, name: String) -> String {
-        let key = OwnedKeySet::generate(name);
+    /// Hybrid key sets also get an X25519 key pair.
+    pub fn gen_key(&mut self, name: String, options: KdtKeyOptions) -> String {
+        let key = OwnedKeySet::generate_with_options(name, options);
         self.ownedkey_db.keys.push(key.clone());
         key.privkey_pair.id
     }
@@ -133,7 +136,7 @@ impl CoreKdtHandler {
         &mut self, pubkey_str: S,
     ) -> Result<String, Box<dyn Error>> {
         // Construct a public key using the given string
-        let pubkey = PubKeyPair::from_str(pubkey_str.to_string()).init();
+        let pubkey = PubKeyPair::parse(&pubkey_str.to_string())?.init();
         // Make sure this public key isn't already registered to the database
         if !self
             .pubkey_db
@@ -174,63 +177,200 @@ impl CoreKdtHandler {
         Ok(())
     }
 
-    /// Encrypts the given message against the public key of the given
+    /// Encrypts the given message against the public keys of all the
+    /// given ids at once.
+    pub fn encrypt(
+        &self, pubkey_ids: Vec<String>, text: String, options: KdtEncryptOptions,
+    ) -> Result<String, Box<dyn Error>> {
+        self.encrypt_bytes(pubkey_ids, text.as_bytes(), options)
+    }
+
+    /// Encrypts the given bytes against the public keys of all the given
+    /// ids at once. Unlike `CoreKdtHandler::encrypt`, the input doesn't
+    /// need to be valid text.
+    pub fn encrypt_bytes(
+        &self, pubkey_ids: Vec<String>, bytes: &[u8], options: KdtEncryptOptions,
+    ) -> Result<String, Box<dyn Error>> {
+        let public_keys = pubkey_ids
+            .into_iter()
+            .map(|id| self.pubkey_db.get_by_id(id))
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        Ok(KdtCryptoHandler::encrypt_bytes_with_options(bytes, public_keys, options)?.to_string())
+    }
+
+    /// Encrypts the given bytes with a passphrase instead of a public key,
+    /// for recipients who don't have a KDT key.
+    pub fn encrypt_with_passphrase(
+        &self, bytes: &[u8], passphrase: String, aead: KdtAead,
+    ) -> Result<String, Box<dyn Error>> {
+        Ok(KdtCryptoHandler::encrypt_bytes_with_passphrase(bytes, &passphrase, aead)?.to_string())
+    }
+
+    /// Decrypts the given passphrase-encrypted message, and returns the
+    /// plaintext bytes without any conversion.
+    pub fn decrypt_with_passphrase(
+        &self, message: String, passphrase: String,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let message = KdtEncryptedMessage::parse(&message)?;
+        KdtCryptoHandler::decrypt_bytes_with_passphrase(message, &passphrase)
+    }
+
+    /// Encrypts everything read from `reader` against the public keys of
+    /// all the given ids, streaming the result into `writer`.
+    pub fn encrypt_stream<R: Read, W: Write>(
+        &self, pubkey_ids: Vec<String>, reader: &mut R, writer: &mut W, options: KdtEncryptOptions,
+    ) -> Result<(), Box<dyn Error>> {
+        let public_keys = pubkey_ids
+            .into_iter()
+            .map(|id| self.pubkey_db.get_by_id(id))
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        KdtCryptoHandler::encrypt_stream_with_options(reader, writer, public_keys, options)
+    }
+
+    /// Decrypts the stream read from `reader` with the private key of the
+    /// given id, streaming the plaintext into `writer`. See
+    /// `CoreKdtHandler::decryption_keysets` for what happens without an
     /// id.
-    pub fn encrypt(&self, pubkey_id: String, text: String) -> String {
-        let public_key = self.pubkey_db.get_by_id(pubkey_id).unwrap();
-        KdtCryptoHandler::encrypt_text(text, public_key.crypto_key)
-            .unwrap()
-            .to_string()
+    pub fn decrypt_stream<R: Read, W: Write>(
+        &self, privkey_id: Option<String>, reader: &mut R, writer: &mut W,
+    ) -> Result<(), Box<dyn Error>> {
+        let keysets = self.decryption_keysets(privkey_id)?;
+        KdtCryptoHandler::decrypt_stream_with_keysets(reader, writer, &keysets)
     }
 
     /// Decrypts the given message with the private key of the given id.
-    pub fn decrypt(&self, privkey_id: String, message: String) -> String {
-        let message = KdtEncryptedMessage::from_str(message);
-        let private_key = self
-            .ownedkey_db
-            .get_by_id(privkey_id)
-            .unwrap()
-            .privkey_pair
-            .crypto_key;
-        KdtCryptoHandler::decrypt_msg(message, private_key)
+    /// See `CoreKdtHandler::decryption_keysets` for what happens without
+    /// an id.
+    pub fn decrypt(
+        &self, privkey_id: Option<String>, message: String,
+    ) -> Result<String, Box<dyn Error>> {
+        let bytes = self.decrypt_bytes(privkey_id, message)?;
+        Ok(String::from_utf8_lossy(&bytes).into())
+    }
+
+    /// Decrypts the given message with the private key of the given id.
+    /// If the plaintext turns out to be a KDT signed message, as made by
+    /// `CoreKdtHandler::sign_and_encrypt`, its signature is checked against
+    /// every public key in the database, and the inner message is returned
+    /// along with who signed it. See `KdtSignageHandler::check_plaintext`.
+    pub fn decrypt_and_verify(
+        &self, privkey_id: Option<String>, message: String,
+    ) -> Result<(String, KdtSignerStatus), Box<dyn Error>> {
+        let plaintext = self.decrypt(privkey_id, message)?;
+        Ok(KdtSignageHandler::check_plaintext(
+            plaintext,
+            &self.pubkey_db.keys,
+        ))
+    }
+
+    /// Decrypts the given message with the private key of the given id,
+    /// and returns the plaintext bytes without any conversion.
+    pub fn decrypt_bytes(
+        &self, privkey_id: Option<String>, message: String,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let message = KdtEncryptedMessage::parse(&message)?;
+        let keysets = self.decryption_keysets(privkey_id)?;
+        KdtCryptoHandler::decrypt_bytes_with_keysets(message, &keysets)
+    }
+
+    /// The owned key sets to decrypt with: the one with the given private
+    /// key id, or every owned key set if there isn't one. Messages name
+    /// the public keys they were encrypted to, so the right key set gets
+    /// picked out of all of them without trying each, unless the message
+    /// is anonymous.
+    fn decryption_keysets(
+        &self, privkey_id: Option<String>,
+    ) -> Result<Vec<OwnedKeySet>, Box<dyn Error>> {
+        match privkey_id {
+            Some(id) => Ok(vec![self.ownedkey_db.get_by_id(id)?]),
+            None => Ok(self.ownedkey_db.keys.clone()),
+        }
     }
 
     /// Signs the given message with the private key of the given id.
-    pub fn sign(&self, privkey_id: String, text: String) -> Result<String, Box<dyn Error>> {
-        let signing_pubkey = self
-            .ownedkey_db
-            .get_by_id(privkey_id.clone())?
-            .pubkey_pair
-            .signage_key;
-        let signing_privkey = self
-            .ownedkey_db
-            .get_by_id(privkey_id)?
-            .privkey_pair
-            .signage_key;
-        let keypair = Keypair::restore_from_keys(signing_pubkey, signing_privkey);
-        Ok(KdtSignageHandler::sign_text(text, keypair))
+    pub fn sign(
+        &self, privkey_id: String, text: String, options: &KdtSignOptions,
+    ) -> Result<String, Box<dyn Error>> {
+        let keyset = self.ownedkey_db.get_by_id(privkey_id)?;
+        KdtSignageHandler::sign_text_with_options(text, &keyset, options)
+    }
+
+    /// Signs the given message with the private key of the given id, then
+    /// encrypts the signed message against the public keys of all the
+    /// given ids.
+    pub fn sign_and_encrypt(
+        &self, privkey_id: String, pubkey_ids: Vec<String>, text: String,
+        sign_options: &KdtSignOptions, options: KdtEncryptOptions,
+    ) -> Result<String, Box<dyn Error>> {
+        let signed = self.sign(privkey_id, text, sign_options)?;
+        self.encrypt(pubkey_ids, signed, options)
+    }
+
+    /// Adds a signature with the private key of the given id to an
+    /// armored signed message, keeping the signatures already on it.
+    pub fn cosign(
+        &self, privkey_id: String, signed_text: String, options: &KdtSignOptions,
+    ) -> Result<String, Box<dyn Error>> {
+        let keyset = self.ownedkey_db.get_by_id(privkey_id)?;
+        KdtSignageHandler::cosign_text(&signed_text, &keyset, options)
+    }
+
+    /// Adds a signature over everything read from `reader` with the
+    /// private key of the given id to an armored detached signature over
+    /// the same data.
+    pub fn cosign_detached<R: Read>(
+        &self, privkey_id: String, reader: &mut R, signature: &str, options: &KdtSignOptions,
+    ) -> Result<KdtDetachedSignature, Box<dyn Error>> {
+        let keyset = self.ownedkey_db.get_by_id(privkey_id)?;
+        let signature = KdtDetachedSignature::parse(signature)?;
+        KdtSignageHandler::cosign_reader(reader, signature, &keyset, options)
+    }
+
+    /// Makes a detached signature over everything read from `reader` with
+    /// the private key of the given id. Prehashing signatures are made in
+    /// constant memory.
+    pub fn sign_detached<R: Read>(
+        &self, privkey_id: String, reader: &mut R, options: &KdtSignOptions,
+    ) -> Result<KdtDetachedSignature, Box<dyn Error>> {
+        let keyset = self.ownedkey_db.get_by_id(privkey_id)?;
+        KdtSignageHandler::sign_reader_with_options(reader, &keyset, options)
+    }
+
+    /// Verifies an armored detached signature over everything read from
+    /// `reader` with the public key of the given id. See
+    /// `CoreKdtHandler::verification_pubkeys` for what happens without an
+    /// id. Every signature on it is checked on its own, co-signatures
+    /// included.
+    pub fn verify_detached<R: Read>(
+        &self, pubkey_id: Option<String>, reader: &mut R, signature: &str,
+    ) -> Result<Vec<KdtSignatureCheck>, Box<dyn Error>> {
+        let pubkeys = self.verification_pubkeys(pubkey_id)?;
+        let signature = KdtDetachedSignature::parse(signature)?;
+        KdtSignageHandler::check_detached_signatures_in_reader(reader, &signature, &pubkeys)
     }
 
     /// Verifies the given KDT-signed message with the public key of the
-    /// given id.
-    pub fn verify(&self, pubkey_id: String, full_text: String) -> Option<bool> {
-        let verification_pubkey = self
-            .pubkey_db
-            .get_by_id(pubkey_id)
-            .unwrap()
-            .signage_key;
-        let parts: Vec<String> = full_text
-            .chars()
-            .skip(35)
-            .take(full_text.len() - 35 - 27)
-            .collect::<String>()
-            .split("-----BEGIN KDT SIGNATURE-----")
-            .map(|x| x.trim())
-            .map(String::from)
-            .collect();
-        let text = parts.first()?.trim().to_owned();
-        let signature = parts.last()?.replace('\n', "");
-        let message = KdtSignedMessage::new(text, Base64::decode_string(signature));
-        Some(KdtSignageHandler::verify(message, verification_pubkey))
+    /// given id. See `CoreKdtHandler::verification_pubkeys` for what
+    /// happens without an id. Every signature on it is checked on its own,
+    /// co-signatures included.
+    pub fn verify(
+        &self, pubkey_id: Option<String>, full_text: String,
+    ) -> Result<Vec<KdtSignatureCheck>, Box<dyn Error>> {
+        let pubkeys = self.verification_pubkeys(pubkey_id)?;
+        let message = KdtSignedMessage::parse(&full_text)?;
+        Ok(KdtSignageHandler::check_signatures(&message, &pubkeys))
+    }
+
+    /// The public keys to verify with: the one with the given id, or
+    /// every public key if there isn't one. Signatures name the key that
+    /// made them, so the right one gets picked out of all of them without
+    /// trying each, unless the signature predates signed metadata.
+    fn verification_pubkeys(
+        &self, pubkey_id: Option<String>,
+    ) -> Result<Vec<PubKeyPair>, Box<dyn Error>> {
+        match pubkey_id {
+            Some(id) => Ok(vec![self.pubkey_db.get_by_id(id)?]),
+            None => Ok(self.pubkey_db.keys.clone()),
+        }
     }
 }
@@ -0,0 +1,152 @@
+// -- imports --
+use crate::core::*;
+use std::fmt;
+
+/// Label of the armor around detached signatures.
+pub const DETACHED_SIGNATURE_ARMOR_LABEL: &str = "KDT DETACHED SIGNATURE";
+
+/// Extension of the files detached signatures are written to, appended
+/// to the name of the signed file.
+pub const DETACHED_SIGNATURE_EXTENSION: &str = "kdtsig";
+
+/// A signature over arbitrary bytes, eg a release tarball, stored apart
+/// from them. It uses the same records as the signature block of a
+/// signed message, in a container of its own kind so the two can't be
+/// mixed up.
+#[derive(Clone)]
+pub struct KdtDetachedSignature {
+    /// Which scheme the data was signed with.
+    pub algorithm: KdtSignatureAlgorithm,
+
+    /// Dilithium or SLH-DSA signature bytes
+    pub signature: Vec<u8>,
+
+    /// Ed25519 signature bytes. Empty unless the data was signed by a
+    /// hybrid key.
+    pub hybrid_signature: Vec<u8>,
+
+    /// Signed metadata of the signature.
+    pub metadata: Option<KdtSignatureMetadata>,
+
+    /// Signatures of other signers over the same data, each made
+    /// independently of the others. Co-signatures don't have
+    /// co-signatures of their own.
+    pub cosignatures: Vec<KdtDetachedSignature>,
+}
+
+impl KdtDetachedSignature {
+    /// How the data was hashed before signing.
+    #[inline(always)]
+    pub fn hash(&self) -> KdtSignatureHash {
+        self.metadata
+            .as_ref()
+            .map_or(KdtSignatureHash::Pure, |m| m.hash)
+    }
+
+    /// Whether the signature says it was made by the key with the given
+    /// public id. Signatures without metadata don't say.
+    #[inline(always)]
+    pub fn is_by(&self, pubkey_id: &str) -> bool {
+        self.metadata
+            .as_ref()
+            .is_some_and(|m| m.signer_id == pubkey_id)
+    }
+
+    /// This signature followed by its co-signatures.
+    pub fn signatures(&self) -> Vec<KdtDetachedSignature> {
+        let mut signatures = vec![Self {
+            cosignatures: Vec::new(),
+            ..self.clone()
+        }];
+        signatures.extend(self.cosignatures.iter().cloned());
+        signatures
+    }
+
+    /// The records of the signature, leaving out its co-signatures.
+    pub fn to_records(&self) -> KdtRecords {
+        let mut records = KdtRecords::new();
+        records.push(SIGNATURE_ALGORITHM, [self.algorithm.id()]);
+        records.push(SIGNATURE_VALUE, self.signature.clone());
+        if self.algorithm.is_hybrid() {
+            records.push(SIGNATURE_HYBRID_VALUE, self.hybrid_signature.clone());
+        }
+        if let Some(metadata) = &self.metadata {
+            records.0.extend(metadata.to_records().0);
+        }
+        records
+    }
+
+    /// Restores a signature from its records, leaving out any
+    /// co-signatures.
+    pub fn from_records(records: &KdtRecords) -> Result<Self, Box<dyn Error>> {
+        let algorithm = KdtSignatureAlgorithm::from_id(records.require_u8(SIGNATURE_ALGORITHM)?)
+            .ok_or(KdtErr::UnknownAlgorithm)?;
+        let hybrid_signature = match algorithm.is_hybrid() {
+            true => records.require(SIGNATURE_HYBRID_VALUE)?.to_vec(),
+            false => Vec::new(),
+        };
+
+        Ok(Self {
+            algorithm,
+            signature: records.require(SIGNATURE_VALUE)?.to_vec(),
+            hybrid_signature,
+            metadata: KdtSignatureMetadata::from_records(records)?,
+            cosignatures: Vec::new(),
+        })
+    }
+
+    /// Appends records holding the given co-signatures to `records`.
+    pub fn push_cosignatures(records: &mut KdtRecords, cosignatures: &[KdtDetachedSignature]) {
+        for cosignature in cosignatures {
+            records.push(SIGNATURE_COSIGNATURE, cosignature.to_records().encode());
+        }
+    }
+
+    /// Restores the co-signatures held in `records`.
+    pub fn cosignatures_from_records(
+        records: &KdtRecords,
+    ) -> Result<Vec<KdtDetachedSignature>, Box<dyn Error>> {
+        records
+            .get_all(SIGNATURE_COSIGNATURE)
+            .map(|bytes| Self::from_records(&KdtRecords::decode(bytes)?))
+            .collect()
+    }
+
+    /// The container holding the signature.
+    pub fn to_container(&self) -> KdtContainer {
+        let mut records = self.to_records();
+        Self::push_cosignatures(&mut records, &self.cosignatures);
+        KdtContainer::new(KdtContainerKind::DetachedSignature, records)
+    }
+
+    /// Derives a `KdtDetachedSignature` from an armored detached
+    /// signature. Doesn't validate input, so it *will* panic if you pass
+    /// invalid inputs. See `KdtDetachedSignature::parse` for a version
+    /// that doesn't.
+    pub fn from_str<S: fmt::Display>(armored: S) -> Self {
+        Self::parse(&armored.to_string()).unwrap()
+    }
+
+    /// Derives a `KdtDetachedSignature` from an armored detached
+    /// signature.
+    pub fn parse(armored: &str) -> Result<Self, Box<dyn Error>> {
+        let bytes = Base64::try_decode_string(Armor::unwrap(armored))?;
+        let container = KdtContainer::from_bytes(&bytes, KdtContainerKind::DetachedSignature)?;
+
+        Ok(Self {
+            cosignatures: Self::cosignatures_from_records(&container.records)?,
+            ..Self::from_records(&container.records)?
+        })
+    }
+}
+
+impl fmt::Display for KdtDetachedSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let signature = Base64::encode_bytes(&self.to_container().to_bytes());
+        write!(
+            f,
+            "{}",
+            Armor::wrap(DETACHED_SIGNATURE_ARMOR_LABEL, &signature)
+        )
+    }
+}
@@ -1,7 +1,18 @@
 // -- imports --
 use crate::core::*;
-use pqc_dilithium::verify as dilithium_verify;
-use pqc_dilithium::Keypair;
+use ed25519_dalek::{
+    Signature as Ed25519Signature,
+    Signer,
+    SigningKey as Ed25519SigningKey,
+    VerifyingKey as Ed25519VerifyingKey,
+};
+use pqc_dilithium::{
+    verify_ctx,
+    verify_prehashed,
+    Keypair,
+    PreHash,
+};
+use std::io::Read;
 
 // -- signage handler struct --
 /// Base Dilithium signature handler object. Incredibly WIP.
@@ -9,29 +20,441 @@ pub struct KdtSignageHandler;
 
 impl KdtSignageHandler {
     /// Generates a KDT Dilithium signature from a passed message
-    /// and a key pair, then formats the message with the signature
+    /// and an owned key set, then formats the message with the signature
     /// in a visually appealing way (mostly just stole GPG's output
-    /// styling).
+    /// styling). Hybrid key sets sign with Ed25519 as well. What gets
+    /// signed is the canonical form of the message, see
+    /// `KdtSignedMessage::canonicalize`.
     #[inline(always)]
-    pub fn sign_text(text: String, signkey: Keypair) -> String {
-        format!("-----BEGIN KDT SIGNED MESSAGE-----\n{}\n\n-----BEGIN KDT SIGNATURE-----\n{}\n-----END KDT SIGNATURE-----", text, Base64::encode_bytes(&signkey.sign(text.as_bytes())).chars()
-        .enumerate()
-        .flat_map(|(i, c)| {
-            if (i + 1) % 64 == 0 {
-                vec![c, '\n']
-            } else {
-                vec![c]
+    pub fn sign_text(text: String, keyset: &OwnedKeySet) -> String {
+        Self::sign_text_with_options(text, keyset, &KdtSignOptions::new())
+            .expect("The default options are valid")
+    }
+
+    /// Signs a message like `KdtSignageHandler::sign_text`, with the
+    /// given options. Fails if the options are invalid, eg the context
+    /// string is too long.
+    pub fn sign_text_with_options(
+        text: String, keyset: &OwnedKeySet, options: &KdtSignOptions,
+    ) -> Result<String, Box<dyn Error>> {
+        let text = KdtSignedMessage::canonicalize(&text);
+        let signature = Self::sign_bytes_with_options(text.as_bytes(), keyset, options)?;
+        let message = KdtSignedMessage::new(text, signature.signature)
+            .with_algorithm(signature.algorithm)
+            .with_metadata(signature.metadata);
+
+        Ok(match signature.algorithm.is_hybrid() {
+            true => message.with_hybrid_signature(signature.hybrid_signature),
+            false => message,
+        }
+        .to_string())
+    }
+
+    /// Generates a detached signature over arbitrary bytes with an owned
+    /// key set. Hybrid key sets sign with Ed25519 as well.
+    #[inline(always)]
+    pub fn sign_bytes(bytes: &[u8], keyset: &OwnedKeySet) -> KdtDetachedSignature {
+        Self::sign_bytes_with_options(bytes, keyset, &KdtSignOptions::new())
+            .expect("The default options are valid")
+    }
+
+    /// Generates a detached signature like `KdtSignageHandler::sign_bytes`,
+    /// with the given options. The signature covers its metadata as well
+    /// as the bytes.
+    #[inline(always)]
+    pub fn sign_bytes_with_options(
+        bytes: &[u8], keyset: &OwnedKeySet, options: &KdtSignOptions,
+    ) -> Result<KdtDetachedSignature, Box<dyn Error>> {
+        Self::check_options(options)?;
+        let metadata = KdtSignatureMetadata::new(keyset.pubkey_pair.id.clone(), options);
+        let algorithm = keyset.pubkey_pair.signature_algorithm();
+        let message = Self::representation(algorithm, Some(&metadata)).of(bytes);
+        Ok(Self::sign_represented(&message, metadata, keyset, options))
+    }
+
+    /// Generates a detached signature over everything read from `reader`,
+    /// like `KdtSignageHandler::sign_bytes_with_options`. Unless the
+    /// options ask for a prehash, the whole input ends up in memory.
+    pub fn sign_reader_with_options<R: Read>(
+        reader: &mut R, keyset: &OwnedKeySet, options: &KdtSignOptions,
+    ) -> Result<KdtDetachedSignature, Box<dyn Error>> {
+        Self::check_options(options)?;
+        let metadata = KdtSignatureMetadata::new(keyset.pubkey_pair.id.clone(), options);
+        let algorithm = keyset.pubkey_pair.signature_algorithm();
+        let representation = Self::representation(algorithm, Some(&metadata));
+        let message = KdtRepresentation::read_all(vec![representation], reader)?.remove(0);
+        Ok(Self::sign_represented(&message, metadata, keyset, options))
+    }
+
+    /// Rejects options no signature can be made with, before any of the
+    /// input is read.
+    fn check_options(options: &KdtSignOptions) -> Result<(), Box<dyn Error>> {
+        if options.context.len() > MAX_CONTEXT_SIZE {
+            return Err(Box::new(KdtErr::ContextTooLong));
+        }
+        Ok(())
+    }
+
+    /// Signs what stands in for the message, see
+    /// `KdtSignageHandler::representation`, along with the metadata.
+    fn sign_represented(
+        message: &[u8], metadata: KdtSignatureMetadata, keyset: &OwnedKeySet,
+        options: &KdtSignOptions,
+    ) -> KdtDetachedSignature {
+        let algorithm = keyset.pubkey_pair.signature_algorithm();
+        let pre_hash = Self::hash_ml_dsa(algorithm, metadata.hash);
+        // HashML-DSA digests already cover the metadata.
+        let signed_data = match pre_hash {
+            Some(_) => metadata.hash.frame(message),
+            None => metadata.signed_data(message),
+        };
+        let privkey = &keyset.privkey_pair;
+        let signature = match privkey.slh_dsa_level {
+            Some(level) => level.sign(
+                &signed_data,
+                &metadata.context,
+                &privkey.signage_key,
+                !options.deterministic,
+            ),
+            None => {
+                let keypair = Keypair::restore_from_keys(
+                    privkey.dilithium_level.params(),
+                    keyset.pubkey_pair.signage_key.clone(),
+                    privkey.signage_key.clone(),
+                )
+                .expect("Dilithium keys don't match their level");
+                let context = Self::dilithium_context(privkey.dilithium_level, &metadata.context);
+                match pre_hash {
+                    Some(pre_hash) => {
+                        keypair.sign_prehashed(message, pre_hash, context, options.signing_mode())
+                    }
+                    None => keypair.sign_ctx(&signed_data, context, options.signing_mode()),
+                }
+                .expect("Context strings are at most `MAX_CONTEXT_SIZE` bytes")
             }
-        })
-        .collect::<String>())
+        };
+        let hybrid_signage_key = keyset.privkey_pair.hybrid_signage_key.as_ref();
+        let hybrid_signature = hybrid_signage_key.map(|key| {
+            let key = <[u8; 32]>::try_from(key.as_slice()).unwrap();
+            Ed25519SigningKey::from_bytes(&key)
+                .sign(&signed_data)
+                .to_vec()
+        });
+
+        KdtDetachedSignature {
+            algorithm,
+            signature,
+            hybrid_signature: hybrid_signature.unwrap_or_default(),
+            metadata: Some(metadata),
+            cosignatures: Vec::new(),
+        }
+    }
+
+    /// Adds a signature with an owned key set to an armored signed
+    /// message, next to the ones already on it, which stay valid. Key sets
+    /// can't sign the same message twice.
+    pub fn cosign_text(
+        signed_text: &str, keyset: &OwnedKeySet, options: &KdtSignOptions,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut signed_message = KdtSignedMessage::parse(signed_text)?;
+        if signed_message
+            .signatures()
+            .iter()
+            .any(|s| s.is_by(&keyset.pubkey_pair.id))
+        {
+            return Err(Box::new(KdtErr::AlreadySigned));
+        }
+        let message = signed_message.message.as_bytes();
+        let cosignature = Self::sign_bytes_with_options(message, keyset, options)?;
+        signed_message.cosignatures.push(cosignature);
+        Ok(signed_message.to_string())
     }
 
-    /// Verifies a KDT dilithium-signed message against its
-    /// corresponding public key.
+    /// Adds a signature over everything read from `reader` with an owned
+    /// key set to a detached signature over the same data, like
+    /// `KdtSignageHandler::cosign_text`.
+    pub fn cosign_reader<R: Read>(
+        reader: &mut R, mut signature: KdtDetachedSignature, keyset: &OwnedKeySet,
+        options: &KdtSignOptions,
+    ) -> Result<KdtDetachedSignature, Box<dyn Error>> {
+        if signature
+            .signatures()
+            .iter()
+            .any(|s| s.is_by(&keyset.pubkey_pair.id))
+        {
+            return Err(Box::new(KdtErr::AlreadySigned));
+        }
+        let cosignature = Self::sign_reader_with_options(reader, keyset, options)?;
+        signature.cosignatures.push(cosignature);
+        Ok(signature)
+    }
+
+    /// Verifies a KDT signed message against its corresponding public
+    /// key. Hybrid keys only accept hybrid signatures, and only when
+    /// both the Dilithium and the Ed25519 signature are valid, so
+    /// breaking one of the two schemes isn't enough to forge one. The
+    /// signature has to be made with the algorithm and at the level of
+    /// the key, Dilithium or SLH-DSA, so keys of every level can be
+    /// checked side by side. Signatures with metadata only verify against
+    /// the key they claim to be made by.
+    pub fn verify(signed_message: &KdtSignedMessage, pubkey: &PubKeyPair) -> bool {
+        let message = signed_message.message.as_bytes();
+        let metadata = signed_message.metadata.as_ref();
+        Self::verify_parts(
+            &Self::representation(signed_message.algorithm, metadata).of(message),
+            signed_message.algorithm,
+            &signed_message.signature,
+            &signed_message.hybrid_signature,
+            signed_message.metadata.as_ref(),
+            pubkey,
+        )
+    }
+
+    /// Verifies a detached signature over the given bytes against a
+    /// public key, by the same rules as `KdtSignageHandler::verify`.
     #[inline(always)]
-    pub fn verify(signed_message: KdtSignedMessage, pubkey: Vec<u8>) -> bool {
-        let text_bytes = signed_message.message.as_bytes();
-        let sig_bytes = signed_message.signature;
-        dilithium_verify(&sig_bytes, text_bytes, &pubkey).is_ok()
+    pub fn verify_bytes(
+        bytes: &[u8], signature: &KdtDetachedSignature, pubkey: &PubKeyPair,
+    ) -> bool {
+        let representation = Self::representation(signature.algorithm, signature.metadata.as_ref());
+        Self::verify_represented(&representation.of(bytes), signature, pubkey)
+    }
+
+    /// Verifies a detached signature over what stands in for the message
+    /// against a public key.
+    fn verify_represented(
+        message: &[u8], signature: &KdtDetachedSignature, pubkey: &PubKeyPair,
+    ) -> bool {
+        Self::verify_parts(
+            message,
+            signature.algorithm,
+            &signature.signature,
+            &signature.hybrid_signature,
+            signature.metadata.as_ref(),
+            pubkey,
+        )
+    }
+
+    /// Checks the signatures of the given algorithm over `message`, and
+    /// the metadata if there is any, against a public key. Prehashed
+    /// signatures pass the digest of the message as `message`.
+    fn verify_parts(
+        message: &[u8], algorithm: KdtSignatureAlgorithm, signature: &[u8],
+        hybrid_signature: &[u8], metadata: Option<&KdtSignatureMetadata>, pubkey: &PubKeyPair,
+    ) -> bool {
+        if algorithm != pubkey.signature_algorithm() {
+            return false;
+        }
+        if metadata.is_some_and(|m| m.signer_id != pubkey.id) {
+            return false;
+        }
+        let pre_hash = metadata.and_then(|m| Self::hash_ml_dsa(algorithm, m.hash));
+        let signed_data = &match (metadata, pre_hash) {
+            (Some(metadata), Some(_)) => metadata.hash.frame(message),
+            (Some(metadata), None) => metadata.signed_data(message),
+            (None, _) => message.to_vec(),
+        };
+        let context = metadata.map_or(&[][..], |m| &m.context);
+        let pq_valid = match algorithm.slh_dsa_level() {
+            Some(level) => level.verify(signature, signed_data, context, &pubkey.signage_key),
+            None => {
+                let mode = pubkey.dilithium_level.params();
+                let context = Self::dilithium_context(pubkey.dilithium_level, context);
+                match pre_hash {
+                    Some(pre_hash) => verify_prehashed(
+                        mode,
+                        signature,
+                        message,
+                        pre_hash,
+                        context,
+                        &pubkey.signage_key,
+                    ),
+                    None => verify_ctx(mode, signature, signed_data, context, &pubkey.signage_key),
+                }
+                .is_ok()
+            }
+        };
+        // The algorithm matches the key, so hybrid signatures are only
+        // checked against hybrid keys.
+        match &pubkey.hybrid_signage_key {
+            Some(hybrid_key) => {
+                pq_valid && Self::verify_ed25519(hybrid_signature, signed_data, hybrid_key)
+            }
+            None => pq_valid,
+        }
+    }
+
+    /// What stands in for the message in a signature of the given
+    /// algorithm, with the given metadata. Prehashed ML-DSA signatures
+    /// are HashML-DSA ones over the digest of the metadata and the
+    /// message, every other signature is made over the metadata and the
+    /// message or its digest, see `KdtSignatureHash::represent`.
+    fn representation(
+        algorithm: KdtSignatureAlgorithm, metadata: Option<&KdtSignatureMetadata>,
+    ) -> KdtRepresentation {
+        match metadata {
+            Some(metadata) if Self::hash_ml_dsa(algorithm, metadata.hash).is_some() => {
+                KdtRepresentation::prefixed_digest(metadata.hash, &metadata.signed_prefix())
+            }
+            Some(metadata) => KdtRepresentation::new(metadata.hash),
+            None => KdtRepresentation::Message,
+        }
+    }
+
+    /// The FIPS 204 hash function a prehashed signature of the given
+    /// algorithm is a HashML-DSA signature under, if it's an ML-DSA one.
+    /// Round 3 Dilithium and SLH-DSA sign KDT's own prehashes instead.
+    fn hash_ml_dsa(algorithm: KdtSignatureAlgorithm, hash: KdtSignatureHash) -> Option<PreHash> {
+        algorithm
+            .dilithium_level()
+            .filter(|level| level.params().is_ml_dsa())
+            .and(hash.pre_hash())
+    }
+
+    /// The context string Dilithium signs with at the given level. Round
+    /// 3 Dilithium doesn't have any, so the signed metadata is all that
+    /// binds it there.
+    fn dilithium_context(level: KdtDilithiumLevel, context: &[u8]) -> &[u8] {
+        match level.params().is_ml_dsa() {
+            true => context,
+            false => &[],
+        }
+    }
+
+    /// Looks for the key that produced the signature on a KDT signed
+    /// message among the given public keys. Returns `None` if none of
+    /// them did, meaning the signature is either invalid or comes from
+    /// a key we don't know.
+    pub fn find_signer<'a>(
+        signed_message: &KdtSignedMessage, pubkeys: &'a [PubKeyPair],
+    ) -> Option<&'a PubKeyPair> {
+        Self::signer_candidates(signed_message.metadata.as_ref(), pubkeys)
+            .find(|k| Self::verify(signed_message, k))
+    }
+
+    /// Checks the signature on a decrypted plaintext, if it's a KDT signed
+    /// message, against the given public keys, and returns the inner
+    /// message along with who signed it. Plaintexts that only look like
+    /// signed messages come back whole, as unverified, since whoever
+    /// encrypted them may still have something to say.
+    pub fn check_plaintext(plaintext: String, pubkeys: &[PubKeyPair]) -> (String, KdtSignerStatus) {
+        if !KdtSignedMessage::is_signed_message(&plaintext) {
+            return (plaintext, KdtSignerStatus::Unsigned);
+        }
+        let Ok(signed_message) = KdtSignedMessage::parse(&plaintext) else {
+            return (plaintext, KdtSignerStatus::Unverified);
+        };
+        let status = match Self::find_signer(&signed_message, pubkeys) {
+            Some(signer) => KdtSignerStatus::Valid(signer.clone()),
+            None => KdtSignerStatus::Unverified,
+        };
+        (signed_message.message, status)
+    }
+
+    /// Looks for the key that produced a detached signature over the
+    /// given bytes among the given public keys, like
+    /// `KdtSignageHandler::find_signer`.
+    pub fn find_detached_signer<'a>(
+        bytes: &[u8], signature: &KdtDetachedSignature, pubkeys: &'a [PubKeyPair],
+    ) -> Option<&'a PubKeyPair> {
+        let representation = Self::representation(signature.algorithm, signature.metadata.as_ref());
+        let message = representation.of(bytes);
+        Self::signer_candidates(signature.metadata.as_ref(), pubkeys)
+            .find(|k| Self::verify_represented(&message, signature, k))
+    }
+
+    /// Looks for the key that produced a detached signature over
+    /// everything read from `reader`, like
+    /// `KdtSignageHandler::find_detached_signer`. Prehashed signatures
+    /// are checked in constant memory.
+    pub fn find_detached_signer_in_reader<'a, R: Read>(
+        reader: &mut R, signature: &KdtDetachedSignature, pubkeys: &'a [PubKeyPair],
+    ) -> Result<Option<&'a PubKeyPair>, Box<dyn Error>> {
+        let representation = Self::representation(signature.algorithm, signature.metadata.as_ref());
+        let message = KdtRepresentation::read_all(vec![representation], reader)?.remove(0);
+        Ok(
+            Self::signer_candidates(signature.metadata.as_ref(), pubkeys)
+                .find(|k| Self::verify_represented(&message, signature, k)),
+        )
+    }
+
+    /// Checks every signature on a KDT signed message on its own, looking
+    /// for the key that made it among the given public keys.
+    pub fn check_signatures(
+        signed_message: &KdtSignedMessage, pubkeys: &[PubKeyPair],
+    ) -> Vec<KdtSignatureCheck> {
+        let message = signed_message.message.as_bytes();
+        signed_message
+            .signatures()
+            .iter()
+            .map(|s| {
+                let representation = Self::representation(s.algorithm, s.metadata.as_ref());
+                Self::check_represented(&representation.of(message), s, pubkeys)
+            })
+            .collect()
+    }
+
+    /// Checks a detached signature over everything read from `reader`
+    /// and each of its co-signatures on its own, like
+    /// `KdtSignageHandler::check_signatures`. The input is read once,
+    /// however many signatures there are.
+    pub fn check_detached_signatures_in_reader<R: Read>(
+        reader: &mut R, signature: &KdtDetachedSignature, pubkeys: &[PubKeyPair],
+    ) -> Result<Vec<KdtSignatureCheck>, Box<dyn Error>> {
+        let signatures = signature.signatures();
+        let representations = signatures
+            .iter()
+            .map(|s| Self::representation(s.algorithm, s.metadata.as_ref()))
+            .collect();
+        let messages = KdtRepresentation::read_all(representations, reader)?;
+        Ok(signatures
+            .iter()
+            .zip(messages)
+            .map(|(s, message)| Self::check_represented(&message, s, pubkeys))
+            .collect())
+    }
+
+    /// Looks for the key that made a single signature over what stands
+    /// in for the message.
+    fn check_represented(
+        message: &[u8], signature: &KdtDetachedSignature, pubkeys: &[PubKeyPair],
+    ) -> KdtSignatureCheck {
+        let signer = Self::signer_candidates(signature.metadata.as_ref(), pubkeys)
+            .find(|k| Self::verify_represented(message, signature, k));
+        KdtSignatureCheck {
+            status: match signer {
+                Some(signer) => KdtSignerStatus::Valid(signer.clone()),
+                None => KdtSignerStatus::Unverified,
+            },
+            metadata: signature.metadata.clone(),
+        }
+    }
+
+    /// The keys that could have made a signature: the one its metadata
+    /// names as the signer, or every key if it predates metadata.
+    fn signer_candidates<'a>(
+        metadata: Option<&KdtSignatureMetadata>, pubkeys: &'a [PubKeyPair],
+    ) -> impl Iterator<Item = &'a PubKeyPair> {
+        let signer_id = metadata.map(|m| m.signer_id.clone());
+        pubkeys
+            .iter()
+            .filter(move |k| signer_id.as_ref().is_none_or(|id| *id == k.id))
+    }
+
+    /// Checks the Ed25519 half of a hybrid signature. Malformed keys or
+    /// signatures simply don't verify.
+    fn verify_ed25519(signature: &[u8], message: &[u8], pubkey: &[u8]) -> bool {
+        let (Ok(pubkey), Ok(signature)) = (
+            <[u8; 32]>::try_from(pubkey),
+            <[u8; 64]>::try_from(signature),
+        ) else {
+            return false;
+        };
+        match Ed25519VerifyingKey::from_bytes(&pubkey) {
+            Ok(key) => key
+                .verify_strict(message, &Ed25519Signature::from_bytes(&signature))
+                .is_ok(),
+            Err(_) => false,
+        }
     }
 }
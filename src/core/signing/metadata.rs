@@ -0,0 +1,170 @@
+// -- imports --
+use crate::core::*;
+use std::{
+    fmt,
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+/// Prefix of the data a signature with metadata is made over, so it can
+/// never be mistaken for a signature over a bare message.
+pub const SIGNED_METADATA_MAGIC: &[u8] = b"KDTSIGMETA";
+
+// -- signature metadata --
+/// What a signature says about itself: when and by whom it was made,
+/// how the message was hashed, and whatever notations the signer added.
+/// It's signed along with the message, so it can't be altered without
+/// breaking the signature. Signatures made before metadata existed
+/// don't have any.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct KdtSignatureMetadata {
+    /// When the signature was made, in seconds since the Unix epoch.
+    pub created: u64,
+
+    /// Id of the public key of the signer.
+    pub signer_id: String,
+
+    /// How the message was hashed before signing.
+    pub hash: KdtSignatureHash,
+
+    /// Free-form notations, as key and value, in the order they were
+    /// added.
+    pub notations: Vec<(String, String)>,
+
+    /// Context string the signature is bound to, empty unless the signer
+    /// picked one. See `KdtSignOptions::context`.
+    pub context: Vec<u8>,
+}
+
+impl KdtSignatureMetadata {
+    /// Creates the metadata of a signature made right now by the key with
+    /// the given public id.
+    pub fn new(signer_id: String, options: &KdtSignOptions) -> Self {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Self {
+            created,
+            signer_id,
+            hash: options.hash,
+            notations: options.notations.clone(),
+            context: options.context.clone(),
+        }
+    }
+
+    /// The records the metadata is stored as.
+    pub fn to_records(&self) -> KdtRecords {
+        let mut records = KdtRecords::new();
+        records.push(SIGNATURE_CREATED, self.created.to_be_bytes());
+        records.push(SIGNATURE_SIGNER, self.signer_id.as_bytes());
+        records.push(SIGNATURE_HASH, [self.hash.id()]);
+        for (key, value) in &self.notations {
+            let mut notation = KdtRecords::new();
+            notation.push(NOTATION_KEY, key.as_bytes());
+            notation.push(NOTATION_VALUE, value.as_bytes());
+            records.push(SIGNATURE_NOTATION, notation.encode());
+        }
+        if !self.context.is_empty() {
+            records.push(SIGNATURE_CONTEXT, self.context.clone());
+        }
+        records
+    }
+
+    /// Restores the metadata from the records of a signature, if it has
+    /// any.
+    pub fn from_records(records: &KdtRecords) -> Result<Option<Self>, Box<dyn Error>> {
+        let Some(created) = records.get(SIGNATURE_CREATED) else {
+            return Ok(None);
+        };
+        let created = <[u8; 8]>::try_from(created).map_err(|_| KdtErr::BadContainer)?;
+        let signer_id = String::from_utf8(records.require(SIGNATURE_SIGNER)?.to_vec())?;
+        let hash = KdtSignatureHash::from_id(records.require_u8(SIGNATURE_HASH)?)
+            .ok_or(KdtErr::UnknownAlgorithm)?;
+        let notations = records
+            .get_all(SIGNATURE_NOTATION)
+            .map(|notation| {
+                let notation = KdtRecords::decode(notation)?;
+                Ok((
+                    String::from_utf8(notation.require(NOTATION_KEY)?.to_vec())?,
+                    String::from_utf8(notation.require(NOTATION_VALUE)?.to_vec())?,
+                ))
+            })
+            .collect::<Result<_, Box<dyn Error>>>()?;
+
+        Ok(Some(Self {
+            created: u64::from_be_bytes(created),
+            signer_id,
+            hash,
+            notations,
+            context: records
+                .get(SIGNATURE_CONTEXT)
+                .unwrap_or_default()
+                .to_vec(),
+        }))
+    }
+
+    /// The bytes that actually get signed: the metadata records, prefixed
+    /// with their length, followed by the message.
+    #[inline(always)]
+    pub fn signed_data(&self, message: &[u8]) -> Vec<u8> {
+        [&self.signed_prefix(), message].concat()
+    }
+
+    /// What comes before the message in `KdtSignatureMetadata::signed_data`.
+    pub fn signed_prefix(&self) -> Vec<u8> {
+        let records = self.to_records().encode();
+        [
+            SIGNED_METADATA_MAGIC,
+            &(records.len() as u32).to_be_bytes(),
+            &records,
+        ]
+        .concat()
+    }
+
+    /// The creation time as a UTC date, eg `2024-05-17 13:37:00 UTC`.
+    pub fn created_utc(&self) -> String {
+        let (days, seconds) = (self.created / 86400, self.created % 86400);
+        // Howard Hinnant's `civil_from_days`, shifted so years start in
+        // March and leap days come last.
+        let z = days as i64 + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    }
+}
+
+impl fmt::Display for KdtSignatureMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Signed by: {}\nSigned on: {}\nHash: {}",
+            self.signer_id,
+            self.created_utc(),
+            self.hash
+        )?;
+        for (key, value) in &self.notations {
+            write!(f, "\nNotation: {}={}", key, value)?;
+        }
+        if !self.context.is_empty() {
+            write!(f, "\nContext: {}", String::from_utf8_lossy(&self.context))?;
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,20 @@
 // -- compiler flags --
 #![allow(dead_code)]
 
+pub mod detached_signature;
 pub mod handler;
+pub mod metadata;
+pub mod options;
+pub mod policy;
+pub mod prehash;
 pub mod signed_message;
+pub mod slh_dsa;
 
+pub use detached_signature::*;
 pub use handler::*;
+pub use metadata::*;
+pub use options::*;
+pub use policy::*;
+pub use prehash::*;
 pub use signed_message::*;
+pub use slh_dsa::*;
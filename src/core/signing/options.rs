@@ -0,0 +1,88 @@
+// -- imports --
+use crate::core::*;
+use pqc_dilithium::SigningMode;
+
+/// Longest context string a signature can be bound to, in bytes, as
+/// FIPS 204 and 205 have it.
+pub const MAX_CONTEXT_SIZE: usize = 255;
+
+// -- signing options --
+/// Knobs for signing. The defaults are what KDT does when nothing else
+/// is asked for.
+#[derive(Clone, Default, Debug)]
+pub struct KdtSignOptions {
+    /// Key and value notations to add to the signed metadata of the
+    /// signature.
+    pub notations: Vec<(String, String)>,
+
+    /// How the message is hashed before signing. Prehashing lets inputs
+    /// of any size be signed and verified in constant memory.
+    pub hash: KdtSignatureHash,
+
+    /// Makes Dilithium and SLH-DSA derive their randomness from the key
+    /// and the message alone, instead of hedging with fresh randomness.
+    /// Hedged signing holds up better against fault and side channel
+    /// attacks, so only use this where signatures need to be
+    /// reproducible, eg in tests.
+    pub deterministic: bool,
+
+    /// Context string to bind the signature to, at most
+    /// `MAX_CONTEXT_SIZE` bytes, so it can't be passed off as made for
+    /// another purpose. ML-DSA and SLH-DSA take it as their FIPS 204/205
+    /// context string, round 3 Dilithium only through the signed
+    /// metadata, which records it either way.
+    pub context: Vec<u8>,
+}
+
+impl KdtSignOptions {
+    /// Creates the default options.
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how the message is hashed before signing.
+    #[inline(always)]
+    pub fn with_hash(mut self, hash: KdtSignatureHash) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    /// Sets whether Dilithium signs deterministically.
+    #[inline(always)]
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Sets the context string to bind the signature to. Signing fails
+    /// if it's longer than `MAX_CONTEXT_SIZE` bytes.
+    #[inline(always)]
+    pub fn with_context<C: Into<Vec<u8>>>(mut self, context: C) -> Self {
+        self.context = context.into();
+        self
+    }
+
+    /// The Dilithium signing mode the options call for.
+    #[inline(always)]
+    pub fn signing_mode(&self) -> SigningMode {
+        match self.deterministic {
+            true => SigningMode::Deterministic,
+            false => SigningMode::Hedged(None),
+        }
+    }
+
+    /// Adds a notation.
+    #[inline(always)]
+    pub fn with_notation<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.notations.push((key.into(), value.into()));
+        self
+    }
+
+    /// Adds several notations at once.
+    #[inline(always)]
+    pub fn with_notations(mut self, notations: Vec<(String, String)>) -> Self {
+        self.notations.extend(notations);
+        self
+    }
+}
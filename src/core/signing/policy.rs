@@ -0,0 +1,103 @@
+// -- imports --
+use crate::core::*;
+use std::path::Path;
+
+// -- signing policy --
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+/// Says what it takes for a document to count as signed: at least
+/// `threshold` of the listed keys have to have made a valid signature
+/// over it. Policies are stored in RON files, like the key databases,
+/// eg:
+///
+/// ```ron
+/// (
+///     threshold: 2,
+///     signers: ["0501BB82...", "02675F30...", "2835AA2E...", "3E6FFCB5..."],
+/// )
+/// ```
+pub struct KdtSignPolicy {
+    /// How many of the signers have to sign.
+    pub threshold: usize,
+
+    /// Public key ids of everyone whose signature counts.
+    pub signers: Vec<String>,
+}
+
+/// How a document fared against a signing policy.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct KdtPolicyVerdict {
+    /// How many of the signers have to sign, as the policy says.
+    pub threshold: usize,
+
+    /// Ids of the policy's signers who made a valid signature.
+    pub signed: Vec<String>,
+
+    /// Ids of the policy's signers who didn't.
+    pub missing: Vec<String>,
+}
+
+impl KdtPolicyVerdict {
+    /// Whether enough of the signers signed.
+    #[inline(always)]
+    pub fn is_satisfied(&self) -> bool {
+        self.signed.len() >= self.threshold
+    }
+}
+
+impl KdtSignPolicy {
+    /// Creates a policy asking for `threshold` of the given signers.
+    #[inline(always)]
+    pub fn new(threshold: usize, signers: Vec<String>) -> Self {
+        Self { threshold, signers }
+    }
+
+    /// Derives a `KdtSignPolicy` from its RON form, making sure it can
+    /// actually be satisfied.
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let policy: Self = ron::from_str(text).map_err(|_| KdtErr::BadPolicy)?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// Reads a policy from the RON file at `path`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Fails unless the threshold is somewhere between one and the
+    /// number of distinct signers.
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let mut signers = self.signers.clone();
+        signers.sort();
+        signers.dedup();
+        if self.threshold == 0 || self.threshold > signers.len() {
+            return Err(Box::new(KdtErr::BadPolicy));
+        }
+        Ok(())
+    }
+
+    /// Weighs the signatures on a document against the policy. Only
+    /// valid signatures by the policy's signers count, and each signer
+    /// only counts once.
+    pub fn evaluate(&self, checks: &[KdtSignatureCheck]) -> KdtPolicyVerdict {
+        let (mut signed, mut missing) = (Vec::new(), Vec::new());
+        for id in &self.signers {
+            if signed.contains(id) || missing.contains(id) {
+                continue;
+            }
+            let has_signed = checks
+                .iter()
+                .any(|c| matches!(&c.status, KdtSignerStatus::Valid(key) if key.id == *id));
+            match has_signed {
+                true => signed.push(id.clone()),
+                false => missing.push(id.clone()),
+            }
+        }
+
+        KdtPolicyVerdict {
+            threshold: self.threshold,
+            signed,
+            missing,
+        }
+    }
+}
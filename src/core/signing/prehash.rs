@@ -0,0 +1,247 @@
+// -- imports --
+use crate::core::*;
+use pqc_dilithium::PreHash;
+use sha2::{
+    Digest,
+    Sha512,
+};
+use sha3::{
+    digest::{
+        ExtendableOutput,
+        Update,
+    },
+    Shake256,
+};
+use std::{
+    fmt,
+    io::Read,
+};
+
+/// Prefix of what a prehashed signature is made over instead of the
+/// message, followed by the id of the hash algorithm and the digest, so
+/// a digest can never pass for a message signed as-is.
+pub const PREHASH_DOMAIN: &[u8] = b"KDTPREHASH";
+
+/// Length of the digest of every prehash algorithm, in bytes.
+pub const PREHASH_DIGEST_SIZE: usize = 64;
+
+/// How much of the input is hashed at a time when prehashing a reader.
+pub const PREHASH_CHUNK_SIZE: usize = 64 * 1024;
+
+// -- hash algorithms --
+/// How the message is fed to the signature schemes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum KdtSignatureHash {
+    /// The message is signed as-is, and Dilithium hashes it itself.
+    /// Needs the whole message in memory.
+    #[default]
+    Pure,
+
+    /// The message is hashed with SHA-512 first, and the digest gets
+    /// signed in its place. ML-DSA keys sign it as FIPS 204's HashML-DSA,
+    /// the others in KDT's own style, see `PREHASH_DOMAIN`.
+    Sha512,
+
+    /// Like `KdtSignatureHash::Sha512`, with 64 bytes of SHAKE256.
+    Shake256,
+}
+
+impl KdtSignatureHash {
+    /// Restores the hash algorithm from its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" | "pure" => Some(Self::Pure),
+            "sha512" | "sha-512" => Some(Self::Sha512),
+            "shake256" => Some(Self::Shake256),
+            _ => None,
+        }
+    }
+
+    /// Numeric id of the hash algorithm, as stored in containers.
+    pub fn id(&self) -> u8 {
+        match self {
+            Self::Pure => 0,
+            Self::Sha512 => 1,
+            Self::Shake256 => 2,
+        }
+    }
+
+    /// Restores the hash algorithm from its numeric id.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::Pure),
+            1 => Some(Self::Sha512),
+            2 => Some(Self::Shake256),
+            _ => None,
+        }
+    }
+
+    /// The FIPS 204 hash function of the same name, which ML-DSA keys
+    /// sign HashML-DSA digests of this algorithm under.
+    pub fn pre_hash(&self) -> Option<PreHash> {
+        match self {
+            Self::Pure => None,
+            Self::Sha512 => Some(PreHash::Sha2_512),
+            Self::Shake256 => Some(PreHash::Shake256),
+        }
+    }
+
+    /// What gets signed in place of `message`: the message itself, or
+    /// its domain-separated digest.
+    #[inline(always)]
+    pub fn represent(&self, message: &[u8]) -> Vec<u8> {
+        KdtRepresentation::new(*self).of(message)
+    }
+
+    /// Like `KdtSignatureHash::represent`, reading the message from
+    /// `reader`. Prehashing only ever holds a chunk of it in memory, so
+    /// it works on inputs of any size.
+    #[inline(always)]
+    pub fn represent_reader<R: Read>(&self, reader: &mut R) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(KdtRepresentation::read_all(vec![KdtRepresentation::new(*self)], reader)?.remove(0))
+    }
+
+    /// `digest` in KDT's own style: `PREHASH_DOMAIN`, the id of the hash
+    /// algorithm, then the digest itself.
+    pub fn frame(&self, digest: &[u8]) -> Vec<u8> {
+        [PREHASH_DOMAIN, &[self.id()], digest].concat()
+    }
+}
+
+impl fmt::Display for KdtSignatureHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pure => write!(f, "none (pure)"),
+            Self::Sha512 => write!(f, "sha512"),
+            Self::Shake256 => write!(f, "shake256"),
+        }
+    }
+}
+
+// -- representations --
+/// What stands in for a message when it's signed, worked out as the
+/// message is read, so several of them can share a single pass over it.
+pub enum KdtRepresentation {
+    /// The message itself, which means holding all of it in memory.
+    Message,
+
+    /// The digest of the message in KDT's own style, see
+    /// `KdtSignatureHash::frame`.
+    Framed(KdtPrehasher),
+
+    /// The bare digest of whatever the prehasher was fed before the
+    /// message, then the message, for FIPS 204's HashML-DSA.
+    Digest(KdtPrehasher),
+}
+
+impl KdtRepresentation {
+    /// The message itself, or its framed digest.
+    pub fn new(hash: KdtSignatureHash) -> Self {
+        match KdtPrehasher::new(hash) {
+            Some(prehasher) => Self::Framed(prehasher),
+            None => Self::Message,
+        }
+    }
+
+    /// The bare digest of `prefix` followed by the message, or the
+    /// message itself if there's no hash algorithm.
+    pub fn prefixed_digest(hash: KdtSignatureHash, prefix: &[u8]) -> Self {
+        match KdtPrehasher::new(hash) {
+            Some(mut prehasher) => {
+                prehasher.update(prefix);
+                Self::Digest(prehasher)
+            }
+            None => Self::Message,
+        }
+    }
+
+    /// The representation of `message`.
+    pub fn of(self, message: &[u8]) -> Vec<u8> {
+        Self::read_all(vec![self], &mut &message[..])
+            .expect("Reading from memory can't fail")
+            .remove(0)
+    }
+
+    /// The given representations of the message read from `reader`, all
+    /// at once, so the input only has to be read once. Only holds the
+    /// whole message in memory if one of them is
+    /// `KdtRepresentation::Message`.
+    pub fn read_all<R: Read>(
+        mut representations: Vec<Self>, reader: &mut R,
+    ) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        let keeps_message = representations
+            .iter()
+            .any(|r| matches!(r, Self::Message));
+        let mut message = Vec::new();
+        let mut chunk = vec![0u8; PREHASH_CHUNK_SIZE];
+        loop {
+            let read = match reader.read(&mut chunk)? {
+                0 => break,
+                read => &chunk[..read],
+            };
+            for representation in &mut representations {
+                if let Self::Framed(prehasher) | Self::Digest(prehasher) = representation {
+                    prehasher.update(read);
+                }
+            }
+            if keeps_message {
+                message.extend_from_slice(read);
+            }
+        }
+        Ok(representations
+            .into_iter()
+            .map(|representation| match representation {
+                Self::Message => message.clone(),
+                Self::Framed(prehasher) => prehasher.finalize(),
+                Self::Digest(prehasher) => prehasher.digest().1,
+            })
+            .collect())
+    }
+}
+
+// -- prehasher --
+/// Incremental hash of a message that's being prehashed.
+pub enum KdtPrehasher {
+    Sha512(Sha512),
+    Shake256(Shake256),
+}
+
+impl KdtPrehasher {
+    /// Starts hashing with the given algorithm. Returns `None` for
+    /// `KdtSignatureHash::Pure`, which doesn't prehash.
+    pub fn new(hash: KdtSignatureHash) -> Option<Self> {
+        match hash {
+            KdtSignatureHash::Pure => None,
+            KdtSignatureHash::Sha512 => Some(Self::Sha512(Sha512::new())),
+            KdtSignatureHash::Shake256 => Some(Self::Shake256(Shake256::default())),
+        }
+    }
+
+    /// Feeds the next part of the message to the hash.
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha512(hasher) => Digest::update(hasher, bytes),
+            Self::Shake256(hasher) => Update::update(hasher, bytes),
+        }
+    }
+
+    /// The bare digest of the message, along with the algorithm that
+    /// made it.
+    pub fn digest(self) -> (KdtSignatureHash, Vec<u8>) {
+        match self {
+            Self::Sha512(hasher) => (KdtSignatureHash::Sha512, hasher.finalize().to_vec()),
+            Self::Shake256(hasher) => {
+                let mut digest = vec![0u8; PREHASH_DIGEST_SIZE];
+                hasher.finalize_xof_into(&mut digest);
+                (KdtSignatureHash::Shake256, digest)
+            }
+        }
+    }
+
+    /// The domain-separated digest of the message, see
+    /// `KdtSignatureHash::frame`.
+    pub fn finalize(self) -> Vec<u8> {
+        let (hash, digest) = self.digest();
+        hash.frame(&digest)
+    }
+}
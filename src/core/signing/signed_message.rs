@@ -1,60 +1,525 @@
 // -- imports --
 use crate::core::*;
+use pqc_dilithium::DilithiumMode;
 use std::fmt;
 
+/// First line of every armored KDT signed message.
+pub const SIGNED_MESSAGE_HEADER: &str = "-----BEGIN KDT SIGNED MESSAGE-----";
+
+/// What checking the signature on a message against the known public
+/// keys turned up.
+pub enum KdtSignerStatus {
+    /// The message wasn't signed at all.
+    Unsigned,
+
+    /// The message carries a valid signature from this key.
+    Valid(PubKeyPair),
+
+    /// The message is signed, but no known key produced a valid
+    /// signature over it.
+    Unverified,
+}
+
+/// What checking one of the signatures on a message turned up, along
+/// with the signed metadata of that signature.
+pub struct KdtSignatureCheck {
+    pub status: KdtSignerStatus,
+    pub metadata: Option<KdtSignatureMetadata>,
+}
+
+/// Label of the armor around the signature block of a signed message.
+pub const SIGNATURE_ARMOR_LABEL: &str = "KDT SIGNATURE";
+
+// -- container record tags --
+/// Id of the signature algorithm.
+pub const SIGNATURE_ALGORITHM: u8 = 1;
+/// Dilithium signature bytes.
+pub const SIGNATURE_VALUE: u8 = 2;
+/// Ed25519 signature bytes. Only there for hybrid signatures.
+pub const SIGNATURE_HYBRID_VALUE: u8 = 3;
+/// Creation time of the signature, as big-endian Unix seconds. Only
+/// there for signatures with metadata, like the tags below.
+pub const SIGNATURE_CREATED: u8 = 4;
+/// Public key id of the signer.
+pub const SIGNATURE_SIGNER: u8 = 5;
+/// Id of the hash algorithm the message was signed with.
+pub const SIGNATURE_HASH: u8 = 6;
+/// A notation, as records of its own: `NOTATION_KEY` and
+/// `NOTATION_VALUE`. There can be any number of these.
+pub const SIGNATURE_NOTATION: u8 = 7;
+/// The records of another signer's signature over the same content.
+/// There can be any number of these too.
+pub const SIGNATURE_COSIGNATURE: u8 = 8;
+/// Context string the signature is bound to. Only there if it isn't
+/// empty.
+pub const SIGNATURE_CONTEXT: u8 = 9;
+
+/// Key of a notation, within its `SIGNATURE_NOTATION` record.
+pub const NOTATION_KEY: u8 = 1;
+/// Value of a notation, within its `SIGNATURE_NOTATION` record.
+pub const NOTATION_VALUE: u8 = 2;
+
+// -- dilithium parameter sets --
+/// Dilithium parameter sets a key set can sign with.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum KdtDilithiumLevel {
+    /// Dilithium2, NIST security level 2. Smaller keys and signatures,
+    /// for peers that can't afford the bigger ones.
+    Dilithium2,
+
+    /// Dilithium3, NIST security level 3. What every key set was before
+    /// the level could be picked, hence the serde default.
+    #[default]
+    Dilithium3,
+
+    /// Dilithium5, NIST security level 5.
+    Dilithium5,
+
+    /// ML-DSA-44, the FIPS 204 standard of Dilithium2.
+    MlDsa44,
+
+    /// ML-DSA-65, the FIPS 204 standard of Dilithium3, and what new key
+    /// sets get.
+    MlDsa65,
+
+    /// ML-DSA-87, the FIPS 204 standard of Dilithium5.
+    MlDsa87,
+}
+
+impl KdtDilithiumLevel {
+    /// The level new key sets are generated with, unless asked otherwise.
+    pub const RECOMMENDED: Self = Self::MlDsa65;
+
+    /// Restores the level from its name, which is either the bare number
+    /// of the parameter set (eg "5" or "65") or its full name (eg
+    /// "dilithium5" or "ml-dsa-65").
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "2" | "dilithium2" => Some(Self::Dilithium2),
+            "3" | "dilithium3" => Some(Self::Dilithium3),
+            "5" | "dilithium5" => Some(Self::Dilithium5),
+            "44" | "ml-dsa-44" => Some(Self::MlDsa44),
+            "65" | "ml-dsa-65" => Some(Self::MlDsa65),
+            "87" | "ml-dsa-87" => Some(Self::MlDsa87),
+            _ => None,
+        }
+    }
+
+    /// The matching parameter set of the Dilithium library.
+    #[inline(always)]
+    pub fn params(&self) -> DilithiumMode {
+        match self {
+            Self::Dilithium2 => DilithiumMode::Dilithium2,
+            Self::Dilithium3 => DilithiumMode::Dilithium3,
+            Self::Dilithium5 => DilithiumMode::Dilithium5,
+            Self::MlDsa44 => DilithiumMode::MlDsa44,
+            Self::MlDsa65 => DilithiumMode::MlDsa65,
+            Self::MlDsa87 => DilithiumMode::MlDsa87,
+        }
+    }
+}
+
+impl fmt::Display for KdtDilithiumLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dilithium2 => write!(f, "dilithium2"),
+            Self::Dilithium3 => write!(f, "dilithium3"),
+            Self::Dilithium5 => write!(f, "dilithium5"),
+            Self::MlDsa44 => write!(f, "ml-dsa-44"),
+            Self::MlDsa65 => write!(f, "ml-dsa-65"),
+            Self::MlDsa87 => write!(f, "ml-dsa-87"),
+        }
+    }
+}
+
+/// Signature schemes a KDT signed message can be signed with.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KdtSignatureAlgorithm {
+    /// Dilithium on its own. Signature blocks that aren't containers
+    /// predate hybrid keys and parameter sets, so they're always
+    /// Dilithium3.
+    Dilithium(KdtDilithiumLevel),
+
+    /// A Dilithium and an Ed25519 signature over the same message, made
+    /// by hybrid keys. Both have to be valid.
+    DilithiumEd25519(KdtDilithiumLevel),
+
+    /// SLH-DSA on its own, made by key sets that sign with it instead of
+    /// Dilithium.
+    SlhDsa(KdtSlhDsaLevel),
+
+    /// An SLH-DSA and an Ed25519 signature over the same message, made by
+    /// hybrid SLH-DSA keys. Both have to be valid.
+    SlhDsaEd25519(KdtSlhDsaLevel),
+}
+
+impl KdtSignatureAlgorithm {
+    /// Creates the algorithm keys of the given level sign with, combined
+    /// with Ed25519 or not.
+    #[inline(always)]
+    pub fn new(level: KdtDilithiumLevel, hybrid: bool) -> Self {
+        match hybrid {
+            true => Self::DilithiumEd25519(level),
+            false => Self::Dilithium(level),
+        }
+    }
+
+    /// Creates the algorithm a key set signs with, out of the levels it
+    /// remembers. Key sets with an SLH-DSA level sign with SLH-DSA, and
+    /// their Dilithium level means nothing.
+    #[inline(always)]
+    pub fn of_key(
+        dilithium_level: KdtDilithiumLevel, slh_dsa_level: Option<KdtSlhDsaLevel>, hybrid: bool,
+    ) -> Self {
+        match (slh_dsa_level, hybrid) {
+            (Some(level), true) => Self::SlhDsaEd25519(level),
+            (Some(level), false) => Self::SlhDsa(level),
+            (None, _) => Self::new(dilithium_level, hybrid),
+        }
+    }
+
+    /// Numeric id of the algorithm, as stored in containers. Dilithium3
+    /// keeps the ids it had before the level could be picked.
+    pub fn id(&self) -> u8 {
+        match self {
+            Self::Dilithium(KdtDilithiumLevel::Dilithium3) => 1,
+            Self::DilithiumEd25519(KdtDilithiumLevel::Dilithium3) => 2,
+            Self::Dilithium(KdtDilithiumLevel::Dilithium2) => 3,
+            Self::Dilithium(KdtDilithiumLevel::Dilithium5) => 4,
+            Self::DilithiumEd25519(KdtDilithiumLevel::Dilithium2) => 5,
+            Self::DilithiumEd25519(KdtDilithiumLevel::Dilithium5) => 6,
+            Self::Dilithium(KdtDilithiumLevel::MlDsa44) => 7,
+            Self::Dilithium(KdtDilithiumLevel::MlDsa65) => 8,
+            Self::Dilithium(KdtDilithiumLevel::MlDsa87) => 9,
+            Self::DilithiumEd25519(KdtDilithiumLevel::MlDsa44) => 10,
+            Self::DilithiumEd25519(KdtDilithiumLevel::MlDsa65) => 11,
+            Self::DilithiumEd25519(KdtDilithiumLevel::MlDsa87) => 12,
+            Self::SlhDsa(KdtSlhDsaLevel::Shake128s) => 13,
+            Self::SlhDsa(KdtSlhDsaLevel::Shake128f) => 14,
+            Self::SlhDsa(KdtSlhDsaLevel::Shake192s) => 15,
+            Self::SlhDsa(KdtSlhDsaLevel::Shake192f) => 16,
+            Self::SlhDsa(KdtSlhDsaLevel::Shake256s) => 17,
+            Self::SlhDsa(KdtSlhDsaLevel::Shake256f) => 18,
+            Self::SlhDsaEd25519(KdtSlhDsaLevel::Shake128s) => 19,
+            Self::SlhDsaEd25519(KdtSlhDsaLevel::Shake128f) => 20,
+            Self::SlhDsaEd25519(KdtSlhDsaLevel::Shake192s) => 21,
+            Self::SlhDsaEd25519(KdtSlhDsaLevel::Shake192f) => 22,
+            Self::SlhDsaEd25519(KdtSlhDsaLevel::Shake256s) => 23,
+            Self::SlhDsaEd25519(KdtSlhDsaLevel::Shake256f) => 24,
+        }
+    }
+
+    /// Restores the algorithm from its numeric id.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Self::Dilithium(KdtDilithiumLevel::Dilithium3)),
+            2 => Some(Self::DilithiumEd25519(KdtDilithiumLevel::Dilithium3)),
+            3 => Some(Self::Dilithium(KdtDilithiumLevel::Dilithium2)),
+            4 => Some(Self::Dilithium(KdtDilithiumLevel::Dilithium5)),
+            5 => Some(Self::DilithiumEd25519(KdtDilithiumLevel::Dilithium2)),
+            6 => Some(Self::DilithiumEd25519(KdtDilithiumLevel::Dilithium5)),
+            7 => Some(Self::Dilithium(KdtDilithiumLevel::MlDsa44)),
+            8 => Some(Self::Dilithium(KdtDilithiumLevel::MlDsa65)),
+            9 => Some(Self::Dilithium(KdtDilithiumLevel::MlDsa87)),
+            10 => Some(Self::DilithiumEd25519(KdtDilithiumLevel::MlDsa44)),
+            11 => Some(Self::DilithiumEd25519(KdtDilithiumLevel::MlDsa65)),
+            12 => Some(Self::DilithiumEd25519(KdtDilithiumLevel::MlDsa87)),
+            13 => Some(Self::SlhDsa(KdtSlhDsaLevel::Shake128s)),
+            14 => Some(Self::SlhDsa(KdtSlhDsaLevel::Shake128f)),
+            15 => Some(Self::SlhDsa(KdtSlhDsaLevel::Shake192s)),
+            16 => Some(Self::SlhDsa(KdtSlhDsaLevel::Shake192f)),
+            17 => Some(Self::SlhDsa(KdtSlhDsaLevel::Shake256s)),
+            18 => Some(Self::SlhDsa(KdtSlhDsaLevel::Shake256f)),
+            19 => Some(Self::SlhDsaEd25519(KdtSlhDsaLevel::Shake128s)),
+            20 => Some(Self::SlhDsaEd25519(KdtSlhDsaLevel::Shake128f)),
+            21 => Some(Self::SlhDsaEd25519(KdtSlhDsaLevel::Shake192s)),
+            22 => Some(Self::SlhDsaEd25519(KdtSlhDsaLevel::Shake192f)),
+            23 => Some(Self::SlhDsaEd25519(KdtSlhDsaLevel::Shake256s)),
+            24 => Some(Self::SlhDsaEd25519(KdtSlhDsaLevel::Shake256f)),
+            _ => None,
+        }
+    }
+
+    /// The Dilithium level of the algorithm, unless it's SLH-DSA.
+    #[inline(always)]
+    pub fn dilithium_level(&self) -> Option<KdtDilithiumLevel> {
+        match self {
+            Self::Dilithium(level) | Self::DilithiumEd25519(level) => Some(*level),
+            Self::SlhDsa(_) | Self::SlhDsaEd25519(_) => None,
+        }
+    }
+
+    /// The SLH-DSA level of the algorithm, unless it's Dilithium.
+    #[inline(always)]
+    pub fn slh_dsa_level(&self) -> Option<KdtSlhDsaLevel> {
+        match self {
+            Self::SlhDsa(level) | Self::SlhDsaEd25519(level) => Some(*level),
+            Self::Dilithium(_) | Self::DilithiumEd25519(_) => None,
+        }
+    }
+
+    /// Whether the algorithm involves Ed25519 as well as Dilithium or
+    /// SLH-DSA.
+    #[inline(always)]
+    pub fn is_hybrid(&self) -> bool {
+        matches!(self, Self::DilithiumEd25519(_) | Self::SlhDsaEd25519(_))
+    }
+
+    /// The same algorithm, combined with Ed25519 or not.
+    #[inline(always)]
+    pub fn with_hybrid(&self, hybrid: bool) -> Self {
+        match (self, hybrid) {
+            (Self::Dilithium(level) | Self::DilithiumEd25519(level), _) => {
+                Self::new(*level, hybrid)
+            }
+            (Self::SlhDsa(level) | Self::SlhDsaEd25519(level), true) => Self::SlhDsaEd25519(*level),
+            (Self::SlhDsa(level) | Self::SlhDsaEd25519(level), false) => Self::SlhDsa(*level),
+        }
+    }
+}
+
+impl fmt::Display for KdtSignatureAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dilithium(level) => write!(f, "{}", level),
+            Self::DilithiumEd25519(level) => write!(f, "{}+ed25519", level),
+            Self::SlhDsa(level) => write!(f, "{}", level),
+            Self::SlhDsaEd25519(level) => write!(f, "{}+ed25519", level),
+        }
+    }
+}
+
 pub struct KdtSignedMessage {
     /// Message string
     pub message: String,
 
-    /// Dilithium signature bytes
+    /// Which scheme the message was signed with.
+    pub algorithm: KdtSignatureAlgorithm,
+
+    /// Dilithium or SLH-DSA signature bytes
     pub signature: Vec<u8>,
+
+    /// Ed25519 signature bytes. Empty unless the message was signed by
+    /// a hybrid key.
+    pub hybrid_signature: Vec<u8>,
+
+    /// Signed metadata of the signature. Signatures made before metadata
+    /// existed don't have any.
+    pub metadata: Option<KdtSignatureMetadata>,
+
+    /// Signatures of other signers over the same message, each made
+    /// independently of the others.
+    pub cosignatures: Vec<KdtDetachedSignature>,
 }
 
 impl KdtSignedMessage {
+    /// Creates a signed message out of a message and its signature. The
+    /// message is canonicalized, as that's the form it's signed in.
     #[inline(always)]
     pub fn new<S: fmt::Display>(message: S, signature: Vec<u8>) -> Self {
         Self {
-            message: message.to_string(),
+            message: Self::canonicalize(&message.to_string()),
+            algorithm: KdtSignatureAlgorithm::Dilithium(KdtDilithiumLevel::Dilithium3),
             signature,
+            hybrid_signature: Vec::new(),
+            metadata: None,
+            cosignatures: Vec::new(),
+        }
+    }
+
+    /// Sets the Dilithium level the signature was made with, which is
+    /// Dilithium3 unless set otherwise.
+    #[inline(always)]
+    pub fn with_dilithium_level(mut self, level: KdtDilithiumLevel) -> Self {
+        self.algorithm = KdtSignatureAlgorithm::new(level, self.algorithm.is_hybrid());
+        self
+    }
+
+    /// Sets the algorithm the signature was made with, which is Dilithium3
+    /// unless set otherwise.
+    #[inline(always)]
+    pub fn with_algorithm(mut self, algorithm: KdtSignatureAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Adds the Ed25519 half of a hybrid signature.
+    #[inline(always)]
+    pub fn with_hybrid_signature(mut self, hybrid_signature: Vec<u8>) -> Self {
+        self.algorithm = self.algorithm.with_hybrid(true);
+        self.hybrid_signature = hybrid_signature;
+        self
+    }
+
+    /// How the message was hashed before signing. Signatures without
+    /// metadata were always made over the message itself.
+    #[inline(always)]
+    pub fn hash(&self) -> KdtSignatureHash {
+        self.metadata
+            .as_ref()
+            .map_or(KdtSignatureHash::Pure, |m| m.hash)
+    }
+
+    /// Every signature on the message, starting with the first one.
+    pub fn signatures(&self) -> Vec<KdtDetachedSignature> {
+        let signature = KdtDetachedSignature {
+            algorithm: self.algorithm,
+            signature: self.signature.clone(),
+            hybrid_signature: self.hybrid_signature.clone(),
+            metadata: self.metadata.clone(),
+            cosignatures: self.cosignatures.clone(),
+        };
+        signature.signatures()
+    }
+
+    /// Sets the signed metadata of the signature.
+    #[inline(always)]
+    pub fn with_metadata(mut self, metadata: Option<KdtSignatureMetadata>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Whether the given text is an armored KDT signed message, eg the
+    /// plaintext of a message that was signed before being encrypted.
+    #[inline(always)]
+    pub fn is_signed_message(text: &str) -> bool {
+        text.starts_with(SIGNED_MESSAGE_HEADER)
+    }
+
+    /// The canonical form of a message, which is what gets signed, so
+    /// that signing and verification agree on it byte for byte however
+    /// the message was carried in between:
+    ///
+    /// * line endings are normalized to `\n`, so CRLF works too
+    /// * spaces and tabs at the end of a line don't count
+    /// * neither do line breaks at the end of the message
+    ///
+    /// Anything else, leading whitespace included, is signed as-is.
+    pub fn canonicalize(text: &str) -> String {
+        text.split('\n')
+            .map(Self::trim_line)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_end_matches('\n')
+            .to_owned()
+    }
+
+    /// A line without the carriage return, spaces and tabs at its end.
+    /// Only these, so that other whitespace, eg non-breaking spaces,
+    /// survives both signing and parsing.
+    fn trim_line(line: &str) -> &str {
+        line.trim_end_matches(['\r', ' ', '\t'])
+    }
+
+    /// Derives a `KdtSignedMessage` from an armored signed message. The
+    /// message runs from the line after the header up to the line that
+    /// begins the signature block, with dash-escaped lines unescaped.
+    /// Messages signed before signature blocks were containers were
+    /// neither escaped nor canonicalized, so theirs is kept as it was.
+    pub fn parse(full_signature: &str) -> Result<Self, Box<dyn Error>> {
+        let signature_header = format!("-----BEGIN {}-----", SIGNATURE_ARMOR_LABEL);
+        let signature_footer = format!("-----END {}-----", SIGNATURE_ARMOR_LABEL);
+        let (header, body) = full_signature
+            .split_once('\n')
+            .ok_or(KdtErr::BadSignedMessage)?;
+        if Self::trim_line(header) != SIGNED_MESSAGE_HEADER {
+            return Err(Box::new(KdtErr::BadSignedMessage));
         }
+        let mut lines = body.split_inclusive('\n');
+        let mut message_len = 0;
+        loop {
+            match lines.next() {
+                Some(line) if Self::trim_line(line.trim_end_matches('\n')) == signature_header => {
+                    break
+                }
+                Some(line) => message_len += line.len(),
+                None => return Err(Box::new(KdtErr::BadSignedMessage)),
+            }
+        }
+        let message = &body[..message_len];
+        let signature_block = lines
+            .map(|line| Self::trim_line(line.trim_end_matches('\n')))
+            .take_while(|line| *line != signature_footer)
+            .collect::<String>();
+        let signature_bytes = Base64::try_decode_string(Armor::unwrap(&signature_block))?;
+        // Signature blocks made before containers existed are nothing but
+        // a raw Dilithium signature, over the message with only its outer
+        // whitespace trimmed.
+        if !KdtContainer::is_container(&signature_bytes) {
+            return Ok(Self {
+                message: message.trim().to_owned(),
+                ..Self::new("", signature_bytes)
+            });
+        }
+        let text = message
+            .lines()
+            .map(Self::trim_line)
+            .map(|line| line.strip_prefix("- ").unwrap_or(line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let container = KdtContainer::from_bytes(&signature_bytes, KdtContainerKind::Signature)?;
+        let records = &container.records;
+        let algorithm = KdtSignatureAlgorithm::from_id(records.require_u8(SIGNATURE_ALGORITHM)?)
+            .ok_or(KdtErr::UnknownAlgorithm)?;
+
+        Ok(Self {
+            algorithm,
+            signature: records.require(SIGNATURE_VALUE)?.to_vec(),
+            hybrid_signature: records
+                .get(SIGNATURE_HYBRID_VALUE)
+                .unwrap_or_default()
+                .to_vec(),
+            metadata: KdtSignatureMetadata::from_records(records)?,
+            cosignatures: KdtDetachedSignature::cosignatures_from_records(records)?,
+            ..Self::new(text, Vec::new())
+        })
+    }
+
+    /// The container holding the signature block.
+    pub fn to_container(&self) -> KdtContainer {
+        let mut records = KdtRecords::new();
+        records.push(SIGNATURE_ALGORITHM, [self.algorithm.id()]);
+        records.push(SIGNATURE_VALUE, self.signature.clone());
+        if self.algorithm.is_hybrid() {
+            records.push(SIGNATURE_HYBRID_VALUE, self.hybrid_signature.clone());
+        }
+        if let Some(metadata) = &self.metadata {
+            records.0.extend(metadata.to_records().0);
+        }
+        KdtDetachedSignature::push_cosignatures(&mut records, &self.cosignatures);
+        KdtContainer::new(KdtContainerKind::Signature, records)
     }
 }
 
 impl KdtMessage for KdtSignedMessage {
+    /// Derives a `KdtSignedMessage` from an armored signed message.
+    /// Doesn't validate input, so it *will* panic if you pass invalid
+    /// inputs. See `KdtSignedMessage::parse` for a version that doesn't.
     fn from_str<S: fmt::Display>(full_signature: S) -> Self {
-        let parts: Vec<String> = full_signature
-            .to_string()
-            .chars()
-            .skip(35)
-            .take(full_signature.to_string().len() - 35 - 27)
-            .collect::<String>()
-            .split("-----BEGIN KDT SIGNATURE-----")
-            .map(|x| x.trim())
-            .map(String::from)
-            .collect();
-        let text = parts.first().unwrap().trim().to_owned();
-        let signature_str = parts.last().unwrap().replace('\n', "");
-
-        Self {
-            message: text,
-            signature: Base64::decode_string(signature_str),
-        }
+        Self::parse(&full_signature.to_string()).unwrap()
     }
 }
 
 impl fmt::Display for KdtSignedMessage {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let fmt_sig = Base64::encode_bytes(&self.signature);
-        let sig = format!("-----BEGIN KDT SIGNED MESSAGE-----\n{}\n\n-----BEGIN KDT SIGNATURE-----\n{}\n-----END KDT SIGNATURE-----", self.message, fmt_sig.chars()
-        .enumerate()
-        .flat_map(|(i, c)| {
-            if (i + 1) % 64 == 0 {
-                vec![c, '\n']
-            } else {
-                vec![c]
-            }
-        })
-        .collect::<String>());
-        write!(f, "{}", sig)
+        let signature = Base64::encode_bytes(&self.to_container().to_bytes());
+        // Lines starting with a dash are escaped, so none of them can be
+        // mistaken for the armor around the signature.
+        let message = self
+            .message
+            .split('\n')
+            .map(|line| match line.starts_with('-') {
+                true => format!("- {}", line),
+                false => line.to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        write!(
+            f,
+            "{}\n{}\n\n{}",
+            SIGNED_MESSAGE_HEADER,
+            message,
+            Armor::wrap(SIGNATURE_ARMOR_LABEL, &signature)
+        )
     }
 }
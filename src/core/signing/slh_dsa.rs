@@ -0,0 +1,146 @@
+// -- imports --
+use crate::core::*;
+use fips205::traits::{
+    SerDes,
+    Signer,
+    Verifier,
+};
+use std::fmt;
+
+// -- slh-dsa parameter sets --
+/// SLH-DSA (FIPS 205) parameter sets a key set can sign with instead of
+/// Dilithium. Their security only rests on the hash function, SHAKE256,
+/// which makes them the conservative pick for signatures that have to
+/// hold up for decades, at the cost of far bigger and slower signatures.
+/// The "s" sets have the smaller signatures, the "f" ones sign faster.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KdtSlhDsaLevel {
+    /// SLH-DSA-SHAKE-128s, NIST security level 1.
+    Shake128s,
+
+    /// SLH-DSA-SHAKE-128f, NIST security level 1.
+    Shake128f,
+
+    /// SLH-DSA-SHAKE-192s, NIST security level 3.
+    Shake192s,
+
+    /// SLH-DSA-SHAKE-192f, NIST security level 3.
+    Shake192f,
+
+    /// SLH-DSA-SHAKE-256s, NIST security level 5, and what SLH-DSA key
+    /// sets get unless asked otherwise.
+    Shake256s,
+
+    /// SLH-DSA-SHAKE-256f, NIST security level 5.
+    Shake256f,
+}
+
+/// Evaluates `$body` with `$params` standing for the module of the
+/// parameter set `$level` picks, as the SLH-DSA library fixes them at
+/// compile time.
+macro_rules! with_params {
+    ($level:expr, $params:ident => $body:expr) => {
+        match $level {
+            KdtSlhDsaLevel::Shake128s => {
+                use fips205::slh_dsa_shake_128s as $params;
+                $body
+            }
+            KdtSlhDsaLevel::Shake128f => {
+                use fips205::slh_dsa_shake_128f as $params;
+                $body
+            }
+            KdtSlhDsaLevel::Shake192s => {
+                use fips205::slh_dsa_shake_192s as $params;
+                $body
+            }
+            KdtSlhDsaLevel::Shake192f => {
+                use fips205::slh_dsa_shake_192f as $params;
+                $body
+            }
+            KdtSlhDsaLevel::Shake256s => {
+                use fips205::slh_dsa_shake_256s as $params;
+                $body
+            }
+            KdtSlhDsaLevel::Shake256f => {
+                use fips205::slh_dsa_shake_256f as $params;
+                $body
+            }
+        }
+    };
+}
+
+impl KdtSlhDsaLevel {
+    /// The level SLH-DSA key sets are generated with, unless asked
+    /// otherwise.
+    pub const RECOMMENDED: Self = Self::Shake256s;
+
+    /// Restores the level from its name, which is either the short name
+    /// of the parameter set (eg "256s") or its full name (eg
+    /// "slh-dsa-shake-256s").
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim_start_matches("slh-dsa-shake-") {
+            "128s" => Some(Self::Shake128s),
+            "128f" => Some(Self::Shake128f),
+            "192s" => Some(Self::Shake192s),
+            "192f" => Some(Self::Shake192f),
+            "256s" => Some(Self::Shake256s),
+            "256f" => Some(Self::Shake256f),
+            _ => None,
+        }
+    }
+
+    /// Generates a key pair, as its public and private key bytes.
+    pub fn keypair(&self) -> (Vec<u8>, Vec<u8>) {
+        with_params!(self, params => {
+            let (public, private) = params::try_keygen_with_rng(&mut rand::thread_rng())
+                .expect("The system RNG failed");
+            (public.into_bytes().to_vec(), private.into_bytes().to_vec())
+        })
+    }
+
+    /// Signs `message` with the given private key, bound to the context
+    /// string `ctx` of at most `MAX_CONTEXT_SIZE` bytes. Hedged
+    /// signatures mix fresh randomness in, otherwise the same message
+    /// always gets the same signature.
+    pub fn sign(&self, message: &[u8], ctx: &[u8], private_key: &[u8], hedged: bool) -> Vec<u8> {
+        with_params!(self, params => {
+            let private_key = private_key
+                .try_into()
+                .ok()
+                .and_then(|bytes| params::PrivateKey::try_from_bytes(bytes).ok())
+                .expect("SLH-DSA keys don't match their level");
+            private_key
+                .try_sign_with_rng(&mut rand::thread_rng(), message, ctx, hedged)
+                .expect("The system RNG failed, or the context string is too long")
+                .to_vec()
+        })
+    }
+
+    /// Checks a signature over `message`, bound to the context string
+    /// `ctx`, against the given public key.
+    pub fn verify(&self, signature: &[u8], message: &[u8], ctx: &[u8], public_key: &[u8]) -> bool {
+        with_params!(self, params => {
+            let public_key = match public_key.try_into() {
+                Ok(bytes) => params::PublicKey::try_from_bytes(bytes),
+                Err(_) => return false,
+            };
+            match (public_key, signature.try_into()) {
+                (Ok(public_key), Ok(signature)) => public_key.verify(message, signature, ctx),
+                _ => false,
+            }
+        })
+    }
+}
+
+impl fmt::Display for KdtSlhDsaLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shake128s => write!(f, "slh-dsa-shake-128s"),
+            Self::Shake128f => write!(f, "slh-dsa-shake-128f"),
+            Self::Shake192s => write!(f, "slh-dsa-shake-192s"),
+            Self::Shake192f => write!(f, "slh-dsa-shake-192f"),
+            Self::Shake256s => write!(f, "slh-dsa-shake-256s"),
+            Self::Shake256f => write!(f, "slh-dsa-shake-256f"),
+        }
+    }
+}
@@ -9,10 +9,14 @@ mod core;
 use crate::arguments::*;
 use crate::core::*;
 use clap::Parser;
+use std::io::{
+    Read,
+    Write,
+};
 
 fn main() {
     let args = Args::parse();
-    let logger = Logger::new(true);
+    let logger = Logger::new(!args.writes_raw_stdout());
     if let Err(e) = args.fail_if_invalid() {
         logger.fatal(e);
     }
@@ -25,6 +29,20 @@ fn main() {
     // Note to self - figure out how to make this
     // part of the code less ugly.
     {
+        // Signing and encrypting at once is handled together with
+        // `--encrypt`, so `--sign` needs to know to stay out of it.
+        let sign_and_encrypt = args.is_sign_and_encrypt();
+        let encrypt_options = args.encrypt_options();
+        let sign_options = args.sign_options();
+        let key_options = args.key_options();
+        let detached_signature_output = args.detached_signature_output();
+        let detached_signature_input = args.detached_signature_input();
+        let policy = match args.policy.as_ref().map(KdtSignPolicy::from_file) {
+            Some(Ok(policy)) => Some(policy),
+            Some(Err(e)) => logger.fatal(e),
+            None => None,
+        };
+
         // options
         // `--export-pubkey`
         if let Some(pubkey_id) = args.export_pubkey {
@@ -52,41 +70,238 @@ fn main() {
             kdt.del_ownedkey(privkey_id);
             logger.success("Succesfully removed the owned key set!");
         }
+        // `-s | --sign` + `-e | --encrypt`
+        if let (Some(privkey_id), Some(ids)) = (args.sign.clone(), args.encrypt.clone()) {
+            logger.info("Type your message below (CTRL-D to finish):");
+            let message = logger.raw_input();
+            match kdt.sign_and_encrypt(privkey_id, ids, message, &sign_options, encrypt_options) {
+                Ok(encrypted) => {
+                    logger.info("Signed and encrypted message:");
+                    println!("{}", encrypted);
+                }
+                Err(e) => logger.fatal(e),
+            }
+        }
         // `-e | --encrypt`
-        if let Some(id) = args.encrypt {
+        else if let Some(ids) = args.encrypt.clone().filter(|_| args.stream) {
+            let result = open_input(&args.input).and_then(|mut input| {
+                let mut output = open_output(&args.output)?;
+                kdt.encrypt_stream(ids, &mut input, &mut output, encrypt_options)
+            });
+            match result {
+                Ok(()) => logger.success("Successfully encrypted the stream!"),
+                Err(e) => logger.fatal(e),
+            }
+        } else if let Some(ids) = args.encrypt.clone().filter(|_| args.binary) {
+            let result = open_input(&args.input).and_then(|mut input| {
+                let mut bytes = Vec::new();
+                input.read_to_end(&mut bytes)?;
+                let encrypted = kdt.encrypt_bytes(ids, &bytes, encrypt_options)?;
+                let mut output = open_output(&args.output)?;
+                writeln!(output, "{}", encrypted)?;
+                Ok(output.flush()?)
+            });
+            match result {
+                Ok(()) => logger.success("Successfully encrypted the input!"),
+                Err(e) => logger.fatal(e),
+            }
+        } else if let Some(ids) = args.encrypt {
             logger.info("Type your message below (CTRL-D to finish):");
             let message = logger.input();
-            logger.info("Encrypted message:");
-            println!("{}", kdt.encrypt(id, message));
+            match kdt.encrypt(ids, message, encrypt_options) {
+                Ok(encrypted) => {
+                    logger.info("Encrypted message:");
+                    println!("{}", encrypted);
+                }
+                Err(e) => logger.fatal(e),
+            }
+        }
+        // `-c | --symmetric`
+        if args.symmetric {
+            let bytes = if args.binary {
+                let mut bytes = Vec::new();
+                if let Err(e) = open_input(&args.input).and_then(|mut input| {
+                    input.read_to_end(&mut bytes)?;
+                    Ok(())
+                }) {
+                    logger.fatal(e);
+                }
+                bytes
+            } else {
+                logger.info("Type your message below (CTRL-D to finish):");
+                logger.input().into_bytes()
+            };
+            let passphrase = logger.passphrase("Enter a passphrase:");
+            if passphrase.is_empty() {
+                logger.fatal(KdtErr::EmptyPassphrase);
+            }
+            if passphrase != logger.passphrase("Repeat the passphrase:") {
+                logger.fatal(KdtErr::PassphraseMismatch);
+            }
+            let result = kdt
+                .encrypt_with_passphrase(&bytes, passphrase, encrypt_options.aead)
+                .and_then(|encrypted| {
+                    if args.binary {
+                        let mut output = open_output(&args.output)?;
+                        writeln!(output, "{}", encrypted)?;
+                        output.flush()?;
+                    } else {
+                        logger.info("Encrypted message:");
+                        println!("{}", encrypted);
+                    }
+                    Ok(())
+                });
+            if let Err(e) = result {
+                logger.fatal(e);
+            }
         }
         // `-d | --decrypt`
-        if let Some(privkey_id) = args.decrypt {
+        if let Some(privkey_id) = args.decrypt.clone().filter(|_| args.stream) {
+            let result = open_input(&args.input).and_then(|mut input| {
+                let mut output = open_output(&args.output)?;
+                kdt.decrypt_stream(privkey_id, &mut input, &mut output)
+            });
+            match result {
+                Ok(()) => logger.success("Successfully decrypted the stream!"),
+                Err(e) => logger.fatal(e),
+            }
+        } else if let Some(privkey_id) = args.decrypt.clone().filter(|_| args.binary) {
+            let result = open_input(&args.input).and_then(|mut input| {
+                let mut message = String::new();
+                input.read_to_string(&mut message)?;
+                let message = message.trim().to_string();
+                let decrypted = if KdtEncryptedMessage::parse(&message)?.is_passphrase_protected() {
+                    kdt.decrypt_with_passphrase(message, logger.passphrase("Passphrase:"))?
+                } else {
+                    kdt.decrypt_bytes(privkey_id, message)?
+                };
+                let mut output = open_output(&args.output)?;
+                output.write_all(&decrypted)?;
+                Ok(output.flush()?)
+            });
+            match result {
+                Ok(()) => logger.success("Successfully decrypted the input!"),
+                Err(e) => logger.fatal(e),
+            }
+        } else if let Some(privkey_id) = args.decrypt {
             logger.info("Input the encrypted message below (CTRL-D to finish):");
             let message = logger.input();
-            logger.info("Decrypted message:");
-            println!("{}", kdt.decrypt(privkey_id, message));
+            let is_passphrase_protected = match KdtEncryptedMessage::parse(&message) {
+                Ok(parsed) => parsed.is_passphrase_protected(),
+                Err(e) => logger.fatal(e),
+            };
+            if is_passphrase_protected {
+                let passphrase = logger.passphrase("Passphrase:");
+                match kdt.decrypt_with_passphrase(message, passphrase) {
+                    Ok(decrypted) => {
+                        logger.info("Decrypted message:");
+                        println!("{}", String::from_utf8_lossy(&decrypted));
+                    }
+                    Err(e) => logger.fatal(e),
+                }
+            } else {
+                let (decrypted, signer) = match kdt.decrypt_and_verify(privkey_id, message) {
+                    Ok(result) => result,
+                    Err(e) => logger.fatal(e),
+                };
+                match signer {
+                    KdtSignerStatus::Unsigned => {}
+                    KdtSignerStatus::Valid(key) => logger.success(format!(
+                        "The message was signed by {} (public key id {}), and the signature is valid!",
+                        key.owner, key.id
+                    )),
+                    KdtSignerStatus::Unverified => logger.warn(
+                        "The message is signed, but the signature isn't valid for any of your public keys!",
+                    ),
+                }
+                logger.info("Decrypted message:");
+                println!("{}", decrypted);
+            }
+        }
+        // `-s | --sign` + `--detach`
+        if let Some(privkey_id) = args.sign.clone().filter(|_| args.detach) {
+            let destination = detached_signature_output;
+            let result = open_input(&args.input).and_then(|mut input| {
+                let signature = match (args.cosign, &detached_signature_input) {
+                    (true, Some(path)) => {
+                        let signature = std::fs::read_to_string(path)?;
+                        kdt.cosign_detached(privkey_id, &mut input, &signature, &sign_options)?
+                    }
+                    (true, None) => return Err(Box::new(KdtErr::NoDetachedSignature).into()),
+                    (false, _) => kdt.sign_detached(privkey_id, &mut input, &sign_options)?,
+                };
+                if destination.is_none() {
+                    logger.info("Detached signature:");
+                }
+                let mut output = open_output(&destination)?;
+                writeln!(output, "{}", signature)?;
+                Ok(output.flush()?)
+            });
+            match (result, destination) {
+                (Ok(()), Some(path)) => logger.success(format!(
+                    "Successfully wrote the detached signature to {}!",
+                    path.display()
+                )),
+                (Ok(()), None) => {}
+                (Err(e), _) => logger.fatal(e),
+            }
+        }
+        // `-s | --sign` + `--cosign`
+        else if let Some(privkey_id) = args.sign.clone().filter(|_| args.cosign) {
+            logger.info(
+                "Input the signed message to add your signature to below (CTRL-D to finish):",
+            );
+            let signed = logger.input();
+            match kdt.cosign(privkey_id, signed, &sign_options) {
+                Ok(cosigned) => {
+                    logger.info("Co-signed message:");
+                    println!("{}", cosigned);
+                }
+                Err(e) => logger.fatal(e),
+            }
         }
         // `-s | --sign`
-        if let Some(privkey_id) = args.sign {
+        else if let Some(privkey_id) = args.sign.filter(|_| !sign_and_encrypt) {
             logger.info("Input the message to sign below (CTRL-D to finish):");
-            let message = logger.input();
+            let message = logger.raw_input();
             logger.info("Signed message:");
-            println!("{}", kdt.sign(privkey_id, message).unwrap());
+            println!(
+                "{}",
+                kdt.sign(privkey_id, message, &sign_options)
+                    .unwrap()
+            );
+        }
+        // `-v | --verify` + `--detach`
+        if let Some(pubkey_id) = args.verify.clone().filter(|_| args.detach) {
+            let result = detached_signature_input
+                .ok_or_else(|| Box::new(KdtErr::NoDetachedSignature).into())
+                .and_then(|path| Ok(std::fs::read_to_string(path)?))
+                .and_then(|signature| {
+                    let mut input = open_input(&args.input)?;
+                    kdt.verify_detached(pubkey_id, &mut input, &signature)
+                });
+            match result {
+                Ok(checks) => {
+                    print_verification(&logger, &checks);
+                    if let Some(policy) = &policy {
+                        print_policy_verdict(&logger, &kdt, policy.evaluate(&checks));
+                    }
+                }
+                Err(e) => logger.fatal(e),
+            }
         }
         // `-v | --verify`
-        if let Some(pubkey_id) = args.verify {
+        else if let Some(pubkey_id) = args.verify {
             logger.info("Input the signed message below (CTRL-D to finish):");
             let message = logger.input();
-            let is_valid = kdt.verify(pubkey_id, message);
-            match is_valid {
-                Some(v) => {
-                    if v {
-                        logger.success("The provided message is valid!");
-                    } else {
-                        logger.warn("The given message is not valid!");
+            match kdt.verify(pubkey_id, message) {
+                Ok(checks) => {
+                    print_verification(&logger, &checks);
+                    if let Some(policy) = &policy {
+                        print_policy_verdict(&logger, &kdt, policy.evaluate(&checks));
                     }
                 }
-                None => logger.fatal("There was an error parsing your input!"),
+                Err(e) => logger.fatal(e),
             }
         }
 
@@ -110,11 +325,14 @@ fn main() {
             logger.info("Type your name below. Note that this will be visible to everyone who imports your public key.");
             let name = logger.input();
             logger.info("Generating owned key set...");
-            let privkey_id = kdt.gen_key(if name.is_empty() {
-                String::from("No name was provided by the key owner!")
-            } else {
-                name
-            });
+            let privkey_id = kdt.gen_key(
+                if name.is_empty() {
+                    String::from("No name was provided by the key owner!")
+                } else {
+                    name
+                },
+                key_options,
+            );
             logger.success(format!(
                 "Successfully created owned key with private id {}!",
                 privkey_id
@@ -154,3 +372,67 @@ fn main() {
         logger.fatal(e);
     }
 }
+
+/// Reports who made each signature on the input, along with its signed
+/// metadata, or that the signer is unknown.
+fn print_verification(logger: &Logger, checks: &[KdtSignatureCheck]) {
+    let count = checks.len();
+    for (i, check) in checks.iter().enumerate() {
+        if count > 1 {
+            logger.info(format!("Signature {} of {}:", i + 1, count));
+        }
+        print_signature_check(logger, check);
+    }
+}
+
+/// Reports who made a signature, along with its signed metadata, or that
+/// the signer is unknown.
+fn print_signature_check(logger: &Logger, check: &KdtSignatureCheck) {
+    match (&check.status, &check.metadata) {
+        (KdtSignerStatus::Valid(key), metadata) => {
+            logger.success(format!(
+                "The signature is valid, and was made by {} (public key id {})!",
+                key.owner, key.id
+            ));
+            match metadata {
+                Some(metadata) => println!("{}", metadata),
+                None => logger.info("The signature predates signed metadata, so it doesn't say when it was made."),
+            }
+        }
+        (_, Some(metadata)) => logger.warn(format!(
+            "Unknown signer! The signature claims to be made by public key id {}, but isn't valid for any of the public keys it was checked against.",
+            metadata.signer_id
+        )),
+        (_, None) => logger.warn(
+            "Unknown signer! The signature isn't valid for any of the public keys it was checked against.",
+        ),
+    }
+}
+
+/// Reports whether enough of a signing policy's signers signed, and which
+/// ones are missing. Exits with status 1 unless the policy is satisfied,
+/// so scripts can rely on `--policy`.
+fn print_policy_verdict(logger: &Logger, kdt: &CoreKdtHandler, verdict: KdtPolicyVerdict) {
+    let summary = format!(
+        "{} of {} signers signed, and {} are required",
+        verdict.signed.len(),
+        verdict.signed.len() + verdict.missing.len(),
+        verdict.threshold
+    );
+    let satisfied = verdict.is_satisfied();
+    if satisfied {
+        logger.success(format!("The signing policy is satisfied: {}!", summary));
+    }
+    for id in verdict.missing {
+        match kdt.pubkey_db.get_by_id(id.clone()) {
+            Ok(key) => println!("Missing: {} (public key id {})", key.owner, id),
+            Err(_) => println!(
+                "Missing: public key id {}, which isn't in your public key database",
+                id
+            ),
+        }
+    }
+    if !satisfied {
+        logger.fatal(format!("The signing policy isn't satisfied: {}!", summary));
+    }
+}
@@ -1,58 +1,1433 @@
 // -- imports --
 use crate::core::*;
-use pqc_dilithium::Keypair;
+use aes_gcm::{
+    aead::{
+        Aead,
+        AeadCore,
+        KeyInit,
+        OsRng,
+    },
+    Aes256Gcm,
+    Key,
+};
+use argon2::{
+    Algorithm,
+    Argon2,
+    Params,
+    Version,
+};
+use pqc_dilithium::{
+    DilithiumMode,
+    Keypair,
+    PreHash,
+};
+use sha2::{
+    Digest,
+    Sha256,
+};
 
 // -- tests --
 #[test]
 fn kyber_with_correct_privkey() {
     let secret_message = String::from("This is a test message");
 
-    let (pubkey, privkey) = {
-        let keyset = OwnedKeySet::generate("Test Key".into());
-
-        (
-            keyset.pubkey_pair.crypto_key,
-            keyset.privkey_pair.crypto_key,
-        )
-    };
-    let encrypted = KdtCryptoHandler::encrypt_text(secret_message.clone(), pubkey).unwrap();
-    let decrypted = KdtCryptoHandler::decrypt_msg(encrypted, privkey);
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    let encrypted =
+        KdtCryptoHandler::encrypt_text(secret_message.clone(), keyset.pubkey_pair.clone()).unwrap();
+    let decrypted = KdtCryptoHandler::decrypt_msg(encrypted, &keyset).unwrap();
 
     assert_eq!(decrypted, secret_message);
 }
 
 #[test]
-#[should_panic]
 fn kyber_with_incorrect_privkey() {
     let secret_message = String::from("This is a test message");
-    let (pubkey, privkey) = {
-        let keyset_1 = OwnedKeySet::generate("Test Key".into());
-        let keyset_2 = OwnedKeySet::generate("Test Key".into());
-
-        (
-            keyset_1.pubkey_pair.crypto_key,
-            keyset_2.privkey_pair.crypto_key,
-        )
-    };
-    let encrypted = KdtCryptoHandler::encrypt_text(secret_message, pubkey).unwrap();
-    // should panic here because the key is wrong
-    KdtCryptoHandler::decrypt_msg(encrypted, privkey);
+    let keyset_1 = OwnedKeySet::generate("Test Key".into(), false);
+    let keyset_2 = OwnedKeySet::generate("Test Key".into(), false);
+    let encrypted = KdtCryptoHandler::encrypt_text(secret_message, keyset_1.pubkey_pair).unwrap();
+    // should fail here because the key is wrong
+    let err = KdtCryptoHandler::decrypt_msg(encrypted, &keyset_2)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.downcast_ref::<KdtErr>(),
+        Some(KdtErr::NotForThisKey)
+    ));
 }
 
 #[test]
 fn dilithium_with_correct_pubkey() {
     let text = String::from("This is a test message");
-    let keypair = {
-        let keyset = OwnedKeySet::generate("Test Key".into());
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+
+    let signed_text = KdtSignageHandler::sign_text(text, &keyset);
+    let msg = KdtSignedMessage::from_str(signed_text);
+    let signature_validity = KdtSignageHandler::verify(&msg, &keyset.pubkey_pair);
+    assert!(signature_validity);
+}
+
+#[test]
+fn kyber_with_multiple_recipients() {
+    let secret_message = String::from("This is a test message");
+    let keyset_1 = OwnedKeySet::generate("Test Key".into(), false);
+    let keyset_2 = OwnedKeySet::generate("Test Key".into(), false);
+
+    let encrypted = KdtCryptoHandler::encrypt_text_multi(
+        secret_message.clone(),
+        vec![keyset_1.pubkey_pair.clone(), keyset_2.pubkey_pair.clone()],
+    )
+    .unwrap();
+    // Round-trip through the armored form so both recipients decrypt the
+    // exact same blob.
+    let armored = encrypted.to_string();
+    for keyset in [keyset_1, keyset_2] {
+        let message = KdtEncryptedMessage::from_str(armored.clone());
+        assert_eq!(
+            KdtCryptoHandler::decrypt_msg(message, &keyset).unwrap(),
+            secret_message
+        );
+    }
+}
+
+#[test]
+fn kyber_stream_round_trip() {
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    // Spans several chunks, with a partial one at the end.
+    let data: Vec<u8> = (0..3 * STREAM_CHUNK_SIZE + 1234)
+        .map(|i| (i % 251) as u8)
+        .collect();
+
+    let mut encrypted = Vec::new();
+    KdtCryptoHandler::encrypt_stream(
+        &mut data.as_slice(),
+        &mut encrypted,
+        vec![keyset.pubkey_pair.clone()],
+    )
+    .unwrap();
+    let mut decrypted = Vec::new();
+    KdtCryptoHandler::decrypt_stream(&mut encrypted.as_slice(), &mut decrypted, &keyset).unwrap();
+
+    assert_eq!(decrypted, data);
+}
+
+#[test]
+fn kyber_stream_rejects_truncation() {
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    let data = vec![7u8; 2 * STREAM_CHUNK_SIZE + 10];
+
+    let mut encrypted = Vec::new();
+    KdtCryptoHandler::encrypt_stream(
+        &mut data.as_slice(),
+        &mut encrypted,
+        vec![keyset.pubkey_pair.clone()],
+    )
+    .unwrap();
+    // Drop the final chunk, so the stream ends cleanly on a chunk boundary.
+    encrypted.truncate(encrypted.len() - (10 + AEAD_TAG_SIZE));
+
+    let result =
+        KdtCryptoHandler::decrypt_stream(&mut encrypted.as_slice(), &mut Vec::new(), &keyset);
+    assert!(result.is_err());
+}
+
+#[test]
+fn kyber_bytes_round_trip() {
+    // Not valid UTF-8, so a lossy conversion would mangle it.
+    let data = vec![0xff, 0x00, 0xfe, 0x80, 0x0a, 0x0d, 0xc3];
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+
+    let encrypted =
+        KdtCryptoHandler::encrypt_bytes(&data, vec![keyset.pubkey_pair.clone()]).unwrap();
+    let message = KdtEncryptedMessage::from_str(encrypted.to_string());
+    let decrypted = KdtCryptoHandler::decrypt_bytes(message, &keyset);
+
+    assert_eq!(decrypted.unwrap(), data);
+}
+
+#[test]
+fn sign_then_encrypt_reports_signer() {
+    let text = String::from("This is a test message");
+    let signer = OwnedKeySet::generate("Signer".into(), false);
+    let recipient = OwnedKeySet::generate("Recipient".into(), false);
+    let stranger = OwnedKeySet::generate("Stranger".into(), false);
+
+    let signed = KdtSignageHandler::sign_text(text.clone(), &signer);
+    let encrypted = KdtCryptoHandler::encrypt_text(signed, recipient.pubkey_pair.clone()).unwrap();
+    let decrypted = KdtCryptoHandler::decrypt_msg(encrypted, &recipient).unwrap();
+    assert!(KdtSignedMessage::is_signed_message(&decrypted));
+
+    let msg = KdtSignedMessage::from_str(decrypted);
+    assert_eq!(msg.message, text);
+    let known_keys = [stranger.pubkey_pair, signer.pubkey_pair.clone()];
+    let found = KdtSignageHandler::find_signer(&msg, &known_keys).unwrap();
+    assert_eq!(found.id, signer.pubkey_pair.id);
+    assert!(KdtSignageHandler::find_signer(&msg, &known_keys[..1]).is_none());
+}
+
+#[test]
+fn malformed_signed_plaintext_is_unverified() {
+    let recipient = OwnedKeySet::generate("Recipient".into(), false);
+    let text = format!("{}\nhello", SIGNED_MESSAGE_HEADER);
+    let encrypted =
+        KdtCryptoHandler::encrypt_text(text.clone(), recipient.pubkey_pair.clone()).unwrap();
+    let decrypted = KdtCryptoHandler::decrypt_msg(encrypted, &recipient).unwrap();
+
+    let (plaintext, status) =
+        KdtSignageHandler::check_plaintext(decrypted, &[recipient.pubkey_pair]);
+    assert_eq!(plaintext, text);
+    assert!(matches!(status, KdtSignerStatus::Unverified));
+
+    let (plaintext, status) = KdtSignageHandler::check_plaintext("hello".into(), &[]);
+    assert_eq!(plaintext, "hello");
+    assert!(matches!(status, KdtSignerStatus::Unsigned));
+}
+
+#[test]
+fn passphrase_round_trip() {
+    let data = b"This is a test message".to_vec();
+    // Cheap parameters, so the test doesn't spend seconds in Argon2.
+    let params = KdtPassphraseParams::new(vec![42u8; PASSPHRASE_SALT_SIZE], 256, 1, 1);
+
+    let encrypted = KdtCryptoHandler::encrypt_bytes_with_params(
+        &data,
+        "correct horse",
+        params,
+        KdtAead::default(),
+    )
+    .unwrap();
+    let message = KdtEncryptedMessage::from_str(encrypted.to_string());
+    assert!(message.is_passphrase_protected());
+    let decrypted = KdtCryptoHandler::decrypt_bytes_with_passphrase(message, "correct horse");
+    assert_eq!(decrypted.unwrap(), data);
+
+    let message = KdtEncryptedMessage::from_str(encrypted.to_string());
+    assert!(KdtCryptoHandler::decrypt_bytes_with_passphrase(message, "battery staple").is_err());
+}
+
+#[test]
+fn oversized_passphrase_params_are_refused() {
+    let params = KdtPassphraseParams::new(vec![42u8; PASSPHRASE_SALT_SIZE], 256, 1, 1);
+    let encrypted = KdtCryptoHandler::encrypt_bytes_with_params(
+        b"hello",
+        "correct horse",
+        params,
+        KdtAead::default(),
+    )
+    .unwrap();
+
+    // A forged message asking for 256 GiB is refused before Argon2 gets
+    // to allocate any of it.
+    let mut message = KdtEncryptedMessage::from_str(encrypted.to_string());
+    message.passphrase.as_mut().unwrap().m_cost = 256 * 1024 * 1024;
+    let message = KdtEncryptedMessage::from_str(message.to_string());
+    let err = KdtCryptoHandler::decrypt_bytes_with_passphrase(message, "correct horse");
+    assert!(matches!(
+        err.unwrap_err().downcast_ref::<KdtErr>(),
+        Some(KdtErr::BadPassphraseParams)
+    ));
+}
+
+#[test]
+fn hybrid_kyber_x25519_round_trip() {
+    let secret_message = String::from("This is a test message");
+    let hybrid = OwnedKeySet::generate("Hybrid Key".into(), true);
+    let plain = OwnedKeySet::generate("Plain Key".into(), false);
+    // The X25519 key has to survive exporting and importing the key.
+    let imported = PubKeyPair::from_str(hybrid.pubkey_pair.to_string()).init();
+    assert!(imported.is_hybrid());
+    assert_eq!(imported.id, hybrid.pubkey_pair.id);
+
+    let encrypted = KdtCryptoHandler::encrypt_text_multi(
+        secret_message.clone(),
+        vec![imported, plain.pubkey_pair.clone()],
+    )
+    .unwrap();
+    assert!(encrypted.recipients[0].is_hybrid());
+    assert!(!encrypted.recipients[1].is_hybrid());
+    let armored = encrypted.to_string();
+    for keyset in [&hybrid, &plain] {
+        let message = KdtEncryptedMessage::from_str(armored.clone());
+        assert_eq!(
+            KdtCryptoHandler::decrypt_msg(message, keyset).unwrap(),
+            secret_message
+        );
+    }
+}
+
+#[test]
+fn hybrid_needs_the_x25519_secret() {
+    let data = b"This is a test message".to_vec();
+    let keyset = OwnedKeySet::generate("Hybrid Key".into(), true);
+    let encrypted =
+        KdtCryptoHandler::encrypt_bytes(&data, vec![keyset.pubkey_pair.clone()]).unwrap();
+
+    // Knowing the Kyber private key alone isn't enough.
+    let mut kyber_only = keyset.clone();
+    kyber_only.privkey_pair.hybrid_crypto_key = Some(vec![1u8; 32]);
+    let message = KdtEncryptedMessage::from_str(encrypted.to_string());
+    assert!(KdtCryptoHandler::decrypt_bytes(message, &kyber_only).is_err());
+}
+
+#[test]
+fn hybrid_dilithium_ed25519_signatures() {
+    let text = String::from("This is a test message");
+    let hybrid = OwnedKeySet::generate("Hybrid Key".into(), true);
+
+    let signed = KdtSignageHandler::sign_text(text.clone(), &hybrid);
+    let msg = KdtSignedMessage::from_str(signed);
+    assert_eq!(
+        msg.algorithm,
+        KdtSignatureAlgorithm::DilithiumEd25519(KdtDilithiumLevel::MlDsa65)
+    );
+    assert_eq!(msg.message, text);
+    assert!(KdtSignageHandler::verify(&msg, &hybrid.pubkey_pair));
 
-        Keypair::restore_from_keys(
-            keyset.pubkey_pair.signage_key,
-            keyset.privkey_pair.signage_key,
+    // A broken Ed25519 half invalidates the whole signature.
+    let mut tampered = KdtSignedMessage::from_str(msg.to_string());
+    tampered.hybrid_signature[0] ^= 1;
+    assert!(!KdtSignageHandler::verify(&tampered, &hybrid.pubkey_pair));
+
+    // So does stripping it, leaving only the Dilithium signature.
+    let stripped = KdtSignedMessage::new(msg.message.clone(), msg.signature.clone());
+    assert!(!KdtSignageHandler::verify(&stripped, &hybrid.pubkey_pair));
+}
+
+#[test]
+fn unmarked_dilithium_signatures_still_verify() {
+    // Neither dash-escaped nor canonicalized back then.
+    let text = String::from("This is a test message  \nChanges:\n- item one \n- item two");
+    let keyset = OwnedKeySet::generate_with_options(
+        "Test Key".into(),
+        KdtKeyOptions::new().with_dilithium_level(KdtDilithiumLevel::Dilithium3),
+    );
+    let keypair = Keypair::restore_from_keys(
+        DilithiumMode::Dilithium3,
+        keyset.pubkey_pair.signage_key.clone(),
+        keyset.privkey_pair.signage_key.clone(),
+    )
+    .unwrap();
+    // Signed messages from before signature blocks were containers.
+    let signature = Base64::encode_bytes(&keypair.sign(text.as_bytes()));
+    let legacy = format!(
+        "-----BEGIN KDT SIGNED MESSAGE-----\n{}\n\n-----BEGIN KDT SIGNATURE-----\n{}\n-----END KDT SIGNATURE-----",
+        text, signature
+    );
+
+    let msg = KdtSignedMessage::from_str(legacy);
+    assert_eq!(
+        msg.algorithm,
+        KdtSignatureAlgorithm::Dilithium(KdtDilithiumLevel::Dilithium3)
+    );
+    assert_eq!(msg.message, text);
+    assert!(KdtSignageHandler::verify(&msg, &keyset.pubkey_pair));
+}
+
+#[test]
+fn clearsigned_messages_survive_line_ending_changes() {
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    let signed = KdtSignageHandler::sign_text("first line  \nsecond line\t\n\n".into(), &keyset);
+
+    // Mail clients and editors love to mess with line endings and trailing whitespace.
+    let mangled = signed.replace('\n', " \r\n");
+    let msg = KdtSignedMessage::parse(&mangled).unwrap();
+    assert_eq!(msg.message, "first line\nsecond line");
+    assert!(KdtSignageHandler::verify(&msg, &keyset.pubkey_pair));
+
+    // Leading whitespace is still signed.
+    let moved = signed.replace("second line", " second line");
+    let msg = KdtSignedMessage::parse(&moved).unwrap();
+    assert!(!KdtSignageHandler::verify(&msg, &keyset.pubkey_pair));
+}
+
+#[test]
+fn clearsigned_messages_keep_other_trailing_whitespace() {
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    for text in ["foo\u{a0}\nbar", "x\u{c}", "line\u{b}\nwide\u{3000}"] {
+        let signed = KdtSignageHandler::sign_text(text.into(), &keyset);
+        let msg = KdtSignedMessage::parse(&signed).unwrap();
+        assert_eq!(msg.message, text);
+        assert!(KdtSignageHandler::verify(&msg, &keyset.pubkey_pair));
+    }
+}
+
+#[test]
+fn clearsigned_messages_dash_escape_lines() {
+    let text = "-----BEGIN KDT SIGNATURE-----\n- a list item\nnot a dash - here\n-\n\nünïcödé";
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    let signed = KdtSignageHandler::sign_text(text.into(), &keyset);
+    assert!(signed
+        .contains("\n- -----BEGIN KDT SIGNATURE-----\n- - a list item\nnot a dash - here\n- -\n"));
+
+    let msg = KdtSignedMessage::parse(&signed).unwrap();
+    assert_eq!(msg.message, text);
+    assert!(KdtSignageHandler::verify(&msg, &keyset.pubkey_pair));
+    assert_eq!(msg.to_string(), signed);
+
+    assert!(KdtSignedMessage::parse("not a signed message").is_err());
+    assert!(KdtSignedMessage::parse(&signed[..signed.len() / 2]).is_err());
+}
+
+#[test]
+fn signature_metadata_is_signed() {
+    let keyset = OwnedKeySet::generate("Test Key".into(), true);
+    let options = KdtSignOptions::new().with_notation("release", "v1.2=final");
+    let signed =
+        KdtSignageHandler::sign_text_with_options("text".into(), &keyset, &options).unwrap();
+
+    let msg = KdtSignedMessage::parse(&signed).unwrap();
+    let metadata = msg.metadata.clone().unwrap();
+    assert_eq!(metadata.signer_id, keyset.pubkey_pair.id);
+    assert_eq!(metadata.hash, KdtSignatureHash::Pure);
+    assert_eq!(
+        metadata.notations,
+        vec![("release".to_owned(), "v1.2=final".to_owned())]
+    );
+    assert!(metadata.created > 0);
+    assert!(KdtSignageHandler::verify(&msg, &keyset.pubkey_pair));
+
+    // Altering any of the metadata breaks the signature.
+    let mut forged = KdtSignedMessage::parse(&signed).unwrap();
+    forged.metadata.as_mut().unwrap().created -= 3600;
+    assert!(!KdtSignageHandler::verify(&forged, &keyset.pubkey_pair));
+    let mut forged = KdtSignedMessage::parse(&signed).unwrap();
+    forged
+        .metadata
+        .as_mut()
+        .unwrap()
+        .notations
+        .clear();
+    assert!(!KdtSignageHandler::verify(&forged, &keyset.pubkey_pair));
+    let mut forged = KdtSignedMessage::parse(&signed).unwrap();
+    forged.metadata = None;
+    assert!(!KdtSignageHandler::verify(&forged, &keyset.pubkey_pair));
+
+    // Detached signatures carry the same metadata.
+    let signature = KdtSignageHandler::sign_bytes(b"data", &keyset);
+    let signature = KdtDetachedSignature::parse(&signature.to_string()).unwrap();
+    assert_eq!(
+        signature.metadata.as_ref().unwrap().signer_id,
+        keyset.pubkey_pair.id
+    );
+    assert!(KdtSignageHandler::verify_bytes(
+        b"data",
+        &signature,
+        &keyset.pubkey_pair
+    ));
+}
+
+#[test]
+fn notation_keys_can_contain_equals_signs() {
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    let options = KdtSignOptions::new().with_notation("release=v1.2", "final=yes");
+    let signed =
+        KdtSignageHandler::sign_text_with_options("text".into(), &keyset, &options).unwrap();
+
+    let msg = KdtSignedMessage::parse(&signed).unwrap();
+    assert_eq!(
+        msg.metadata.clone().unwrap().notations,
+        vec![("release=v1.2".to_owned(), "final=yes".to_owned())]
+    );
+    assert!(KdtSignageHandler::verify(&msg, &keyset.pubkey_pair));
+}
+
+#[test]
+fn prehashed_signatures_cover_streamed_input() {
+    let data = (0..200_000u32)
+        .map(|i| i as u8)
+        .collect::<Vec<_>>();
+    let keyset = OwnedKeySet::generate("Test Key".into(), true);
+    let keyring = [keyset.pubkey_pair.clone()];
+
+    for hash in [KdtSignatureHash::Sha512, KdtSignatureHash::Shake256] {
+        let options = KdtSignOptions::new().with_hash(hash);
+        let signature =
+            KdtSignageHandler::sign_reader_with_options(&mut data.as_slice(), &keyset, &options)
+                .unwrap();
+        let signature = KdtDetachedSignature::parse(&signature.to_string()).unwrap();
+        assert_eq!(signature.hash(), hash);
+        assert!(KdtSignageHandler::verify_bytes(
+            &data,
+            &signature,
+            &keyset.pubkey_pair
+        ));
+        let signer = KdtSignageHandler::find_detached_signer_in_reader(
+            &mut data.as_slice(),
+            &signature,
+            &keyring,
+        )
+        .unwrap();
+        assert!(signer.is_some());
+        assert!(!KdtSignageHandler::verify_bytes(
+            &data[1..],
+            &signature,
+            &keyset.pubkey_pair
+        ));
+
+        // The prehash is part of the signed metadata.
+        let mut forged = KdtDetachedSignature::parse(&signature.to_string()).unwrap();
+        forged.metadata.as_mut().unwrap().hash = KdtSignatureHash::Pure;
+        assert!(!KdtSignageHandler::verify_bytes(
+            &data,
+            &forged,
+            &keyset.pubkey_pair
+        ));
+    }
+
+    // Signed messages can be prehashed too.
+    let options = KdtSignOptions::new().with_hash(KdtSignatureHash::Shake256);
+    let signed =
+        KdtSignageHandler::sign_text_with_options("text".into(), &keyset, &options).unwrap();
+    let msg = KdtSignedMessage::parse(&signed).unwrap();
+    assert_eq!(msg.hash(), KdtSignatureHash::Shake256);
+    assert!(KdtSignageHandler::verify(&msg, &keyset.pubkey_pair));
+}
+
+#[test]
+fn prehashes_are_domain_separated_digests() {
+    let sha512 = KdtSignatureHash::Sha512.represent(b"abc");
+    assert_eq!(&sha512[..PREHASH_DOMAIN.len()], PREHASH_DOMAIN);
+    assert_eq!(sha512[PREHASH_DOMAIN.len()], KdtSignatureHash::Sha512.id());
+    assert_eq!(
+        Base64::encode_bytes(&sha512[PREHASH_DOMAIN.len() + 1..]),
+        "3a81oZNherrMQXNJriBBMRLm+k6JqX6iCp7u5ktV05ohkpkqJ0/BqDa6PCOj/uu9RU1EI2Q86A4qmslPpUyknw=="
+    );
+    let shake256 = KdtSignatureHash::Shake256.represent(b"");
+    assert_eq!(
+        Base64::encode_bytes(&shake256[PREHASH_DOMAIN.len() + 1..]),
+        "RrndKwuojRMjOz/rdD7rJD/NUupiuBuCtQwnZG7Vdi/XXcTd2MDyAMsFAZ1ntZL2/IIcSUeatIZAKS6ss7fEvg=="
+    );
+    assert_eq!(KdtSignatureHash::Pure.represent(b"abc"), b"abc");
+}
+
+#[test]
+fn ml_dsa_binds_contexts_and_prehashes_as_hash_ml_dsa() {
+    let data = b"release tarball".to_vec();
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    let public_key = &keyset.pubkey_pair.signage_key;
+
+    // Pure signatures take the context string as their FIPS 204 one.
+    let options = KdtSignOptions::new().with_context("release");
+    let signature = KdtSignageHandler::sign_bytes_with_options(&data, &keyset, &options).unwrap();
+    let metadata = signature.metadata.as_ref().unwrap();
+    assert_eq!(metadata.context, b"release");
+    assert!(metadata.to_string().ends_with("Context: release"));
+    let signature = KdtDetachedSignature::parse(&signature.to_string()).unwrap();
+    assert!(KdtSignageHandler::verify_bytes(
+        &data,
+        &signature,
+        &keyset.pubkey_pair
+    ));
+    let signed_data = metadata.signed_data(&data);
+    let verify = |ctx: &[u8]| {
+        pqc_dilithium::verify_ctx(
+            DilithiumMode::MlDsa65,
+            &signature.signature,
+            &signed_data,
+            ctx,
+            public_key,
         )
     };
+    assert!(verify(b"release").is_ok());
+    assert!(verify(b"").is_err());
+    let mut forged = signature.clone();
+    forged.metadata.as_mut().unwrap().context = b"debug".to_vec();
+    assert!(!KdtSignageHandler::verify_bytes(
+        &data,
+        &forged,
+        &keyset.pubkey_pair
+    ));
 
-    let signed_text = KdtSignageHandler::sign_text(text, keypair);
-    let msg = KdtSignedMessage::from_str(signed_text);
-    let signature_validity = KdtSignageHandler::verify(msg, keypair.public.to_vec());
-    assert!(signature_validity);
+    // Prehashed ones are HashML-DSA signatures over the digest of the
+    // metadata and the message.
+    for (hash, pre_hash) in [
+        (KdtSignatureHash::Sha512, PreHash::Sha2_512),
+        (KdtSignatureHash::Shake256, PreHash::Shake256),
+    ] {
+        let options = options.clone().with_hash(hash);
+        let signature =
+            KdtSignageHandler::sign_reader_with_options(&mut &data[..], &keyset, &options).unwrap();
+        assert!(KdtSignageHandler::verify_bytes(
+            &data,
+            &signature,
+            &keyset.pubkey_pair
+        ));
+        let checks = KdtSignageHandler::check_detached_signatures_in_reader(
+            &mut &data[..],
+            &signature,
+            std::slice::from_ref(&keyset.pubkey_pair),
+        )
+        .unwrap();
+        assert!(matches!(checks[0].status, KdtSignerStatus::Valid(_)));
+        assert!(!KdtSignageHandler::verify_bytes(
+            b"forged",
+            &signature,
+            &keyset.pubkey_pair
+        ));
+
+        let metadata = signature.metadata.as_ref().unwrap();
+        let mut prehasher = KdtPrehasher::new(hash).unwrap();
+        prehasher.update(&metadata.signed_prefix());
+        prehasher.update(&data);
+        let (_, digest) = prehasher.digest();
+        assert!(pqc_dilithium::verify_prehashed(
+            DilithiumMode::MlDsa65,
+            &signature.signature,
+            &digest,
+            pre_hash,
+            b"release",
+            public_key
+        )
+        .is_ok());
+    }
+
+    // Round 3 Dilithium has no context strings, but the metadata still
+    // binds it, and its prehashes stay KDT's own.
+    let legacy = OwnedKeySet::generate_with_options(
+        "Legacy Key".into(),
+        KdtKeyOptions::new().with_dilithium_level(KdtDilithiumLevel::Dilithium3),
+    );
+    let options = options.with_hash(KdtSignatureHash::Sha512);
+    let signature = KdtSignageHandler::sign_bytes_with_options(&data, &legacy, &options).unwrap();
+    assert!(KdtSignageHandler::verify_bytes(
+        &data,
+        &signature,
+        &legacy.pubkey_pair
+    ));
+    let mut forged = signature.clone();
+    forged.metadata.as_mut().unwrap().context = Vec::new();
+    assert!(!KdtSignageHandler::verify_bytes(
+        &data,
+        &forged,
+        &legacy.pubkey_pair
+    ));
+}
+
+#[test]
+fn oversized_contexts_are_refused() {
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    let options = KdtSignOptions::new().with_context(vec![0u8; MAX_CONTEXT_SIZE + 1]);
+
+    let err = KdtSignageHandler::sign_bytes_with_options(b"text", &keyset, &options)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.downcast_ref::<KdtErr>(),
+        Some(KdtErr::ContextTooLong)
+    ));
+}
+
+#[test]
+fn cosignatures_are_checked_one_by_one() {
+    let alice = OwnedKeySet::generate("Alice".into(), false);
+    let bob = OwnedKeySet::generate("Bob".into(), true);
+    let carol = OwnedKeySet::generate("Carol".into(), false);
+    let keyring = [alice.pubkey_pair.clone(), bob.pubkey_pair.clone()];
+    let shake256 = KdtSignOptions::new().with_hash(KdtSignatureHash::Shake256);
+
+    let signed = KdtSignageHandler::sign_text("max_connections = 64".into(), &alice);
+    let signed = KdtSignageHandler::cosign_text(&signed, &bob, &KdtSignOptions::new()).unwrap();
+    let signed = KdtSignageHandler::cosign_text(&signed, &carol, &shake256).unwrap();
+    assert!(KdtSignageHandler::cosign_text(&signed, &alice, &KdtSignOptions::new()).is_err());
+
+    let msg = KdtSignedMessage::parse(&signed).unwrap();
+    assert_eq!(msg.cosignatures.len(), 2);
+    // Co-signing leaves the first signature as it was.
+    assert!(KdtSignageHandler::verify(&msg, &alice.pubkey_pair));
+    let checks = KdtSignageHandler::check_signatures(&msg, &keyring);
+    let signers = checks
+        .iter()
+        .map(|c| match &c.status {
+            KdtSignerStatus::Valid(key) => Some(key.owner.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(signers, [Some("Alice".into()), Some("Bob".into()), None]);
+    assert_eq!(
+        checks[2].metadata.as_ref().unwrap().signer_id,
+        carol.pubkey_pair.id
+    );
+
+    // Breaking one co-signature doesn't affect the others.
+    let mut broken = KdtSignedMessage::parse(&signed).unwrap();
+    broken.cosignatures[0].signature[0] ^= 1;
+    let checks = KdtSignageHandler::check_signatures(&broken, &keyring);
+    assert!(matches!(checks[0].status, KdtSignerStatus::Valid(_)));
+    assert!(matches!(checks[1].status, KdtSignerStatus::Unverified));
+
+    // Detached signatures can be co-signed too, whatever each signer
+    // hashes the data with.
+    let data = b"release.tar.gz contents".to_vec();
+    let signature = KdtSignageHandler::sign_bytes(&data, &alice);
+    let signature =
+        KdtSignageHandler::cosign_reader(&mut data.as_slice(), signature, &bob, &shake256).unwrap();
+    let signature = KdtDetachedSignature::parse(&signature.to_string()).unwrap();
+    let checks = KdtSignageHandler::check_detached_signatures_in_reader(
+        &mut data.as_slice(),
+        &signature,
+        &keyring,
+    )
+    .unwrap();
+    assert_eq!(checks.len(), 2);
+    assert!(checks
+        .iter()
+        .all(|c| matches!(c.status, KdtSignerStatus::Valid(_))));
+}
+
+#[test]
+fn signing_policies_count_distinct_valid_signers() {
+    let keysets =
+        ["Alice", "Bob", "Carol", "Dave"].map(|name| OwnedKeySet::generate(name.into(), false));
+    let ids = keysets
+        .iter()
+        .map(|k| k.pubkey_pair.id.clone())
+        .collect::<Vec<_>>();
+    // Dave's key isn't in the keyring, so his signature can't count.
+    let keyring = keysets[..3]
+        .iter()
+        .map(|k| k.pubkey_pair.clone())
+        .collect::<Vec<_>>();
+    let policy = KdtSignPolicy::parse(&format!(
+        "(threshold: 2, signers: [{}])",
+        ids.iter()
+            .map(|id| format!("{:?}", id))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+    .unwrap();
+
+    let signed = KdtSignageHandler::sign_text("version = 2".into(), &keysets[0]);
+    let checks =
+        KdtSignageHandler::check_signatures(&KdtSignedMessage::parse(&signed).unwrap(), &keyring);
+    let verdict = policy.evaluate(&checks);
+    assert!(!verdict.is_satisfied());
+    assert_eq!(verdict.signed, ids[..1]);
+    assert_eq!(verdict.missing, ids[1..]);
+
+    let signed =
+        KdtSignageHandler::cosign_text(&signed, &keysets[3], &KdtSignOptions::new()).unwrap();
+    let checks =
+        KdtSignageHandler::check_signatures(&KdtSignedMessage::parse(&signed).unwrap(), &keyring);
+    assert!(!policy.evaluate(&checks).is_satisfied());
+
+    let signed =
+        KdtSignageHandler::cosign_text(&signed, &keysets[2], &KdtSignOptions::new()).unwrap();
+    let checks =
+        KdtSignageHandler::check_signatures(&KdtSignedMessage::parse(&signed).unwrap(), &keyring);
+    let verdict = policy.evaluate(&checks);
+    assert!(verdict.is_satisfied());
+    assert_eq!(verdict.missing, [ids[1].clone(), ids[3].clone()]);
+
+    // Policies that can never be satisfied are rejected.
+    assert!(KdtSignPolicy::parse("(threshold: 0, signers: [\"A\"])").is_err());
+    assert!(KdtSignPolicy::parse("(threshold: 2, signers: [\"A\", \"A\"])").is_err());
+    assert!(KdtSignPolicy::parse("threshold = 2").is_err());
+}
+
+#[test]
+fn signers_are_found_in_the_keyring() {
+    let keysets = [
+        OwnedKeySet::generate("Alice".into(), false),
+        OwnedKeySet::generate("Bob".into(), true),
+    ];
+    let keyring = keysets
+        .iter()
+        .map(|k| k.pubkey_pair.clone())
+        .collect::<Vec<_>>();
+
+    for keyset in &keysets {
+        let signed = KdtSignageHandler::sign_text("text".into(), keyset);
+        let msg = KdtSignedMessage::parse(&signed).unwrap();
+        let signer = KdtSignageHandler::find_signer(&msg, &keyring).unwrap();
+        assert_eq!(signer.id, keyset.pubkey_pair.id);
+
+        let signature = KdtSignageHandler::sign_bytes(b"data", keyset);
+        let signer =
+            KdtSignageHandler::find_detached_signer(b"data", &signature, &keyring).unwrap();
+        assert_eq!(signer.id, keyset.pubkey_pair.id);
+        assert!(KdtSignageHandler::find_detached_signer(b"other", &signature, &keyring).is_none());
+
+        // Without the signer's key, there's no one to find.
+        let others = keyring
+            .iter()
+            .filter(|k| k.id != keyset.pubkey_pair.id)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert!(KdtSignageHandler::find_signer(&msg, &others).is_none());
+    }
+}
+
+#[test]
+fn signature_metadata_dates_are_utc() {
+    let metadata = KdtSignatureMetadata {
+        created: 951782400,
+        signer_id: String::new(),
+        hash: KdtSignatureHash::Pure,
+        notations: Vec::new(),
+        context: Vec::new(),
+    };
+    assert_eq!(metadata.created_utc(), "2000-02-29 00:00:00 UTC");
+    let metadata = KdtSignatureMetadata {
+        created: 1700000000,
+        ..metadata
+    };
+    assert_eq!(metadata.created_utc(), "2023-11-14 22:13:20 UTC");
+}
+
+#[test]
+fn tampered_header_fails_to_decrypt() {
+    let data = b"This is a test message".to_vec();
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    let encrypted =
+        KdtCryptoHandler::encrypt_bytes(&data, vec![keyset.pubkey_pair.clone()]).unwrap();
+
+    let mut message = KdtEncryptedMessage::from_str(encrypted.to_string());
+    message.header.version += 1;
+    assert!(KdtCryptoHandler::decrypt_bytes(message, &keyset).is_err());
+
+    // The wrapped key is bound to the recipient's key id as well.
+    let mut renamed = keyset.clone();
+    renamed.pubkey_pair.id = OwnedKeySet::generate("Other Key".into(), false)
+        .pubkey_pair
+        .id;
+    let message = KdtEncryptedMessage::from_str(encrypted.to_string());
+    assert!(KdtCryptoHandler::decrypt_bytes(message, &renamed).is_err());
+}
+
+#[test]
+fn legacy_messages_still_decrypt() {
+    let secret_message = String::from("This is a test message");
+    // KDT 0.1 only ever made Kyber-1024 keys.
+    let keyset = OwnedKeySet::generate_with_options(
+        "Test Key".into(),
+        KdtKeyOptions::new().with_kyber_level(KdtKyberLevel::Kyber1024),
+    );
+    // Encrypted the way KDT 0.1 did it: the Kyber shared secret is the
+    // AES key, and the message is `secret*message*nonce`.
+    let (encrypted_secret, shared_secret) = pqc_kyber::encapsulate(
+        pqc_kyber::KyberLevel::Kyber1024,
+        &keyset.pubkey_pair.crypto_key,
+        &mut rand::thread_rng(),
+    )
+    .unwrap();
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&shared_secret));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, secret_message.as_bytes())
+        .unwrap();
+    let legacy = format!(
+        "-----BEGIN KDT MESSAGE-----\n{}*{}*{}\n-----END KDT MESSAGE-----",
+        Base64::encode_bytes(&encrypted_secret),
+        Base64::encode_bytes(&ciphertext),
+        Base64::encode_bytes(&nonce)
+    );
+
+    let message = KdtEncryptedMessage::from_str(legacy);
+    assert!(message.header.is_legacy());
+    assert_eq!(
+        KdtCryptoHandler::decrypt_msg(message, &keyset).unwrap(),
+        secret_message
+    );
+}
+
+#[test]
+fn keys_keep_their_ids_in_containers() {
+    let keyset = OwnedKeySet::generate("Test Key".into(), true);
+    let pubkey = keyset.pubkey_pair.to_string();
+    assert!(!pubkey.contains('*'));
+
+    let imported = PubKeyPair::parse(&pubkey).unwrap().init();
+    assert_eq!(imported.id, keyset.pubkey_pair.id);
+    assert_eq!(
+        imported.hybrid_signage_key,
+        keyset.pubkey_pair.hybrid_signage_key
+    );
+    let restored = PrivKeyPair::parse(&keyset.privkey_pair.to_string())
+        .unwrap()
+        .init();
+    assert_eq!(restored.id, keyset.privkey_pair.id);
+
+    // Keys exported by KDT 0.1 still import, under the same id.
+    let keyset = OwnedKeySet::generate_with_options(
+        "Test Key".into(),
+        KdtKeyOptions::new()
+            .with_hybrid(true)
+            .with_kyber_level(KdtKyberLevel::Kyber1024)
+            .with_dilithium_level(KdtDilithiumLevel::Dilithium3),
+    );
+    let legacy = keyset
+        .pubkey_pair
+        .material()
+        .to_legacy_armor(PUBKEY_ARMOR_LABEL);
+    assert_eq!(
+        PubKeyPair::parse(&legacy).unwrap().init().id,
+        keyset.pubkey_pair.id
+    );
+}
+
+#[test]
+fn key_ids_bind_the_kem() {
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+
+    // The same bytes as a round 3 Kyber-1024 key make a different key.
+    let mut kyber = keyset.pubkey_pair.clone();
+    kyber.kyber_level = KdtKyberLevel::Kyber1024;
+    kyber.dilithium_level = KdtDilithiumLevel::Dilithium3;
+    let kyber = kyber.init();
+    let mut ml_kem = kyber.clone();
+    ml_kem.kyber_level = KdtKyberLevel::MlKem1024;
+    let ml_kem = ml_kem.init();
+    assert_ne!(ml_kem.id, kyber.id);
+
+    // Only keys that could've been made before the levels keep the hash
+    // of their KDT 0.1 armor as their id.
+    let mut hasher = Sha256::new();
+    hasher.update(
+        kyber
+            .material()
+            .to_legacy_armor(PUBKEY_ARMOR_LABEL),
+    );
+    assert_eq!(kyber.id, format!("{:X}", hasher.finalize()));
+}
+
+#[test]
+fn key_ids_bind_the_signature_algorithm() {
+    let keyset = OwnedKeySet::generate_with_options(
+        "Test Key".into(),
+        KdtKeyOptions::new().with_kyber_level(KdtKyberLevel::Kyber1024),
+    );
+    assert_eq!(
+        keyset.pubkey_pair.dilithium_level,
+        KdtDilithiumLevel::MlDsa65
+    );
+
+    // The same bytes as a round 3 Dilithium3 key make a different key,
+    // which its signatures don't verify against anyway.
+    let mut dilithium = keyset.pubkey_pair.clone();
+    dilithium.dilithium_level = KdtDilithiumLevel::Dilithium3;
+    let dilithium = dilithium.init();
+    assert_ne!(dilithium.id, keyset.pubkey_pair.id);
+
+    let msg = KdtSignedMessage::from_str(KdtSignageHandler::sign_text("hello".into(), &keyset));
+    assert!(KdtSignageHandler::find_signer(&msg, &[dilithium]).is_none());
+}
+
+#[test]
+fn key_ids_bind_the_slh_dsa_level() {
+    let keyset = OwnedKeySet::generate_with_options(
+        "Archive Key".into(),
+        KdtKeyOptions::new()
+            .with_kyber_level(KdtKyberLevel::Kyber1024)
+            .with_dilithium_level(KdtDilithiumLevel::Dilithium3)
+            .with_slh_dsa_level(Some(KdtSlhDsaLevel::Shake128f)),
+    );
+    let pubkey = PubKeyPair::from_str(keyset.pubkey_pair.to_string()).init();
+    assert_eq!(pubkey.id, keyset.pubkey_pair.id);
+
+    // SHAKE-128s keys are just as long, but sign differently.
+    let mut small = pubkey.clone();
+    small.slh_dsa_level = Some(KdtSlhDsaLevel::Shake128s);
+    assert_ne!(small.init().id, pubkey.id);
+
+    // Nor do they pass for a legacy key with the same bytes.
+    let mut dilithium = pubkey.clone();
+    dilithium.slh_dsa_level = None;
+    assert_ne!(dilithium.init().id, pubkey.id);
+}
+
+#[test]
+fn unknown_records_are_skipped() {
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    let bytes = Base64::try_decode_string(Armor::unwrap(&keyset.pubkey_pair.to_string())).unwrap();
+    let mut container = KdtContainer::from_bytes(&bytes, KdtContainerKind::PubKey).unwrap();
+    container
+        .records
+        .push(200, b"from the future".to_vec());
+    let armored = Armor::wrap(
+        PUBKEY_ARMOR_LABEL,
+        &Base64::encode_bytes(&container.to_bytes()),
+    );
+
+    assert_eq!(
+        PubKeyPair::parse(&armored).unwrap().init().id,
+        keyset.pubkey_pair.id
+    );
+}
+
+#[test]
+fn messages_authenticate_unknown_records() {
+    // Cheap parameters, so the test doesn't spend seconds in Argon2.
+    let params = KdtPassphraseParams::new(vec![42u8; PASSPHRASE_SALT_SIZE], 256, 1, 1);
+    let mut key = vec![0u8; 32];
+    Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        Params::new(256, 1, 1, Some(32)).unwrap(),
+    )
+    .hash_password_into(b"correct horse", &params.salt, &mut key)
+    .unwrap();
+
+    // What a newer KDT would write: a record this one doesn't know, in
+    // front of the others and authenticated along with them.
+    let aead = KdtAead::default();
+    let nonce = aead.generate_nonce();
+    let message = KdtEncryptedMessage::with_passphrase(params, Vec::new(), nonce.clone());
+    let mut records = KdtRecords::new();
+    records.push(200, b"from the future".to_vec());
+    records.0.extend(
+        message
+            .to_container()
+            .records
+            .without(MESSAGE_CIPHERTEXT)
+            .0,
+    );
+    let mut container = KdtContainer::new(KdtContainerKind::Message, records);
+    let ciphertext = aead.seal(&key, &nonce, b"hello", &container.to_bytes());
+    container
+        .records
+        .push(MESSAGE_CIPHERTEXT, ciphertext);
+    let armored = Armor::wrap(
+        MESSAGE_ARMOR_LABEL,
+        &Base64::encode_bytes(&container.to_bytes()),
+    );
+
+    let message = KdtEncryptedMessage::parse(&armored).unwrap();
+    let decrypted = KdtCryptoHandler::decrypt_bytes_with_passphrase(message, "correct horse");
+    assert_eq!(decrypted.unwrap(), b"hello");
+}
+
+#[test]
+fn stream_headers_authenticate_unknown_records() {
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    let mut encrypted = Vec::new();
+    KdtCryptoHandler::encrypt_stream(
+        &mut &b"hello"[..],
+        &mut encrypted,
+        vec![keyset.pubkey_pair.clone()],
+    )
+    .unwrap();
+    let header = KdtStreamHeader::read_from(&mut encrypted.as_slice()).unwrap();
+
+    // The same header as a newer KDT would write it, with a record this
+    // one doesn't know in front of the others.
+    let mut records = KdtRecords::new();
+    records.push(200, b"from the future".to_vec());
+    records.0.extend(header.to_container().records.0);
+    let bytes = KdtContainer::new(KdtContainerKind::Stream, records).to_bytes();
+    let (prefix, records) = bytes.split_at(CONTAINER_MAGIC.len() + 2);
+    let written = [prefix, &(records.len() as u32).to_be_bytes(), records].concat();
+
+    let future = KdtStreamHeader::read_from(&mut written.as_slice()).unwrap();
+    assert_eq!(future.associated_data(), Sha256::digest(&bytes).to_vec());
+    assert_ne!(future.associated_data(), header.associated_data());
+}
+
+#[test]
+fn unsupported_versions_are_rejected() {
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    let encrypted =
+        KdtCryptoHandler::encrypt_text("This is a test message".into(), keyset.pubkey_pair)
+            .unwrap();
+    let mut container = encrypted.to_container();
+    container.version += 1;
+    let armored = Armor::wrap(
+        MESSAGE_ARMOR_LABEL,
+        &Base64::encode_bytes(&container.to_bytes()),
+    );
+
+    let err = KdtEncryptedMessage::parse(&armored)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.downcast_ref::<KdtErr>(),
+        Some(KdtErr::UnsupportedVersion(3))
+    ));
+}
+
+#[test]
+fn decryption_picks_the_right_keyset() {
+    let data = b"This is a test message".to_vec();
+    let keysets: Vec<_> = (0..3)
+        .map(|_| OwnedKeySet::generate("Test Key".into(), false))
+        .collect();
+    let encrypted =
+        KdtCryptoHandler::encrypt_bytes(&data, vec![keysets[2].pubkey_pair.clone()]).unwrap();
+
+    let message = KdtEncryptedMessage::from_str(encrypted.to_string());
+    assert_eq!(
+        message.recipients[0].key_id.as_ref(),
+        Some(&keysets[2].pubkey_pair.id)
+    );
+    assert_eq!(
+        KdtCryptoHandler::decrypt_bytes_with_keysets(message, &keysets).unwrap(),
+        data
+    );
+    let message = KdtEncryptedMessage::from_str(encrypted.to_string());
+    assert!(KdtCryptoHandler::decrypt_bytes_with_keysets(message, &keysets[..2]).is_err());
+}
+
+#[test]
+fn anonymous_recipients_are_found_by_trial_decryption() {
+    let data = b"This is a test message".to_vec();
+    let keysets: Vec<_> = (0..3)
+        .map(|i| OwnedKeySet::generate("Test Key".into(), i == 1))
+        .collect();
+    let options = KdtEncryptOptions::new().with_anonymous(true);
+    let encrypted = KdtCryptoHandler::encrypt_bytes_with_options(
+        &data,
+        vec![keysets[1].pubkey_pair.clone()],
+        options,
+    )
+    .unwrap();
+    let armored = encrypted.to_string();
+
+    let message = KdtEncryptedMessage::from_str(armored.clone());
+    assert!(message
+        .recipients
+        .iter()
+        .all(|r| r.key_id.is_none()));
+    assert_eq!(
+        KdtCryptoHandler::decrypt_bytes_with_keysets(message, &keysets).unwrap(),
+        data
+    );
+
+    // Every other key cleanly reports that the message isn't for it.
+    let message = KdtEncryptedMessage::from_str(armored);
+    let err = KdtCryptoHandler::decrypt_bytes_with_keysets(
+        message,
+        &[keysets[0].clone(), keysets[2].clone()],
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(
+        err.downcast_ref::<KdtErr>(),
+        Some(KdtErr::NotForThisKey)
+    ));
+}
+
+#[test]
+fn chacha_round_trips() {
+    let data = vec![7u8; STREAM_CHUNK_SIZE + 3];
+    let keyset = OwnedKeySet::generate("Test Key".into(), true);
+    for aead in [KdtAead::ChaCha20Poly1305, KdtAead::XChaCha20Poly1305] {
+        let options = KdtEncryptOptions::new().with_aead(aead);
+        let encrypted = KdtCryptoHandler::encrypt_bytes_with_options(
+            &data,
+            vec![keyset.pubkey_pair.clone()],
+            options,
+        )
+        .unwrap();
+        let message = KdtEncryptedMessage::from_str(encrypted.to_string());
+        assert_eq!(message.header.aead, aead);
+        assert_eq!(message.nonce.len(), aead.nonce_size());
+        assert_eq!(
+            KdtCryptoHandler::decrypt_bytes(message, &keyset).unwrap(),
+            data
+        );
+
+        let mut stream = Vec::new();
+        KdtCryptoHandler::encrypt_stream_with_options(
+            &mut data.as_slice(),
+            &mut stream,
+            vec![keyset.pubkey_pair.clone()],
+            options,
+        )
+        .unwrap();
+        let mut decrypted = Vec::new();
+        KdtCryptoHandler::decrypt_stream(&mut stream.as_slice(), &mut decrypted, &keyset).unwrap();
+        assert_eq!(decrypted, data);
+    }
+}
+
+#[test]
+fn kyber_levels_interoperate() {
+    let secret_message = String::from("This is a test message");
+    let keysets = [
+        KdtKyberLevel::Kyber512,
+        KdtKyberLevel::Kyber768,
+        KdtKyberLevel::Kyber1024,
+        KdtKyberLevel::MlKem512,
+        KdtKyberLevel::MlKem768,
+        KdtKyberLevel::MlKem1024,
+    ]
+    .map(|level| {
+        OwnedKeySet::generate_with_options(
+            "Test Key".into(),
+            KdtKeyOptions::new()
+                .with_hybrid(matches!(
+                    level,
+                    KdtKyberLevel::Kyber768 | KdtKyberLevel::MlKem768
+                ))
+                .with_kyber_level(level),
+        )
+    });
+    let encrypted = KdtCryptoHandler::encrypt_bytes_with_options(
+        secret_message.as_bytes(),
+        keysets
+            .iter()
+            .map(|k| k.pubkey_pair.clone())
+            .collect(),
+        KdtEncryptOptions::new().with_anonymous(true),
+    )
+    .unwrap();
+    let message = KdtEncryptedMessage::from_str(encrypted.to_string());
+    assert_eq!(
+        message
+            .recipients
+            .iter()
+            .map(|r| r.kem)
+            .collect::<Vec<_>>(),
+        vec![
+            KdtKem::Kyber(KdtKyberLevel::Kyber512),
+            KdtKem::KyberX25519(KdtKyberLevel::Kyber768),
+            KdtKem::Kyber(KdtKyberLevel::Kyber1024),
+            KdtKem::Kyber(KdtKyberLevel::MlKem512),
+            KdtKem::KyberX25519(KdtKyberLevel::MlKem768),
+            KdtKem::Kyber(KdtKyberLevel::MlKem1024),
+        ]
+    );
+
+    for keyset in &keysets {
+        // The level survives a round trip through the armored keys.
+        let pubkey = PubKeyPair::from_str(keyset.pubkey_pair.to_string()).init();
+        let privkey = PrivKeyPair::from_str(keyset.privkey_pair.to_string()).init();
+        assert_eq!(pubkey.kyber_level, keyset.pubkey_pair.kyber_level);
+        assert_eq!(privkey.kyber_level, keyset.privkey_pair.kyber_level);
+        assert_eq!(pubkey.id, keyset.pubkey_pair.id);
+
+        let message = KdtEncryptedMessage::from_str(encrypted.to_string());
+        let decrypted = KdtCryptoHandler::decrypt_bytes(message, keyset).unwrap();
+        assert_eq!(decrypted, secret_message.as_bytes());
+    }
+}
+
+#[test]
+fn dilithium_levels_verify_side_by_side() {
+    let text = String::from("This is a test message");
+    let keysets = [
+        KdtDilithiumLevel::Dilithium2,
+        KdtDilithiumLevel::Dilithium3,
+        KdtDilithiumLevel::Dilithium5,
+        KdtDilithiumLevel::MlDsa44,
+        KdtDilithiumLevel::MlDsa65,
+        KdtDilithiumLevel::MlDsa87,
+    ]
+    .map(|level| {
+        OwnedKeySet::generate_with_options(
+            "Test Key".into(),
+            KdtKeyOptions::new()
+                .with_hybrid(matches!(
+                    level,
+                    KdtDilithiumLevel::Dilithium5 | KdtDilithiumLevel::MlDsa87
+                ))
+                .with_dilithium_level(level),
+        )
+    });
+    let pubkeys: Vec<PubKeyPair> = keysets
+        .iter()
+        .map(|k| PubKeyPair::from_str(k.pubkey_pair.to_string()).init())
+        .collect();
+
+    for (keyset, pubkey) in keysets.iter().zip(&pubkeys) {
+        // The level survives a round trip through the armored keys.
+        assert_eq!(pubkey.dilithium_level, keyset.pubkey_pair.dilithium_level);
+        assert_eq!(pubkey.id, keyset.pubkey_pair.id);
+
+        let signed = KdtSignageHandler::sign_text(text.clone(), keyset);
+        let msg = KdtSignedMessage::from_str(signed);
+        assert_eq!(
+            msg.algorithm.dilithium_level(),
+            Some(keyset.privkey_pair.dilithium_level)
+        );
+        let signer = KdtSignageHandler::find_signer(&msg, &pubkeys).unwrap();
+        assert_eq!(signer.id, pubkey.id);
+    }
+}
+
+#[test]
+fn new_key_sets_sign_with_ml_dsa() {
+    let text = String::from("This is a test message");
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    assert_eq!(
+        keyset.privkey_pair.dilithium_level,
+        KdtDilithiumLevel::MlDsa65
+    );
+
+    let msg = KdtSignedMessage::from_str(KdtSignageHandler::sign_text(text.clone(), &keyset));
+    assert_eq!(msg.algorithm.to_string(), "ml-dsa-65");
+    assert!(KdtSignageHandler::verify(&msg, &keyset.pubkey_pair));
+
+    // They're FIPS 204 signatures with an empty context string.
+    let signed_data = msg
+        .metadata
+        .as_ref()
+        .unwrap()
+        .signed_data(text.as_bytes());
+    assert!(pqc_dilithium::verify_ctx(
+        DilithiumMode::MlDsa65,
+        &msg.signature,
+        &signed_data,
+        &[],
+        &keyset.pubkey_pair.signage_key
+    )
+    .is_ok());
+
+    // Round 3 Dilithium3 doesn't accept them, despite the equal lattice.
+    let mut legacy = KdtSignedMessage::from_str(msg.to_string());
+    legacy.algorithm = KdtSignatureAlgorithm::Dilithium(KdtDilithiumLevel::Dilithium3);
+    assert!(!KdtSignageHandler::verify(&legacy, &keyset.pubkey_pair));
+
+    for id in 1..=12 {
+        let algorithm = KdtSignatureAlgorithm::from_id(id).unwrap();
+        assert_eq!(algorithm.id(), id);
+        let level = algorithm.dilithium_level().unwrap();
+        assert_eq!(
+            KdtDilithiumLevel::from_name(&level.to_string()),
+            Some(level)
+        );
+        assert_eq!(algorithm.slh_dsa_level(), None);
+    }
+}
+
+#[test]
+fn slh_dsa_key_sets_sign_and_verify() {
+    let text = String::from("This is a test message");
+    let data = b"\x00\xffarchived contract\n";
+    // The fast parameter set keeps the test quick, the others only differ
+    // in their sizes.
+    for hybrid in [false, true] {
+        let keyset = OwnedKeySet::generate_with_options(
+            "Archive Key".into(),
+            KdtKeyOptions::new()
+                .with_hybrid(hybrid)
+                .with_slh_dsa_level(Some(KdtSlhDsaLevel::Shake128f)),
+        );
+        let algorithm = keyset.pubkey_pair.signature_algorithm();
+        assert_eq!(algorithm.slh_dsa_level(), Some(KdtSlhDsaLevel::Shake128f));
+        assert_eq!(algorithm.dilithium_level(), None);
+        assert_eq!(algorithm.is_hybrid(), hybrid);
+
+        // Peers learn the algorithm from the exported public key.
+        let pubkey = PubKeyPair::from_str(keyset.pubkey_pair.to_string()).init();
+        assert_eq!(pubkey.slh_dsa_level, Some(KdtSlhDsaLevel::Shake128f));
+        assert_eq!(pubkey.signature_algorithm(), algorithm);
+        assert_eq!(pubkey.id, keyset.pubkey_pair.id);
+
+        let msg = KdtSignedMessage::from_str(KdtSignageHandler::sign_text(text.clone(), &keyset));
+        assert_eq!(msg.algorithm, algorithm);
+        assert!(msg
+            .algorithm
+            .to_string()
+            .starts_with("slh-dsa-shake-128f"));
+        assert!(KdtSignageHandler::verify(&msg, &pubkey));
+
+        let signature = KdtSignageHandler::sign_bytes(data, &keyset);
+        let signature = KdtDetachedSignature::parse(&signature.to_string()).unwrap();
+        assert_eq!(signature.algorithm, algorithm);
+        assert!(KdtSignageHandler::verify_bytes(data, &signature, &pubkey));
+        assert!(!KdtSignageHandler::verify_bytes(
+            b"forged", &signature, &pubkey
+        ));
+
+        // Unhedged signatures are reproducible, hedged ones aren't.
+        let level = KdtSlhDsaLevel::Shake128f;
+        let signage_key = &keyset.privkey_pair.signage_key;
+        assert_eq!(
+            level.sign(data, &[], signage_key, false),
+            level.sign(data, &[], signage_key, false)
+        );
+        assert_ne!(
+            level.sign(data, &[], signage_key, true),
+            level.sign(data, &[], signage_key, true)
+        );
+
+        // Nor does a Dilithium key set of the same owner accept them.
+        let other = OwnedKeySet::generate("Archive Key".into(), hybrid);
+        assert!(!KdtSignageHandler::verify(&msg, &other.pubkey_pair));
+    }
+
+    for id in 13..=24 {
+        let algorithm = KdtSignatureAlgorithm::from_id(id).unwrap();
+        assert_eq!(algorithm.id(), id);
+        assert_eq!(algorithm.dilithium_level(), None);
+        let level = algorithm.slh_dsa_level().unwrap();
+        assert_eq!(KdtSlhDsaLevel::from_name(&level.to_string()), Some(level));
+    }
+}
+
+#[test]
+fn detached_signatures_cover_raw_bytes() {
+    // Trailing whitespace and invalid UTF-8 have to be signed as-is.
+    let data = [b"\x00\xffrelease tarball\n\n".as_slice(), &[0x80; 64]].concat();
+    for keyset in [
+        OwnedKeySet::generate("Test Key".into(), false),
+        OwnedKeySet::generate("Hybrid Key".into(), true),
+    ] {
+        let signature = KdtSignageHandler::sign_bytes(&data, &keyset);
+        let armored = signature.to_string();
+        assert!(armored.starts_with("-----BEGIN KDT DETACHED SIGNATURE-----"));
+
+        let signature = KdtDetachedSignature::parse(&armored).unwrap();
+        assert!(KdtSignageHandler::verify_bytes(
+            &data,
+            &signature,
+            &keyset.pubkey_pair
+        ));
+
+        let mut tampered = data.clone();
+        tampered.pop();
+        assert!(!KdtSignageHandler::verify_bytes(
+            &tampered,
+            &signature,
+            &keyset.pubkey_pair
+        ));
+    }
+
+    // The signature block of a signed message isn't a detached signature.
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    let signed = KdtSignageHandler::sign_text("text".into(), &keyset);
+    let block = &signed[signed
+        .find("-----BEGIN KDT SIGNATURE-----")
+        .unwrap()..];
+    assert!(KdtDetachedSignature::parse(block).is_err());
+}
+
+#[test]
+fn new_key_sets_encrypt_with_ml_kem() {
+    let secret_message = String::from("This is a test message");
+    let keyset = OwnedKeySet::generate("Test Key".into(), false);
+    assert_eq!(keyset.pubkey_pair.kyber_level, KdtKyberLevel::MlKem1024);
+    assert_eq!(
+        keyset.pubkey_pair.kem(),
+        KdtKem::Kyber(KdtKyberLevel::MlKem1024)
+    );
+
+    // Peers learn the mechanism from the exported public key.
+    let pubkey = PubKeyPair::from_str(keyset.pubkey_pair.to_string()).init();
+    assert_eq!(pubkey.kem(), keyset.pubkey_pair.kem());
+    assert_eq!(pubkey.id, keyset.pubkey_pair.id);
+
+    let encrypted = KdtCryptoHandler::encrypt_bytes_with_options(
+        secret_message.as_bytes(),
+        vec![pubkey],
+        KdtEncryptOptions::new(),
+    )
+    .unwrap();
+    let message = KdtEncryptedMessage::from_str(encrypted.to_string());
+    assert_eq!(message.recipients[0].kem.to_string(), "ml-kem-1024");
+    let decrypted = KdtCryptoHandler::decrypt_bytes(message, &keyset).unwrap();
+    assert_eq!(decrypted, secret_message.as_bytes());
+
+    // A round 3 Kyber-1024 key of the same bytes can't make sense of it.
+    let mut legacy = keyset.clone();
+    legacy.pubkey_pair.kyber_level = KdtKyberLevel::Kyber1024;
+    legacy.privkey_pair.kyber_level = KdtKyberLevel::Kyber1024;
+    let message = KdtEncryptedMessage::from_str(encrypted.to_string());
+    assert!(KdtCryptoHandler::decrypt_bytes(message, &legacy).is_err());
+
+    for id in 1..=12 {
+        let kem = KdtKem::from_id(id).unwrap();
+        assert_eq!(kem.id(), id);
+        let level = kem.level();
+        assert_eq!(KdtKyberLevel::from_name(&level.to_string()), Some(level));
+    }
 }